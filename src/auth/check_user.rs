@@ -20,21 +20,29 @@ impl FromRequest for CheckLogin {
             Some(_) => {
                 let _split: Vec<&str> = _auth.unwrap().to_str().unwrap().split("Bearer").collect();
                 let token = _split[1].trim();
-                let _var = std::env::var("JWT_SECRET")
-                             .expect("Error retrieving jwt secret");
-                let key = _var.as_ref();
-                match decode::<MyClaims>(
-                    token,
-                    &DecodingKey::from_secret(key),
-                    &Validation { algorithms: vec![Algorithm::HS512], ..Default::default() },
-                ) {
-                    Ok(token) => {
-                        ok(CheckLogin { user_id: token.claims.sub })
-                    },
-                    Err(_e) => err(ErrorUnauthorized("invalid token!")),
+                match CheckLogin::from_token(token) {
+                    Ok(check) => ok(check),
+                    Err(e) => err(e),
                 }
             }
             None => err(ErrorUnauthorized("blocked!")),
         }
     }
+}
+
+impl CheckLogin {
+    // Also used for WebSockets, where browsers send the token as a protocol
+    pub fn from_token(token: &str) -> Result<CheckLogin, Error> {
+        let _var = std::env::var("JWT_SECRET")
+                     .expect("Error retrieving jwt secret");
+        let key = _var.as_ref();
+        match decode::<MyClaims>(
+            token,
+            &DecodingKey::from_secret(key),
+            &Validation { algorithms: vec![Algorithm::HS512], ..Default::default() },
+        ) {
+            Ok(token) => Ok(CheckLogin { user_id: token.claims.sub }),
+            Err(_e) => Err(ErrorUnauthorized("invalid token!")),
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,17 @@
+use crate::models::audit::{AuditEntry, AuditFilter};
+use crate::auth::check_user;
+use crate::MongoDb;
+
+use actix_web::{web, HttpResponse};
+
+pub async fn get_audit_log(db: web::Data<MongoDb>,
+                           query: web::Query<AuditFilter>,
+                           check: check_user::CheckLogin
+) -> HttpResponse {
+    let audit_filter = query.into_inner();
+
+    match AuditEntry::get_entries(audit_filter, check.user_id, &db).await {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
@@ -0,0 +1,25 @@
+use crate::models::currency::{ExchangeRate, RateUpdate};
+use crate::auth::check_user;
+use crate::MongoDb;
+
+use actix_web::{web, HttpResponse};
+
+pub async fn get_rates(db: web::Data<MongoDb>) -> HttpResponse {
+    match ExchangeRate::get_rates(&db).await {
+        Ok(rates) => HttpResponse::Ok().json(rates),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn set_rate(db: web::Data<MongoDb>,
+                      rate_json: web::Json<RateUpdate>,
+                      check: check_user::CheckLogin
+) -> HttpResponse {
+    let rate_update = rate_json.into_inner();
+    let admin_id = check.user_id;
+
+    match ExchangeRate::set_rate(rate_update, admin_id, &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
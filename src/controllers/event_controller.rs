@@ -1,10 +1,16 @@
 use crate::models::event::{Event, EventUpdate, EventFilter};
+use crate::models::favorite::{Favorite, FavoriteFilter};
+use crate::models::currency::{CurrencyQuery, resolve_currency};
+use crate::models::report::ModerationReason;
 use crate::utils::external_services::create_presgigned_url;
 use crate::auth::{check_user};
+use crate::utils::client_ip::client_ip;
+use crate::utils::etag::{etag, if_match, error_response};
 use crate::MongoDb;
 
 use log::debug;
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::header::ETAG;
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 
@@ -12,26 +18,113 @@ pub async fn create_event(db: web::Data<MongoDb>, event_json: web::Json<Event>,
                           _: check_user::CheckLogin) -> HttpResponse {
 
     let event = event_json.into_inner();
-    let event_id = Event::create(event, &db).await;
 
-    HttpResponse::Created().json(event_id)
+    match Event::create(event, &db).await {
+        Ok(event_id) => HttpResponse::Created().json(event_id),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
 }
 
-pub async fn get_event(db: web::Data<MongoDb>, event_json: web::Path<String>) -> HttpResponse {
+pub async fn get_event(db: web::Data<MongoDb>,
+                       event_json: web::Path<String>,
+                       currency_json: web::Query<CurrencyQuery>,
+                       check: Option<check_user::CheckLogin>
+) -> HttpResponse {
     let event_id = event_json.into_inner();
+    let user_id = check.map(|c| c.user_id);
+
+    let currency = match resolve_currency(currency_json.into_inner().currency, user_id.clone(), &db).await {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
 
     match Event::get_event(event_id, &db).await {
-        Ok(event) => HttpResponse::Ok().json(event),
+        Ok(event) if !event.visible_to(user_id.as_ref()) => HttpResponse::BadRequest().body("Event not found"),
+        Ok(event) => {
+            let mut events = vec![event];
+            if let Some(user_id) = user_id {
+                if let Err(e) = Favorite::mark_saved(&mut events, user_id, &db).await {
+                    return HttpResponse::BadRequest().body(e);
+                }
+            }
+            if let Some(currency) = currency {
+                if let Err(e) = Event::convert_prices(&mut events, currency, &db).await {
+                    return HttpResponse::BadRequest().body(e);
+                }
+            }
+            let event = events.remove(0);
+            HttpResponse::Ok().header(ETAG, etag(event.version)).json(event)
+        },
         Err(e) => HttpResponse::BadRequest().body(e),
     }
 }
 
-pub async fn get_events(db: web::Data<MongoDb>, event_json: web::Query<EventFilter>
+pub async fn get_events(db: web::Data<MongoDb>,
+                        event_json: web::Query<EventFilter>,
+                        check: Option<check_user::CheckLogin>
 ) -> HttpResponse {
-    let event_filter = event_json.into_inner();
-    let events = Event::get_filtered_events(event_filter, &db).await;
+    let mut event_filter = event_json.into_inner();
+    let user_id = check.map(|c| c.user_id);
+    event_filter.requester_id = user_id.clone();
+
+    let currency = match resolve_currency(event_filter.currency.take(), user_id.clone(), &db).await {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    event_filter.currency = currency.clone();
+
+    match Event::get_filtered_events(event_filter, &db).await {
+        Ok(mut events) => {
+            if let Some(user_id) = user_id {
+                if let Err(e) = Favorite::mark_saved(&mut events.items, user_id, &db).await {
+                    return HttpResponse::BadRequest().body(e);
+                }
+            }
+            if let Some(currency) = currency {
+                if let Err(e) = Event::convert_prices(&mut events.items, currency, &db).await {
+                    return HttpResponse::BadRequest().body(e);
+                }
+            }
+            HttpResponse::Ok().json(events)
+        },
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn save_event(db: web::Data<MongoDb>,
+                        event_path: web::Path<String>,
+                        check: check_user::CheckLogin
+) -> HttpResponse {
+    let event_id = event_path.into_inner();
+
+    match Favorite::save(event_id, check.user_id, &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
 
-    HttpResponse::Ok().json(events)
+pub async fn unsave_event(db: web::Data<MongoDb>,
+                          event_path: web::Path<String>,
+                          check: check_user::CheckLogin
+) -> HttpResponse {
+    let event_id = event_path.into_inner();
+
+    match Favorite::unsave(event_id, check.user_id, &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn get_saved_events(db: web::Data<MongoDb>,
+                              favorite_json: web::Query<FavoriteFilter>,
+                              check: check_user::CheckLogin
+) -> HttpResponse {
+    let favorite_filter = favorite_json.into_inner();
+
+    match Favorite::get_saved_events(check.user_id, favorite_filter, &db).await {
+        Ok(events) => HttpResponse::Ok().json(events),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
 }
 
 pub async fn count_events(db: web::Data<MongoDb>, event_json: web::Query<EventFilter>
@@ -50,32 +143,67 @@ pub async fn count_events(db: web::Data<MongoDb>, event_json: web::Query<EventFi
 
 pub async fn update_event(db: web::Data<MongoDb>,
                           event_json: web::Json<EventUpdate>,
-                          _: check_user::CheckLogin
+                          _: check_user::CheckLogin,
+                          req: HttpRequest
 ) -> HttpResponse {
     let event = event_json.into_inner();
+    let expected_version = match if_match(&req) {
+        Ok(v) => v,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
 
-    match EventUpdate::update(event, &db).await {
-        Ok(event) => HttpResponse::Ok().json(event),
+    match EventUpdate::update(event, expected_version, &db).await {
+        Ok(event) => HttpResponse::Ok().header(ETAG, etag(event.version)).json(event),
         Err(e) => {
             println!("{}", e.clone());
-            HttpResponse::BadRequest().body(e)
+            error_response(e)
         }
     }
 }
 
+pub async fn delete_event(db: web::Data<MongoDb>,
+                          event_path: web::Path<String>,
+                          check: check_user::CheckLogin,
+                          req: HttpRequest
+) -> HttpResponse {
+    let event_id = event_path.into_inner();
+
+    match Event::delete_event(event_id, check.user_id, client_ip(&req), &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
 pub async fn force_private(db: web::Data<MongoDb>,
                            event_path: web::Path<String>,
-                           check: check_user::CheckLogin
+                           reason_json: Option<web::Json<ModerationReason>>,
+                           check: check_user::CheckLogin,
+                           req: HttpRequest
 ) -> HttpResponse {
     let event_id = event_path.into_inner();
     let admin_id = check.user_id;
+    let reason = reason_json.and_then(|r| r.into_inner().reason);
 
-    match Event::force_private(event_id, admin_id, &db).await {
+    match Event::force_private(event_id, reason, admin_id, client_ip(&req), &db).await {
         Ok(msg) => HttpResponse::Created().body(msg),
         Err(e) => HttpResponse::BadRequest().body(e),
     }
 }
 
+pub async fn unhide(db: web::Data<MongoDb>,
+                    event_path: web::Path<String>,
+                    check: check_user::CheckLogin,
+                    req: HttpRequest
+) -> HttpResponse {
+    let event_id = event_path.into_inner();
+    let admin_id = check.user_id;
+
+    match Event::unhide(event_id, admin_id, client_ip(&req), &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PresignedRequest {
     file_extension: String,
@@ -1,3 +1,10 @@
 pub(crate) mod user_controller;
 pub(crate) mod event_controller;
-pub(crate) mod trip_controller;
\ No newline at end of file
+pub(crate) mod trip_controller;
+pub(crate) mod review_controller;
+pub(crate) mod currency_controller;
+pub(crate) mod tag_controller;
+pub(crate) mod place_controller;
+pub(crate) mod report_controller;
+pub(crate) mod audit_controller;
+pub(crate) mod trash_controller;
\ No newline at end of file
@@ -0,0 +1,22 @@
+use crate::models::place::{Country, City, PlaceQuery};
+use crate::MongoDb;
+
+use actix_web::{web, HttpResponse};
+
+pub async fn autocomplete_countries(db: web::Data<MongoDb>, query: web::Query<PlaceQuery>) -> HttpResponse {
+    let place_query = query.into_inner();
+
+    match Country::autocomplete(place_query, &db).await {
+        Ok(countries) => HttpResponse::Ok().json(countries),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn autocomplete_cities(db: web::Data<MongoDb>, query: web::Query<PlaceQuery>) -> HttpResponse {
+    let place_query = query.into_inner();
+
+    match City::autocomplete(place_query, &db).await {
+        Ok(cities) => HttpResponse::Ok().json(cities),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
@@ -0,0 +1,57 @@
+use crate::models::report::{Report, ReportCreate, ReportResolve, ReportFilter};
+use crate::auth::check_user;
+use crate::utils::client_ip::client_ip;
+use crate::MongoDb;
+
+use actix_web::{web, HttpRequest, HttpResponse};
+
+pub async fn create_report(db: web::Data<MongoDb>,
+                           report_json: web::Json<ReportCreate>,
+                           check: check_user::CheckLogin
+) -> HttpResponse {
+    let report = report_json.into_inner();
+
+    match Report::create(report, check.user_id, &db).await {
+        Ok(id) => HttpResponse::Created().json(id),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn get_report_queue(db: web::Data<MongoDb>,
+                              query: web::Query<ReportFilter>,
+                              check: check_user::CheckLogin
+) -> HttpResponse {
+    let report_filter = query.into_inner();
+
+    match Report::get_queue(report_filter, check.user_id, &db).await {
+        Ok(reports) => HttpResponse::Ok().json(reports),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn resolve_report(db: web::Data<MongoDb>,
+                            path: web::Path<String>,
+                            resolve_json: web::Json<ReportResolve>,
+                            check: check_user::CheckLogin,
+                            req: HttpRequest
+) -> HttpResponse {
+    let report_id = path.into_inner();
+    let resolution = resolve_json.into_inner();
+
+    match Report::resolve(report_id, resolution, check.user_id, client_ip(&req), &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn get_moderation_log(db: web::Data<MongoDb>,
+                                query: web::Query<ReportFilter>,
+                                check: check_user::CheckLogin
+) -> HttpResponse {
+    let report_filter = query.into_inner();
+
+    match Report::get_log(report_filter, check.user_id, &db).await {
+        Ok(log) => HttpResponse::Ok().json(log),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
@@ -0,0 +1,72 @@
+use crate::models::review::{Review, ReviewCreate, ReviewEdit, ReviewFilter, ReviewReplyCreate};
+use crate::auth::check_user;
+use crate::MongoDb;
+
+use actix_web::{web, HttpResponse};
+
+pub async fn create_review(db: web::Data<MongoDb>,
+                           event_path: web::Path<String>,
+                           review_json: web::Json<ReviewCreate>,
+                           check: check_user::CheckLogin
+) -> HttpResponse {
+    let event_id = event_path.into_inner();
+    let review = review_json.into_inner();
+
+    match Review::create(review, event_id, check.user_id, &db).await {
+        Ok(review_id) => HttpResponse::Created().json(review_id),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn get_event_reviews(db: web::Data<MongoDb>,
+                               event_path: web::Path<String>,
+                               review_json: web::Query<ReviewFilter>
+) -> HttpResponse {
+    let event_id = event_path.into_inner();
+    let review_filter = review_json.into_inner();
+
+    match Review::get_event_reviews(event_id, review_filter, &db).await {
+        Ok(reviews) => HttpResponse::Ok().json(reviews),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn update_review(db: web::Data<MongoDb>,
+                           review_path: web::Path<String>,
+                           review_json: web::Json<ReviewEdit>,
+                           check: check_user::CheckLogin
+) -> HttpResponse {
+    let review_id = review_path.into_inner();
+    let review_edit = review_json.into_inner();
+
+    match Review::update(review_id, review_edit, check.user_id, &db).await {
+        Ok(review) => HttpResponse::Ok().json(review),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn delete_review(db: web::Data<MongoDb>,
+                           review_path: web::Path<String>,
+                           check: check_user::CheckLogin
+) -> HttpResponse {
+    let review_id = review_path.into_inner();
+
+    match Review::delete(review_id, check.user_id, &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn reply_review(db: web::Data<MongoDb>,
+                          review_path: web::Path<String>,
+                          reply_json: web::Json<ReviewReplyCreate>,
+                          check: check_user::CheckLogin
+) -> HttpResponse {
+    let review_id = review_path.into_inner();
+    let reply = reply_json.into_inner();
+
+    match Review::reply(review_id, reply, check.user_id, &db).await {
+        Ok(review) => HttpResponse::Ok().json(review),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
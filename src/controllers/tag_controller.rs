@@ -0,0 +1,40 @@
+use crate::models::tag::{Tag, TagCreate, TagEdit, TagFilter};
+use crate::auth::check_user;
+use crate::MongoDb;
+
+use actix_web::{web, HttpResponse};
+
+pub async fn get_tags(db: web::Data<MongoDb>, query: web::Query<TagFilter>) -> HttpResponse {
+    let tag_filter = query.into_inner();
+
+    match Tag::get_tags(tag_filter, &db).await {
+        Ok(tags) => HttpResponse::Ok().json(tags),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn create_tag(db: web::Data<MongoDb>,
+                        tag_json: web::Json<TagCreate>,
+                        check: check_user::CheckLogin
+) -> HttpResponse {
+    let tag = tag_json.into_inner();
+
+    match Tag::create(tag, check.user_id, &db).await {
+        Ok(id) => HttpResponse::Created().json(id),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn update_tag(db: web::Data<MongoDb>,
+                        path: web::Path<String>,
+                        tag_json: web::Json<TagEdit>,
+                        check: check_user::CheckLogin
+) -> HttpResponse {
+    let tag_id = path.into_inner();
+    let edit_info = tag_json.into_inner();
+
+    match Tag::update(tag_id, edit_info, check.user_id, &db).await {
+        Ok(tag) => HttpResponse::Ok().json(tag),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
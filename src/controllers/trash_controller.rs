@@ -0,0 +1,25 @@
+use crate::models::trash;
+use crate::models::report::ContentType;
+use crate::auth::check_user;
+use crate::MongoDb;
+
+use actix_web::{web, HttpResponse};
+
+pub async fn get_trash(db: web::Data<MongoDb>, check: check_user::CheckLogin) -> HttpResponse {
+    match trash::get_trash(check.user_id, &db).await {
+        Ok(items) => HttpResponse::Ok().json(items),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn restore(db: web::Data<MongoDb>,
+                     path: web::Path<(ContentType, String)>,
+                     check: check_user::CheckLogin
+) -> HttpResponse {
+    let (content_type, content_id) = path.into_inner();
+
+    match trash::restore(content_type, content_id, check.user_id, &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
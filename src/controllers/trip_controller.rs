@@ -1,9 +1,21 @@
-use crate::models::trip::{Trip, TripCreate, TripEdit, TripFilter, EventEntry, TripFork};
+use crate::models::trip::{Trip, TripCreate, TripEdit, TripFilter, EventEntry, TripFork, ExpandQuery};
+use crate::models::currency::{CurrencyQuery, resolve_currency};
+use crate::models::report::ModerationReason;
+use crate::models::itinerary::{Itinerary, ItineraryQuery};
+use crate::models::routing::StraightLineRouter;
+use crate::models::optimizer::{Proposal, ProposalAccept};
+use crate::models::collaborator::{Collaborator, CollaboratorInvite, InvitationFilter};
+use crate::models::share_link::{ShareLink, ShareLinkCreate};
+use crate::models::trip_hub::{TripHub, LiveQuery};
 use crate::auth::check_user;
+use crate::utils::client_ip::client_ip;
+use crate::utils::websocket;
+use crate::utils::etag::{etag, if_match, error_response};
 use crate::MongoDb;
 
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use actix_web::http::StatusCode;
+use actix_web::http::header::ETAG;
 use std::collections::HashMap;
 
 pub async fn create_trip(db: web::Data<MongoDb>,
@@ -12,25 +24,158 @@ pub async fn create_trip(db: web::Data<MongoDb>,
 ) -> HttpResponse {
     let trip = trip_json.into_inner();
 
-    let trip_id = Trip::create(trip, &db).await;
+    match Trip::create(trip, &db).await {
+        Ok(trip_id) => HttpResponse::Created().json(trip_id),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
 
-    HttpResponse::Created().json(trip_id)
+pub async fn get_trip(db: web::Data<MongoDb>,
+                      trip_path: web::Path<String>,
+                      currency_json: web::Query<CurrencyQuery>,
+                      expand_json: web::Query<ExpandQuery>,
+                      check: Option<check_user::CheckLogin>
+) -> HttpResponse {
+    let trip_id = trip_path.into_inner();
+    let user_id = check.map(|c| c.user_id);
+    let expand_events = match expand_json.events() {
+        Ok(b) => b,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+
+    let currency = match resolve_currency(currency_json.into_inner().currency, user_id.clone(), &db).await {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+
+    match Trip::get_trip(trip_id, &db).await.and_then(|trip| trip.visible(user_id.as_ref())) {
+        Ok(trip) => {
+            let mut trips = vec![trip];
+            let entries = match expand_events {
+                true => Trip::expand_events(&mut trips, user_id.as_ref(), &db).await,
+                false => Trip::mark_unavailable_entries(&mut trips, &db).await,
+            };
+            if let Err(e) = entries {
+                return HttpResponse::BadRequest().body(e);
+            }
+            if let Err(e) = Trip::attribute_forks(&mut trips, user_id.as_ref(), &db).await {
+                return HttpResponse::BadRequest().body(e);
+            }
+            if let Some(currency) = currency {
+                if let Err(e) = Trip::convert_budgets(&mut trips, currency, &db).await {
+                    return HttpResponse::BadRequest().body(e);
+                }
+            }
+            let trip = trips.remove(0);
+            HttpResponse::Ok().header(ETAG, etag(trip.version)).json(trip)
+        },
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
 }
 
-pub async fn get_trip(db: web::Data<MongoDb>, trip_path: web::Path<String>) -> HttpResponse {
+pub async fn get_itinerary(db: web::Data<MongoDb>,
+                           trip_path: web::Path<String>,
+                           query: web::Query<ItineraryQuery>,
+                           check: Option<check_user::CheckLogin>
+) -> HttpResponse {
     let trip_id = trip_path.into_inner();
 
-    match Trip::get_trip(trip_id, &db).await {
-        Ok(trip) => HttpResponse::Ok().json(trip),
+    match Itinerary::get(trip_id, query.into_inner(), check.map(|c| c.user_id), &StraightLineRouter, &db).await {
+        Ok(itinerary) => HttpResponse::Ok().json(itinerary),
         Err(e) => HttpResponse::BadRequest().body(e),
     }
 }
 
-pub async fn get_trips(db: web::Data<MongoDb>, trip_json: web::Query<TripFilter>) -> HttpResponse {
-    let trip_filter = trip_json.into_inner();
-    let trips = Trip::get_filtered_trips(trip_filter, &db).await;
+pub async fn get_optimized_itinerary(db: web::Data<MongoDb>,
+                                     trip_path: web::Path<String>,
+                                     query: web::Query<ItineraryQuery>,
+                                     check: check_user::CheckLogin
+) -> HttpResponse {
+    let trip_id = trip_path.into_inner();
 
-    HttpResponse::Ok().json(trips)
+    match Proposal::get(trip_id, query.into_inner(), check.user_id, &StraightLineRouter, &db).await {
+        Ok(proposal) => HttpResponse::Ok().json(proposal),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn accept_optimized_itinerary(db: web::Data<MongoDb>,
+                                        trip_path: web::Path<String>,
+                                        accept_json: web::Json<ProposalAccept>,
+                                        check: check_user::CheckLogin
+) -> HttpResponse {
+    let trip_id = trip_path.into_inner();
+
+    match Proposal::accept(trip_id, accept_json.into_inner(), check.user_id, &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn get_trips(db: web::Data<MongoDb>,
+                       trip_json: web::Query<TripFilter>,
+                       expand_json: web::Query<ExpandQuery>,
+                       check: Option<check_user::CheckLogin>
+) -> HttpResponse {
+    let mut trip_filter = trip_json.into_inner();
+    trip_filter.requester_id = check.map(|c| c.user_id);
+    let requester_id = trip_filter.requester_id.clone();
+    let expand_events = match expand_json.events() {
+        Ok(b) => b,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+
+    let currency = match resolve_currency(trip_filter.currency.take(), trip_filter.requester_id.clone(), &db).await {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    trip_filter.currency = currency.clone();
+
+    match Trip::get_filtered_trips(trip_filter, &db).await {
+        Ok(mut trips) => {
+            let entries = match expand_events {
+                true => Trip::expand_events(&mut trips.items, requester_id.as_ref(), &db).await,
+                false => Trip::mark_unavailable_entries(&mut trips.items, &db).await,
+            };
+            if let Err(e) = entries {
+                return HttpResponse::BadRequest().body(e);
+            }
+            if let Err(e) = Trip::attribute_forks(&mut trips.items, requester_id.as_ref(), &db).await {
+                return HttpResponse::BadRequest().body(e);
+            }
+            if let Some(currency) = currency {
+                if let Err(e) = Trip::convert_budgets(&mut trips.items, currency, &db).await {
+                    return HttpResponse::BadRequest().body(e);
+                }
+            }
+            HttpResponse::Ok().json(trips)
+        },
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn get_trip_forks(db: web::Data<MongoDb>,
+                            trip_path: web::Path<String>,
+                            trip_json: web::Query<TripFilter>,
+                            check: Option<check_user::CheckLogin>
+) -> HttpResponse {
+    let trip_id = trip_path.into_inner();
+    let mut trip_filter = trip_json.into_inner();
+    trip_filter.requester_id = check.map(|c| c.user_id);
+    let requester_id = trip_filter.requester_id.clone();
+
+    match Trip::get_forks(trip_id, trip_filter, &db).await {
+        Ok(mut trips) => {
+            if let Err(e) = Trip::mark_unavailable_entries(&mut trips.items, &db).await {
+                return HttpResponse::BadRequest().body(e);
+            }
+            if let Err(e) = Trip::attribute_forks(&mut trips.items, requester_id.as_ref(), &db).await {
+                return HttpResponse::BadRequest().body(e);
+            }
+            HttpResponse::Ok().json(trips)
+        },
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
 }
 
 pub async fn count_trips(db: web::Data<MongoDb>, trip_json: web::Query<TripFilter>) -> HttpResponse {
@@ -46,51 +191,75 @@ pub async fn count_trips(db: web::Data<MongoDb>, trip_json: web::Query<TripFilte
     }
 }
 
-pub async fn delete_trip(db: web::Data<MongoDb>, trip_path: web::Path<String>) -> HttpResponse {
+pub async fn delete_trip(db: web::Data<MongoDb>,
+                         trip_path: web::Path<String>,
+                         check: check_user::CheckLogin,
+                         req: HttpRequest
+) -> HttpResponse {
     let trip_id = trip_path.into_inner();
 
-    match Trip::delete_trip(trip_id, &db).await {
-        Ok(_count) => HttpResponse::Ok().finish(),
+    match Trip::delete_trip(trip_id, check.user_id, client_ip(&req), &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
         Err(e) => HttpResponse::BadRequest().body(e),
     }
 }
 
 pub async fn update_trip(db: web::Data<MongoDb>,
+                        hub: web::Data<TripHub>,
                         trip_json: web::Json<TripEdit>,
-                        _: check_user::CheckLogin
+                        check: check_user::CheckLogin,
+                        req: HttpRequest
 ) -> HttpResponse {
     let trip_edit = trip_json.into_inner();
-    match Trip::update(trip_edit, &db).await {
-        Ok(trip) => HttpResponse::Ok().json(trip),
+    let expected_version = match if_match(&req) {
+        Ok(v) => v,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+
+    match Trip::update(trip_edit, expected_version, check.user_id, &hub, &db).await {
+        Ok(trip) => HttpResponse::Ok().header(ETAG, etag(trip.version)).json(trip),
         Err(e) => {
             println!("{}", e.clone());
-            HttpResponse::BadRequest().body(e)
+            error_response(e)
         }
     }
 }
 
 pub async fn add_event_entry(db: web::Data<MongoDb>,
+                             hub: web::Data<TripHub>,
                              entry_json: web::Json<EventEntry>,
-                             _:check_user::CheckLogin
+                             check: check_user::CheckLogin,
+                             req: HttpRequest
 ) -> HttpResponse {
     let event_entry = entry_json.into_inner();
+    let expected_version = match if_match(&req) {
+        Ok(v) => v,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
 
-    match Trip::push_event_entry(event_entry, &db).await {
-        Ok(msg) => HttpResponse::Ok().body(msg),
-        Err(e) => HttpResponse::BadRequest().body(e),
+    match Trip::push_event_entry(event_entry, expected_version, check.user_id, &hub, &db).await {
+        Ok(result) if result.added => HttpResponse::Ok().json(result),
+        Ok(result) => HttpResponse::Conflict().json(result),
+        Err(e) => error_response(e),
     }
 }
 
 pub async fn remove_event_entry(db: web::Data<MongoDb>,
+                                hub: web::Data<TripHub>,
                                 entry_json:
                                 web::Json<EventEntry>,
-                                _:check_user::CheckLogin
+                                check: check_user::CheckLogin,
+                                req: HttpRequest
 ) -> HttpResponse {
     let event_entry = entry_json.into_inner();
+    let expected_version = match if_match(&req) {
+        Ok(v) => v,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
 
-    match Trip::pull_event_entry(event_entry, &db).await {
+    match Trip::pull_event_entry(event_entry, expected_version, check.user_id, &hub, &db).await {
         Ok(msg) => HttpResponse::Ok().body(msg),
-        Err(e) => HttpResponse::BadRequest().body(e),
+        Err(e) => error_response(e),
     }
 }
 
@@ -106,15 +275,179 @@ pub async fn fork_trip(db: web::Data<MongoDb>,
     }
 }
 
+pub async fn invite_collaborator(db: web::Data<MongoDb>,
+                                 trip_path: web::Path<String>,
+                                 invite_json: web::Json<CollaboratorInvite>,
+                                 check: check_user::CheckLogin
+) -> HttpResponse {
+    let trip_id = trip_path.into_inner();
+
+    match Collaborator::invite(trip_id, invite_json.into_inner(), check.user_id, &db).await {
+        Ok(msg) => HttpResponse::Created().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn remove_collaborator(db: web::Data<MongoDb>,
+                                 hub: web::Data<TripHub>,
+                                 path: web::Path<(String, String)>,
+                                 check: check_user::CheckLogin
+) -> HttpResponse {
+    let (trip_id, member_id) = path.into_inner();
+
+    match Collaborator::remove(trip_id, member_id, check.user_id, &hub, &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn accept_invitation(db: web::Data<MongoDb>,
+                               trip_path: web::Path<String>,
+                               check: check_user::CheckLogin
+) -> HttpResponse {
+    let trip_id = trip_path.into_inner();
+
+    match Collaborator::accept(trip_id, check.user_id, &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn decline_invitation(db: web::Data<MongoDb>,
+                                trip_path: web::Path<String>,
+                                check: check_user::CheckLogin
+) -> HttpResponse {
+    let trip_id = trip_path.into_inner();
+
+    match Collaborator::decline(trip_id, check.user_id, &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn get_invitations(db: web::Data<MongoDb>,
+                             filter_json: web::Query<InvitationFilter>,
+                             check: check_user::CheckLogin
+) -> HttpResponse {
+    match Collaborator::get_invitations(check.user_id, filter_json.into_inner(), &db).await {
+        Ok(trips) => HttpResponse::Ok().json(trips),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn create_share_link(db: web::Data<MongoDb>,
+                               trip_path: web::Path<String>,
+                               link_json: Option<web::Json<ShareLinkCreate>>,
+                               check: check_user::CheckLogin
+) -> HttpResponse {
+    let trip_id = trip_path.into_inner();
+    let link_create = link_json.map(|l| l.into_inner()).unwrap_or_default();
+
+    match ShareLink::create(trip_id, link_create, check.user_id, &db).await {
+        Ok(link) => HttpResponse::Created().json(link),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn get_share_links(db: web::Data<MongoDb>,
+                             trip_path: web::Path<String>,
+                             check: check_user::CheckLogin
+) -> HttpResponse {
+    let trip_id = trip_path.into_inner();
+
+    match ShareLink::get_links(trip_id, check.user_id, &db).await {
+        Ok(links) => HttpResponse::Ok().json(links),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+pub async fn revoke_share_link(db: web::Data<MongoDb>,
+                               path: web::Path<(String, String)>,
+                               check: check_user::CheckLogin
+) -> HttpResponse {
+    let (trip_id, link_id) = path.into_inner();
+
+    match ShareLink::revoke(trip_id, link_id, check.user_id, &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+// No login needed, the token is the credential
+pub async fn get_shared_trip(db: web::Data<MongoDb>, token_path: web::Path<String>) -> HttpResponse {
+    let token = token_path.into_inner();
+
+    match ShareLink::get_shared_trip(token, &db).await {
+        Ok(trip) => HttpResponse::Ok().json(trip),
+        Err(e) => HttpResponse::NotFound().body(e),
+    }
+}
+
+// Changes made by the members, as they happen. Only the owner and collaborators who
+// accepted can listen
+pub async fn trip_live(db: web::Data<MongoDb>,
+                       hub: web::Data<TripHub>,
+                       trip_path: web::Path<String>,
+                       query: web::Query<LiveQuery>,
+                       check: Option<check_user::CheckLogin>,
+                       req: HttpRequest,
+                       payload: web::Payload
+) -> HttpResponse {
+    let trip_id = trip_path.into_inner();
+    let live_query = query.into_inner();
+    let user_id = match (check, websocket::protocol_token(&req)) {
+        (Some(check), _) => check.user_id,
+        (None, Some(token)) => match check_user::CheckLogin::from_token(token.as_str()) {
+            Ok(check) => check.user_id,
+            Err(e) => return HttpResponse::from_error(e),
+        },
+        (None, None) => return HttpResponse::Unauthorized().body("blocked!"),
+    };
+
+    let trip = match Trip::get_trip(trip_id, &db).await {
+        Ok(trip) => trip,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    if !trip.owned_by(user_id.as_str()) && trip.role_of(user_id.as_str()).is_none() {
+        return HttpResponse::Forbidden().body("Only members can follow the trip");
+    }
+
+    let subscription = hub.subscribe(&trip._id, user_id.as_str(), live_query.since);
+    let connection_id = subscription.connection_id;
+    let hub = hub.into_inner();
+    let trip_oid = trip._id;
+    match websocket::start(&req, payload, subscription.receiver, move || hub.unsubscribe(&trip_oid, connection_id)) {
+        Ok(response) => response,
+        Err(e) => HttpResponse::from_error(e),
+    }
+}
+
 pub async fn force_private(db: web::Data<MongoDb>,
                            trip_path: web::Path<String>,
-                           check: check_user::CheckLogin
+                           reason_json: Option<web::Json<ModerationReason>>,
+                           check: check_user::CheckLogin,
+                           req: HttpRequest
 ) -> HttpResponse {
     let trip_id = trip_path.into_inner();
     let admin_id = check.user_id;
+    let reason = reason_json.and_then(|r| r.into_inner().reason);
 
-    match Trip::force_private(trip_id, admin_id, &db).await {
+    match Trip::force_private(trip_id, reason, admin_id, client_ip(&req), &db).await {
         Ok(msg) => HttpResponse::Created().body(msg),
         Err(e) => HttpResponse::BadRequest().body(e),
     }
+}
+
+pub async fn unhide(db: web::Data<MongoDb>,
+                    trip_path: web::Path<String>,
+                    check: check_user::CheckLogin,
+                    req: HttpRequest
+) -> HttpResponse {
+    let trip_id = trip_path.into_inner();
+    let admin_id = check.user_id;
+
+    match Trip::unhide(trip_id, admin_id, client_ip(&req), &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
 }
\ No newline at end of file
@@ -1,9 +1,11 @@
 use crate::models::user::{User, UserLogin, ProvidedGoogleUser};
+use crate::models::currency::CurrencyPreference;
 use crate::auth::check_user;
 use crate::auth::{authentication};
+use crate::utils::client_ip::client_ip;
 use crate::MongoDb;
 
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use serde::{Serialize, Deserialize};
 use ureq;
 
@@ -19,10 +21,10 @@ pub async fn index() -> impl Responder {
     HttpResponse::Ok().body("Hello world!")
 }
 
-pub async fn login(db: web::Data<MongoDb>, user_form: web::Form<UserLogin>) -> impl Responder {
+pub async fn login(db: web::Data<MongoDb>, user_form: web::Form<UserLogin>, req: HttpRequest) -> impl Responder {
     let user_login = user_form.into_inner();
 
-    match User::find_user(user_login, &db).await {
+    match User::find_user(user_login, client_ip(&req), &db).await {
         Ok(validated_user) => {
             let user_id = validated_user._id.clone().unwrap();
             let username = validated_user.username.clone();
@@ -91,12 +93,13 @@ pub async fn get_all_like_user(db: web::Data<MongoDb>,
 
 pub async fn promote(db: web::Data<MongoDb>,
                      user_path: web::Path<String>,
-                     check: check_user::CheckLogin
+                     check: check_user::CheckLogin,
+                     req: HttpRequest
 ) -> HttpResponse {
     let user_id = user_path.into_inner();
     let admin_id = check.user_id;
 
-    match User::promote_user(user_id, admin_id, &db).await {
+    match User::promote_user(user_id, admin_id, client_ip(&req), &db).await {
         Ok(msg) => HttpResponse::Created().body(msg),
         Err(e) => HttpResponse::BadRequest().body(e),
     }
@@ -104,17 +107,30 @@ pub async fn promote(db: web::Data<MongoDb>,
 
 pub async fn demote(db: web::Data<MongoDb>,
                      user_path: web::Path<String>,
-                     check: check_user::CheckLogin
+                     check: check_user::CheckLogin,
+                     req: HttpRequest
 ) -> HttpResponse {
     let user_id = user_path.into_inner();
     let admin_id = check.user_id;
 
-    match User::demote_user(user_id, admin_id, &db).await {
+    match User::demote_user(user_id, admin_id, client_ip(&req), &db).await {
         Ok(msg) => HttpResponse::Created().body(msg),
         Err(e) => HttpResponse::BadRequest().body(e),
     }
 }
 
+pub async fn set_preferred_currency(db: web::Data<MongoDb>,
+                                    currency_json: web::Json<CurrencyPreference>,
+                                    check: check_user::CheckLogin
+) -> HttpResponse {
+    let preference = currency_json.into_inner();
+
+    match User::set_preferred_currency(check.user_id, preference.currency, &db).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
 pub async fn register_from_google(user_json: web::Json<ProvidedGoogleUser>, db: web::Data<MongoDb>)
     -> HttpResponse {
     // Validate that token_id is valid
@@ -149,7 +165,7 @@ pub async fn register_from_google(user_json: web::Json<ProvidedGoogleUser>, db:
     }
 }
 
-pub async fn login_from_google(user_json: web::Json<ProvidedGoogleUser>, db: web::Data<MongoDb>)
+pub async fn login_from_google(user_json: web::Json<ProvidedGoogleUser>, db: web::Data<MongoDb>, req: HttpRequest)
                                -> HttpResponse {
     let user_login = user_json.into_inner();
 
@@ -157,7 +173,7 @@ pub async fn login_from_google(user_json: web::Json<ProvidedGoogleUser>, db: web
         .call();
 
     if resp.ok() {
-        match User::find_google_user(user_login, &db).await {
+        match User::find_google_user(user_login, client_ip(&req), &db).await {
             Ok(validated_user) => {
                 let user_id = validated_user._id.clone().unwrap();
                 let username = validated_user.username.clone();
@@ -6,7 +6,28 @@ mod tests;
 
 extern crate argon2;
 
-use crate::controllers::{user_controller, event_controller, trip_controller};
+use crate::controllers::{
+    user_controller,
+    event_controller,
+    trip_controller,
+    review_controller,
+    currency_controller,
+    tag_controller,
+    place_controller,
+    report_controller,
+    audit_controller,
+    trash_controller
+};
+use crate::models::event::Event;
+use crate::models::review::Review;
+use crate::models::favorite::Favorite;
+use crate::models::tag::Tag;
+use crate::models::place;
+use crate::models::report::Report;
+use crate::models::audit::AuditEntry;
+use crate::models::trash;
+use crate::models::share_link::ShareLink;
+use crate::models::trip_hub::TripHub;
 use actix_web::{web, middleware, App, HttpServer, HttpResponse};
 use mongodb::{Database ,Client, options::ClientOptions};
 use mongodb::options::ResolverConfig;
@@ -34,15 +55,39 @@ async fn main() -> std::io::Result<()> {
     let mongo_db = mongo_client.database(std::env::var("DATABASE_NAME")
         .expect("Error retrieving database name")
         .as_str());
+    Event::create_indexes(&mongo_db).await.expect("Error creating indexes");
+    Review::create_indexes(&mongo_db).await.expect("Error creating indexes");
+    Favorite::create_indexes(&mongo_db).await.expect("Error creating indexes");
+    Tag::create_indexes(&mongo_db).await.expect("Error creating indexes");
+    place::create_indexes(&mongo_db).await.expect("Error creating indexes");
+    Report::create_indexes(&mongo_db).await.expect("Error creating indexes");
+    AuditEntry::create_indexes(&mongo_db).await.expect("Error creating indexes");
+    trash::create_indexes(&mongo_db).await.expect("Error creating indexes");
+    ShareLink::create_indexes(&mongo_db).await.expect("Error creating indexes");
+    // Events and trips are validated against the places, so they must exist before serving
+    place::load_places(&mongo_db).await.expect("Error loading places");
+
+    if let Some(command) = std::env::args().nth(1) {
+        return utils::commands::run(command.as_str(), &mongo_db).await;
+    }
+
+    actix_rt::spawn(trash::purge_periodically(mongo_db.clone()));
+
+    // Shared by every worker so members on different workers hear each other
+    let trip_hub = web::Data::new(TripHub::default());
+
     let server = HttpServer::new(move || {
         App::new()
             .data(mongo_client.clone())
             .data(mongo_db.clone())
             .data(s3_client.clone())
+            .app_data(trip_hub.clone())
             .wrap(middleware::Logger::default())
             .wrap(
                 Cors::new()
                     .send_wildcard()
+                    // Clients send it back in If-Match
+                    .expose_headers(vec!["ETag"])
                     .finish()
             )
             .route("/", web::get().to(user_controller::index))
@@ -58,7 +103,35 @@ async fn main() -> std::io::Result<()> {
                     .route("/create", web::post().to(event_controller::create_event))
                     .route("/update", web::put().to(event_controller::update_event))
                     .route("/forceprivate/{id}", web::put().to(event_controller::force_private))
+                    .route("/unhide/{id}", web::put().to(event_controller::unhide))
                     .route("/{id}", web::get().to(event_controller::get_event))
+                    .route("/{id}", web::delete().to(event_controller::delete_event))
+                    .route("/{id}/reviews", web::get().to(review_controller::get_event_reviews))
+                    .route("/{id}/reviews", web::post().to(review_controller::create_review))
+                    .route("/{id}/save", web::put().to(event_controller::save_event))
+                    .route("/{id}/save", web::delete().to(event_controller::unsave_event))
+            )
+            .service(
+                web::scope("/review")
+                    .route("/{id}", web::put().to(review_controller::update_review))
+                    .route("/{id}", web::delete().to(review_controller::delete_review))
+                    .route("/{id}/reply", web::put().to(review_controller::reply_review))
+            )
+            .service(
+                web::scope("/report")
+                    .route("", web::get().to(report_controller::get_report_queue))
+                    .route("", web::post().to(report_controller::create_report))
+                    .route("/log", web::get().to(report_controller::get_moderation_log))
+                    .route("/{id}/resolve", web::put().to(report_controller::resolve_report))
+            )
+            .service(
+                web::scope("/trash")
+                    .route("", web::get().to(trash_controller::get_trash))
+                    .route("/{content_type}/{id}/restore", web::put().to(trash_controller::restore))
+            )
+            .service(
+                web::scope("/audit")
+                    .route("", web::get().to(audit_controller::get_audit_log))
             )
             .service(
                 web::scope("/trip")
@@ -69,15 +142,48 @@ async fn main() -> std::io::Result<()> {
                     .route("/add", web::put().to(trip_controller::add_event_entry))
                     .route("/remove", web::put().to(trip_controller::remove_event_entry))
                     .route("/fork", web::post().to(trip_controller::fork_trip))
+                    .route("/invitations", web::get().to(trip_controller::get_invitations))
+                    .route("/shared/{token}", web::get().to(trip_controller::get_shared_trip))
                     .route("/forceprivate/{id}", web::put().to(trip_controller::force_private))
+                    .route("/unhide/{id}", web::put().to(trip_controller::unhide))
                     .route("/{id}", web::get().to(trip_controller::get_trip))
+                    .route("/{id}/forks", web::get().to(trip_controller::get_trip_forks))
+                    .route("/{id}/live", web::get().to(trip_controller::trip_live))
+                    .route("/{id}/collaborators", web::post().to(trip_controller::invite_collaborator))
+                    .route("/{id}/collaborators/{user_id}", web::delete().to(trip_controller::remove_collaborator))
+                    .route("/{id}/invitation/accept", web::put().to(trip_controller::accept_invitation))
+                    .route("/{id}/invitation/decline", web::put().to(trip_controller::decline_invitation))
+                    .route("/{id}/share", web::post().to(trip_controller::create_share_link))
+                    .route("/{id}/share", web::get().to(trip_controller::get_share_links))
+                    .route("/{id}/share/{link_id}", web::delete().to(trip_controller::revoke_share_link))
+                    .route("/{id}/itinerary", web::get().to(trip_controller::get_itinerary))
+                    .route("/{id}/optimize", web::get().to(trip_controller::get_optimized_itinerary))
+                    .route("/{id}/optimize", web::put().to(trip_controller::accept_optimized_itinerary))
                     .route("/{id}", web::delete().to(trip_controller::delete_trip))
             )
             .service(
                 web::scope("/user")
+                    .route("/saved", web::get().to(event_controller::get_saved_events))
+                    .route("/currency", web::put().to(user_controller::set_preferred_currency))
                     .route("/promote/{id}", web::put().to(user_controller::promote))
                     .route("/demote/{id}", web::put().to(user_controller::demote))
             )
+            .service(
+                web::scope("/currency")
+                    .route("/rates", web::get().to(currency_controller::get_rates))
+                    .route("/rates", web::put().to(currency_controller::set_rate))
+            )
+            .service(
+                web::scope("/tags")
+                    .route("", web::get().to(tag_controller::get_tags))
+                    .route("", web::post().to(tag_controller::create_tag))
+                    .route("/{id}", web::put().to(tag_controller::update_tag))
+            )
+            .service(
+                web::scope("/places")
+                    .route("/countries", web::get().to(place_controller::autocomplete_countries))
+                    .route("/cities", web::get().to(place_controller::autocomplete_cities))
+            )
             .service(
                 web::scope("/users")
                     .route("/{str}", web::get().to(user_controller::get_all_like_user))
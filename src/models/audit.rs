@@ -0,0 +1,145 @@
+use crate::MongoDb;
+use crate::models::user::User;
+use crate::utils::pagination::{find_page, Page, PageRequest};
+
+use serde::{Deserialize, Serialize};
+use bson::oid::ObjectId;
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::options::InsertOneOptions;
+use chrono::{DateTime, Utc};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    PromoteUser,
+    DemoteUser,
+    ForcePrivate,
+    Unhide,
+    DeleteTrip,
+    DeleteEvent,
+    Login,
+    LoginFailed,
+}
+
+// Entries are only ever inserted, nothing updates or deletes them
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AuditEntry {
+    pub _id: ObjectId,
+    pub action: AuditAction,
+    pub actor_id: Option<ObjectId>,
+    pub target_type: String,
+    pub target_id: Option<ObjectId>,
+    pub before: Option<Document>,
+    pub after: Option<Document>,
+    pub ip: Option<String>,
+    pub created_at: String,
+}
+
+pub struct AuditRecord {
+    pub action: AuditAction,
+    pub actor_id: Option<ObjectId>,
+    pub target_type: &'static str,
+    pub target_id: Option<ObjectId>,
+    pub before: Option<Document>,
+    pub after: Option<Document>,
+    pub ip: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AuditFilter {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    pub action: Option<AuditAction>,
+    pub actor_id: Option<String>,
+    pub target_id: Option<String>,
+    // RFC 3339 bounds on created_at
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl AuditEntry {
+    pub async fn record(record: AuditRecord, db: &MongoDb) -> Result<(), String> {
+        let audit_collection = db.collection("audit_log");
+
+        let audit_doc = doc! {
+            "action": bson::to_bson(&record.action).unwrap_or(Bson::Null),
+            "actor_id": bson::to_bson(&record.actor_id).unwrap_or(Bson::Null),
+            "target_type": record.target_type,
+            "target_id": bson::to_bson(&record.target_id).unwrap_or(Bson::Null),
+            "before": bson::to_bson(&record.before).unwrap_or(Bson::Null),
+            "after": bson::to_bson(&record.after).unwrap_or(Bson::Null),
+            "ip": bson::to_bson(&record.ip).unwrap_or(Bson::Null),
+            "created_at": Utc::now().to_rfc3339(),
+        };
+
+        match audit_collection.insert_one(audit_doc, InsertOneOptions::default()).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Error writing audit log".to_string()),
+        }
+    }
+
+    pub async fn get_entries(mut audit_filter: AuditFilter, requester_id: String, db: &MongoDb) -> Result<Page<AuditEntry>, String> {
+        let audit_collection = db.collection("audit_log");
+
+        User::check_superadmin(requester_id, db).await?;
+
+        let page_request = PageRequest::new(audit_filter.cursor.take(), audit_filter.limit)?;
+        let filter = get_find_filter(audit_filter)?;
+
+        find_page(&audit_collection, filter.clone(), filter, Some(doc! {"_id": -1}), page_request).await
+    }
+
+    pub async fn create_indexes(db: &MongoDb) -> Result<(), String> {
+        let command = doc! {
+            "createIndexes": "audit_log",
+            "indexes": [
+                {"key": {"action": 1, "_id": -1}, "name": "action"},
+                {"key": {"actor_id": 1, "_id": -1}, "name": "actor"},
+                {"key": {"target_id": 1, "_id": -1}, "name": "target"},
+            ]
+        };
+
+        match db.run_command(command, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Error creating audit indexes".to_string()),
+        }
+    }
+}
+
+pub(crate) fn get_find_filter(audit_filter: AuditFilter) -> Result<Document, String> {
+    let mut filter = doc! {};
+    if let Some(action) = audit_filter.action {
+        filter.insert("action", bson::to_bson(&action).unwrap_or(Bson::Null));
+    }
+    if let Some(actor_id) = audit_filter.actor_id {
+        let actor_oid = ObjectId::with_string(actor_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+        filter.insert("actor_id", actor_oid);
+    }
+    if let Some(target_id) = audit_filter.target_id {
+        let target_oid = ObjectId::with_string(target_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+        filter.insert("target_id", target_oid);
+    }
+
+    // Timestamps share the same RFC 3339 UTC format so they compare as strings
+    let mut created_range = doc! {};
+    if let Some(from) = audit_filter.from {
+        created_range.insert("$gte", to_utc(from.as_str())?);
+    }
+    if let Some(to) = audit_filter.to {
+        created_range.insert("$lte", to_utc(to.as_str())?);
+    }
+    if !created_range.is_empty() {
+        filter.insert("created_at", created_range);
+    }
+
+    Ok(filter)
+}
+
+fn to_utc(date: &str) -> Result<String, String> {
+    match DateTime::parse_from_rfc3339(date) {
+        Ok(parsed) => Ok(parsed.with_timezone(&Utc).to_rfc3339()),
+        Err(_) => Err(format!("Invalid date, expecting RFC 3339: {}", date)),
+    }
+}
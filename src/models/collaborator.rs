@@ -0,0 +1,182 @@
+use crate::MongoDb;
+use crate::models::trip::Trip;
+use crate::models::trip_hub::TripHub;
+use crate::utils::pagination::{find_page, Page, PageRequest};
+
+use serde::{Deserialize, Serialize};
+use bson::oid::ObjectId;
+use mongodb::bson::{doc, Bson};
+use mongodb::options::{FindOneOptions, UpdateOptions};
+use chrono::Utc;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollaboratorRole {
+    Viewer,
+    // Can also add and remove entries
+    Editor,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum InvitationStatus {
+    Pending,
+    Accepted,
+}
+
+// Member of a trip other than its owner, declined invitations are removed
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Collaborator {
+    pub user_id: ObjectId,
+    pub role: CollaboratorRole,
+    pub status: InvitationStatus,
+    pub invited_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CollaboratorInvite {
+    pub user_id: String,
+    pub role: CollaboratorRole,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct InvitationFilter {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+impl CollaboratorRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CollaboratorRole::Viewer => "viewer",
+            CollaboratorRole::Editor => "editor",
+        }
+    }
+}
+
+impl Collaborator {
+    // Only the owner invites, each user is invited once
+    pub async fn invite(trip_id: String, invite: CollaboratorInvite, owner_id: String, db: &MongoDb) -> Result<String, String> {
+        let trip_collection = db.collection("trips");
+        let user_collection = db.collection("users");
+
+        let trip = Trip::get_trip(trip_id, db).await?;
+        if !trip.owned_by(owner_id.as_str()) {
+            return Err("Only the owner can invite collaborators".to_string());
+        }
+        let user_oid = ObjectId::with_string(invite.user_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+        if trip.owned_by(invite.user_id.as_str()) {
+            return Err("The owner cannot be invited to their own trip".to_string());
+        }
+
+        match user_collection.find_one(doc! {"_id": user_oid.clone()}, FindOneOptions::default())
+            .await.expect("Error finding user") {
+            Some(_) => (),
+            None => return Err("User not found".to_string()),
+        }
+
+        let collaborator_doc = doc! {
+            "user_id": user_oid.clone(),
+            "role": invite.role.as_str(),
+            "status": "pending",
+            "invited_at": Utc::now().to_rfc3339(),
+        };
+
+        match trip_collection.update_one(doc! {"_id": trip._id, "collaborators.user_id": {"$ne": user_oid}},
+                                         doc! {"$push": {"collaborators": collaborator_doc}, "$inc": {"version": 1}},
+                                         UpdateOptions::default()
+        ).await {
+            Ok(result) if result.modified_count > 0 => Ok("User successfully invited".to_string()),
+            Ok(_) => Err("User is already a collaborator".to_string()),
+            Err(_) => Err("Error inviting user".to_string()),
+        }
+    }
+
+    pub async fn accept(trip_id: String, user_id: String, db: &MongoDb) -> Result<String, String> {
+        let trip_collection = db.collection("trips");
+        let (trip_oid, user_oid) = trip_and_user_oids(trip_id, user_id)?;
+
+        match trip_collection.update_one(
+            doc! {
+                "_id": trip_oid,
+                "deleted_at": Bson::Null,
+                "collaborators": {"$elemMatch": {"user_id": user_oid, "status": "pending"}}
+            },
+            doc! {"$set": {"collaborators.$.status": "accepted"}, "$inc": {"version": 1}},
+            UpdateOptions::default()
+        ).await {
+            Ok(result) if result.modified_count > 0 => Ok("Invitation accepted".to_string()),
+            Ok(_) => Err("Invitation not found".to_string()),
+            Err(_) => Err("Error accepting invitation".to_string()),
+        }
+    }
+
+    pub async fn decline(trip_id: String, user_id: String, db: &MongoDb) -> Result<String, String> {
+        let trip_collection = db.collection("trips");
+        let (trip_oid, user_oid) = trip_and_user_oids(trip_id, user_id)?;
+
+        match trip_collection.update_one(
+            doc! {
+                "_id": trip_oid,
+                "deleted_at": Bson::Null,
+                "collaborators": {"$elemMatch": {"user_id": user_oid.clone(), "status": "pending"}}
+            },
+            doc! {"$pull": {"collaborators": {"user_id": user_oid, "status": "pending"}}, "$inc": {"version": 1}},
+            UpdateOptions::default()
+        ).await {
+            Ok(result) if result.modified_count > 0 => Ok("Invitation declined".to_string()),
+            Ok(_) => Err("Invitation not found".to_string()),
+            Err(_) => Err("Error declining invitation".to_string()),
+        }
+    }
+
+    // The owner removes anyone, collaborators can only leave. Their live connections to
+    // the trip are closed
+    pub async fn remove(trip_id: String, member_id: String, requester_id: String, hub: &TripHub, db: &MongoDb) -> Result<String, String> {
+        let trip_collection = db.collection("trips");
+
+        let trip = Trip::get_trip(trip_id, db).await?;
+        if !trip.owned_by(requester_id.as_str()) && member_id != requester_id {
+            return Err("Only the owner can remove other collaborators".to_string());
+        }
+        let member_oid = ObjectId::with_string(member_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        match trip_collection.update_one(doc! {"_id": trip._id.clone(), "collaborators.user_id": member_oid.clone()},
+                                         doc! {"$pull": {"collaborators": {"user_id": member_oid}}, "$inc": {"version": 1}},
+                                         UpdateOptions::default()
+        ).await {
+            Ok(result) if result.modified_count > 0 => {
+                hub.disconnect(&trip._id, member_id.as_str());
+                Ok("Collaborator successfully removed".to_string())
+            },
+            Ok(_) => Err("Collaborator not found".to_string()),
+            Err(_) => Err("Error removing collaborator".to_string()),
+        }
+    }
+
+    // Trips the user was invited to and has not answered yet
+    pub async fn get_invitations(user_id: String, mut invitation_filter: InvitationFilter, db: &MongoDb) -> Result<Page<Trip>, String> {
+        let trip_collection = db.collection("trips");
+        let user_oid = ObjectId::with_string(user_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        let page_request = PageRequest::new(invitation_filter.cursor.take(), invitation_filter.limit)?;
+        let filter = doc! {
+            "deleted_at": Bson::Null,
+            "collaborators": {"$elemMatch": {"user_id": user_oid, "status": "pending"}}
+        };
+
+        find_page(&trip_collection, filter.clone(), filter, None, page_request).await
+    }
+}
+
+fn trip_and_user_oids(trip_id: String, user_id: String) -> Result<(ObjectId, ObjectId), String> {
+    let trip_oid = ObjectId::with_string(trip_id.as_str())
+        .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+    let user_oid = ObjectId::with_string(user_id.as_str())
+        .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+    Ok((trip_oid, user_oid))
+}
@@ -0,0 +1,161 @@
+use crate::models::trip::Trip;
+use crate::models::currency::{Money, CurrencyConverter};
+use crate::models::itinerary::ItineraryStop;
+
+use serde::{Deserialize, Serialize};
+use bson::oid::ObjectId;
+use chrono::NaiveDateTime;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    Overlap,
+    BeforeTripStart,
+    AfterTripEnd,
+    UnknownStart,
+    OverBudget,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictSeverity {
+    // The entry is not added
+    Error,
+    Warning,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EntryConflict {
+    pub kind: ConflictKind,
+    pub severity: ConflictSeverity,
+    pub message: String,
+    // The entry the new one overlaps with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<ObjectId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    // Amount above the budget limit, in the trip's currency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub over_by: Option<Money>,
+}
+
+impl ConflictKind {
+    pub fn severity(&self) -> ConflictSeverity {
+        match self {
+            ConflictKind::OverBudget => ConflictSeverity::Warning,
+            _ => ConflictSeverity::Error,
+        }
+    }
+}
+
+impl EntryConflict {
+    fn new(kind: ConflictKind, message: String) -> EntryConflict {
+        EntryConflict {
+            kind,
+            severity: kind.severity(),
+            message,
+            event_id: None,
+            start: None,
+            end: None,
+            over_by: None,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == ConflictSeverity::Error
+    }
+}
+
+// Checks a new entry against the trip's dates, its other entries and its budget limit.
+// Existing entries need their events expanded to know their durations
+pub fn entry_conflicts(trip: &Trip, stop: &ItineraryStop, converter: &CurrencyConverter) -> Result<Vec<EntryConflict>, String> {
+    let mut conflicts = Vec::new();
+
+    match (stop.start_at, stop.end_at) {
+        (Some(start), Some(end)) => {
+            conflicts.extend(range_conflicts(trip, start, end));
+            conflicts.extend(overlap_conflicts(trip, start, end));
+        },
+        _ => conflicts.push(EntryConflict::new(
+            ConflictKind::UnknownStart,
+            "Could not read the entry start date and hour".to_string()
+        )),
+    }
+
+    if let Some(conflict) = budget_conflict(trip, &stop.budget, converter)? {
+        conflicts.push(conflict);
+    }
+
+    Ok(conflicts)
+}
+
+fn range_conflicts(trip: &Trip, start: NaiveDateTime, end: NaiveDateTime) -> Vec<EntryConflict> {
+    let mut conflicts = Vec::new();
+
+    let first_day = trip.first_day();
+    if start.date() < first_day {
+        conflicts.push(EntryConflict::new(
+            ConflictKind::BeforeTripStart,
+            format!("Entry starts before the trip starts on {}", first_day.format("%Y-%m-%d"))
+        ));
+    }
+    let last_day = trip.last_day();
+    if end > last_day.succ().and_hms(0, 0, 0) {
+        conflicts.push(EntryConflict::new(
+            ConflictKind::AfterTripEnd,
+            format!("Entry ends after the trip ends on {}", last_day.format("%Y-%m-%d"))
+        ));
+    }
+
+    conflicts
+}
+
+fn overlap_conflicts(trip: &Trip, start: NaiveDateTime, end: NaiveDateTime) -> Vec<EntryConflict> {
+    trip.events.iter()
+        .map(|event_entry| ItineraryStop::from_entry(event_entry, trip.tz()))
+        .filter(|other| match (other.start_at, other.end_at) {
+            // Entries without a duration still clash when they start together
+            (Some(other_start), Some(other_end)) =>
+                (start < other_end && other_start < end) || start == other_start,
+            _ => false,
+        })
+        .map(|other| {
+            let name = other.name.clone().unwrap_or_else(|| other.event_id.to_hex());
+            let mut conflict = EntryConflict::new(
+                ConflictKind::Overlap,
+                format!("Entry overlaps with {}", name)
+            );
+            conflict.event_id = Some(other.event_id);
+            conflict.start = other.start;
+            conflict.end = other.end;
+            conflict
+        })
+        .collect()
+}
+
+fn budget_conflict(trip: &Trip, budget: &Money, converter: &CurrencyConverter) -> Result<Option<EntryConflict>, String> {
+    let budget_limit = match trip.budget_limit.as_ref() {
+        Some(budget_limit) => budget_limit,
+        None => return Ok(None),
+    };
+    let currency = trip.budget.currency.as_str();
+
+    let spent = trip.entries_budget(converter)?;
+    let limit = converter.convert(budget_limit, currency)?;
+    let cost = converter.convert(budget, currency)?;
+    let over_by = spent.amount + cost.amount - limit.amount;
+    if over_by <= 0 {
+        return Ok(None);
+    }
+
+    let remaining = Money::new((limit.amount - spent.amount).max(0), currency);
+    let mut conflict = EntryConflict::new(
+        ConflictKind::OverBudget,
+        format!("Entry costs more than the remaining budget of {} {}", remaining.to_decimal(), currency)
+    );
+    conflict.over_by = Some(Money::new(over_by, currency));
+
+    Ok(Some(conflict))
+}
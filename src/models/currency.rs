@@ -0,0 +1,384 @@
+use crate::MongoDb;
+
+use serde::{Deserialize, Serialize};
+use bson::oid::ObjectId;
+use mongodb::bson::{doc, Bson};
+use mongodb::options::{FindOneOptions, FindOptions, UpdateOptions};
+use futures::stream::StreamExt;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive, RoundingStrategy};
+use std::collections::HashMap;
+
+// Supported ISO-4217 currencies and the number of digits of their minor unit
+const CURRENCIES: [(&str, u32); 24] = [
+    ("ARS", 2), ("AUD", 2), ("BHD", 3), ("BRL", 2), ("CAD", 2), ("CHF", 2),
+    ("CLP", 0), ("CNY", 2), ("COP", 2), ("EUR", 2), ("GBP", 2), ("HKD", 2),
+    ("INR", 2), ("JPY", 0), ("KRW", 0), ("KWD", 3), ("MXN", 2), ("NZD", 2),
+    ("PEN", 2), ("SGD", 2), ("THB", 2), ("TWD", 2), ("USD", 2), ("VND", 0),
+];
+
+// Amount expressed in the currency's minor unit, e.g. cents
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(from = "MoneyRepr")]
+pub struct Money {
+    pub amount: i64,
+    pub currency: String,
+}
+
+// Prices used to be plain floats in the base currency
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MoneyRepr {
+    Structured { amount: i64, currency: String },
+    Legacy(f64),
+}
+
+impl From<MoneyRepr> for Money {
+    fn from(repr: MoneyRepr) -> Money {
+        match repr {
+            MoneyRepr::Structured { amount, currency } => Money { amount, currency },
+            MoneyRepr::Legacy(value) => Money::from_major(value, base_currency().as_str()),
+        }
+    }
+}
+
+impl Money {
+    pub fn new(amount: i64, currency: &str) -> Money {
+        Money { amount, currency: currency.to_string() }
+    }
+
+    pub fn from_major(value: f64, currency: &str) -> Money {
+        Money::from_decimal(Decimal::from_f64(value).unwrap_or_default(), currency)
+    }
+
+    // Exact value in major units, 1050 USD cents are 10.50
+    pub fn to_decimal(&self) -> Decimal {
+        Decimal::new(self.amount, minor_unit_exponent(self.currency.as_str()).unwrap_or(2))
+    }
+
+    // Rounds half away from zero to the currency's minor unit
+    pub fn from_decimal(value: Decimal, currency: &str) -> Money {
+        let exponent = minor_unit_exponent(currency).unwrap_or(2);
+        let minor = (value * Decimal::from(10_i64.pow(exponent)))
+            .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero);
+        Money::new(minor.to_i64().unwrap_or(0), currency)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        validate_currency(self.currency.as_str())?;
+        if self.amount < 0 {
+            return Err("Amount cannot be negative".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn to_bson(&self) -> Bson {
+        Bson::Document(doc! {"amount": self.amount, "currency": self.currency.clone()})
+    }
+}
+
+pub fn minor_unit_exponent(currency: &str) -> Option<u32> {
+    CURRENCIES.iter()
+        .find(|(code, _)| *code == currency)
+        .map(|(_, exponent)| *exponent)
+}
+
+pub fn validate_currency(currency: &str) -> Result<(), String> {
+    match minor_unit_exponent(currency) {
+        Some(_) => Ok(()),
+        None => Err(format!("Unsupported currency: {}", currency)),
+    }
+}
+
+// Currency every rate is quoted against
+pub fn base_currency() -> String {
+    std::env::var("BASE_CURRENCY").unwrap_or_else(|_| "USD".to_string())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExchangeRate {
+    pub _id: ObjectId,
+    pub currency: String,
+    // Units of `currency` bought by one unit of the base currency
+    pub rate: f64,
+    pub updated_at: String,
+    pub updated_by: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RateUpdate {
+    pub currency: String,
+    pub rate: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CurrencyPreference {
+    pub currency: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CurrencyQuery {
+    pub currency: Option<String>,
+}
+
+impl ExchangeRate {
+    pub async fn get_rates(db: &MongoDb) -> Result<Vec<ExchangeRate>, String> {
+        let rate_collection = db.collection("exchange_rates");
+
+        let mut cursor = match rate_collection.find(doc! {}, FindOptions::default()).await {
+            Ok(cursor) => cursor,
+            Err(_) => return Err("Error finding exchange rates".to_string()),
+        };
+        let mut rates = Vec::new();
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) =>
+                    match bson::from_bson::<ExchangeRate>(bson::Bson::Document(document)) {
+                        Ok(rate) => rates.push(rate),
+                        Err(e) => println!("{:?}", e),
+                    },
+                Err(_) => println!("Error retrieving Document"),
+            }
+        }
+
+        Ok(rates)
+    }
+
+    pub async fn set_rate(rate_update: RateUpdate, admin_id: String, db: &MongoDb) -> Result<String, String> {
+        let rate_collection = db.collection("exchange_rates");
+        let user_collection = db.collection("users");
+        let admin_oid = ObjectId::with_string(admin_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        validate_currency(rate_update.currency.as_str())?;
+        if rate_update.currency == base_currency() {
+            return Err("The base currency rate is always 1".to_string());
+        }
+        if !(rate_update.rate > 0.0 && rate_update.rate.is_finite()) {
+            return Err("Rate must be a positive number".to_string());
+        }
+
+        match user_collection.find_one(
+            doc!{"_id": admin_oid.clone()},
+            FindOneOptions::default()
+        ).await.expect("Error finding user") {
+            Some(admin_found) => {
+                let admin_role = admin_found.get_str("role").expect("Error getting admin role");
+
+                match admin_role {
+                    "superadmin" | "admin" => {
+                        let upsert_options = UpdateOptions::builder()
+                            .upsert(true)
+                            .build();
+
+                        match rate_collection.update_one(
+                            doc! {"currency": rate_update.currency.clone()},
+                            doc! {"$set": {
+                                "rate": rate_update.rate,
+                                "updated_at": Utc::now().to_rfc3339(),
+                                "updated_by": admin_oid,
+                            }},
+                            upsert_options
+                        ).await {
+                            Ok(_) => {
+                                refresh_base_prices(rate_update.currency.as_str(), db).await?;
+                                Ok("Successfully updated exchange rate".to_string())
+                            },
+                            Err(_) => Err("Error updating exchange rate".to_string())
+                        }
+                    },
+                    _ => Err("Access Denied: user don't have sufficient privileges".to_string())
+                }
+            },
+            None => Err("User not found".to_string())
+        }
+    }
+}
+
+pub struct CurrencyConverter {
+    base: String,
+    rates: HashMap<String, Decimal>,
+}
+
+impl CurrencyConverter {
+    pub fn new(base: String, rates: HashMap<String, f64>) -> CurrencyConverter {
+        let mut rates: HashMap<String, Decimal> = rates.into_iter()
+            .filter_map(|(currency, rate)| Decimal::from_f64(rate).map(|rate| (currency, rate)))
+            .collect();
+        rates.insert(base.clone(), Decimal::ONE);
+        CurrencyConverter { base, rates }
+    }
+
+    pub async fn load(db: &MongoDb) -> Result<CurrencyConverter, String> {
+        let rates = ExchangeRate::get_rates(db).await?
+            .into_iter()
+            .map(|rate| (rate.currency, rate.rate))
+            .collect();
+
+        Ok(CurrencyConverter::new(base_currency(), rates))
+    }
+
+    pub fn convert(&self, money: &Money, currency: &str) -> Result<Money, String> {
+        if money.currency == currency {
+            return Ok(money.clone());
+        }
+
+        Ok(Money::from_decimal(self.convert_exact(money, currency)?, currency))
+    }
+
+    // Total of the amounts in `currency`, rounded once after adding them up
+    pub fn sum(&self, amounts: &[Money], currency: &str) -> Result<Money, String> {
+        let mut total = Decimal::ZERO;
+        for money in amounts {
+            total += self.convert_exact(money, currency)?;
+        }
+
+        Ok(Money::from_decimal(total, currency))
+    }
+
+    pub fn to_base(&self, money: &Money) -> Result<Money, String> {
+        self.convert(money, self.base.as_str())
+    }
+
+    // Unrounded value in major units of `currency`
+    fn convert_exact(&self, money: &Money, currency: &str) -> Result<Decimal, String> {
+        let from_rate = self.rate(money.currency.as_str())?;
+        let to_rate = self.rate(currency)?;
+
+        Ok(money.to_decimal() / from_rate * to_rate)
+    }
+
+    fn rate(&self, currency: &str) -> Result<Decimal, String> {
+        match self.rates.get(currency) {
+            Some(rate) => Ok(*rate),
+            None => Err(format!("No exchange rate for {}", currency)),
+        }
+    }
+}
+
+// Currency a response should be shown in: explicit request first, then the user's preference
+pub async fn resolve_currency(requested: Option<String>, user_id: Option<String>, db: &MongoDb) -> Result<Option<String>, String> {
+    if let Some(currency) = requested {
+        validate_currency(currency.as_str())?;
+        return Ok(Some(currency));
+    }
+
+    let user_oid = match user_id.map(|id| ObjectId::with_string(id.as_str())) {
+        Some(Ok(oi)) => oi,
+        _ => return Ok(None),
+    };
+    let user_collection = db.collection("users");
+
+    match user_collection.find_one(doc! {"_id": user_oid}, FindOneOptions::default())
+        .await.expect("Error finding user") {
+        Some(user_found) => Ok(user_found.get_str("preferred_currency").ok().map(|s| s.to_string())),
+        None => Ok(None),
+    }
+}
+
+// Events keep their price and trips their budget in the base currency to filter and sort
+// across currencies
+async fn refresh_base_prices(currency: &str, db: &MongoDb) -> Result<(), String> {
+    let event_collection = db.collection("events");
+    let trip_collection = db.collection("trips");
+    let converter = CurrencyConverter::load(db).await?;
+
+    let mut cursor = match event_collection.find(doc! {"price.currency": currency}, FindOptions::default()).await {
+        Ok(cursor) => cursor,
+        Err(_) => return Err("Error finding events".to_string()),
+    };
+    while let Some(result) = cursor.next().await {
+        if let Ok(document) = result {
+            let price = match document.get("price").cloned().map(bson::from_bson::<Money>) {
+                Some(Ok(price)) => price,
+                _ => continue,
+            };
+            let price_base = converter.to_base(&price)?;
+            event_collection.update_one(doc! {"_id": document.get("_id").cloned().unwrap_or(Bson::Null)},
+                                        doc! {"$set": {"price_base": price_base.amount}},
+                                        UpdateOptions::default()
+            ).await.map_err(|_| "Error updating event price".to_string())?;
+        }
+    }
+
+    let mut cursor = match trip_collection.find(doc! {"budget.currency": currency}, FindOptions::default()).await {
+        Ok(cursor) => cursor,
+        Err(_) => return Err("Error finding trips".to_string()),
+    };
+    while let Some(result) = cursor.next().await {
+        if let Ok(document) = result {
+            let budget = match document.get("budget").cloned().map(bson::from_bson::<Money>) {
+                Some(Ok(budget)) => budget,
+                _ => continue,
+            };
+            let budget_base = converter.to_base(&budget)?;
+            trip_collection.update_one(doc! {"_id": document.get("_id").cloned().unwrap_or(Bson::Null)},
+                                       doc! {"$set": {"budget_base": budget_base.amount}},
+                                       UpdateOptions::default()
+            ).await.map_err(|_| "Error updating trip budget".to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+// Rewrite prices and budgets stored as floats into the base currency's minor unit
+pub async fn migrate_legacy_amounts(db: &MongoDb) -> Result<String, String> {
+    let event_collection = db.collection("events");
+    let trip_collection = db.collection("trips");
+    let base = base_currency();
+    let mut migrated = 0;
+
+    let mut cursor = event_collection.find(doc! {"price": {"$type": "number"}}, FindOptions::default())
+        .await.map_err(|_| "Error finding events".to_string())?;
+    while let Some(Ok(document)) = cursor.next().await {
+        let value = document.get("price").and_then(Bson::as_f64)
+            .or_else(|| document.get("price").and_then(Bson::as_i32).map(f64::from))
+            .unwrap_or(0.0);
+        let price = Money::from_major(value, base.as_str());
+        event_collection.update_one(doc! {"_id": document.get("_id").cloned().unwrap_or(Bson::Null)},
+                                    doc! {"$set": {"price": price.to_bson(), "price_base": price.amount}},
+                                    UpdateOptions::default()
+        ).await.map_err(|_| "Error updating event".to_string())?;
+        migrated += 1;
+    }
+
+    let mut cursor = trip_collection.find(doc! {}, FindOptions::default())
+        .await.map_err(|_| "Error finding trips".to_string())?;
+    while let Some(Ok(document)) = cursor.next().await {
+        let mut update = doc! {};
+        if let Some(Bson::Double(value)) = document.get("budget") {
+            let budget = Money::from_major(*value, base.as_str());
+            update.insert("budget_base", budget.amount);
+            update.insert("budget", budget.to_bson());
+        }
+        if let Ok(events) = document.get_array("events") {
+            let entries: Vec<Bson> = events.iter().map(|entry| match entry {
+                Bson::Document(entry_doc) => {
+                    let mut entry_doc = entry_doc.clone();
+                    if let Some(Bson::Double(value)) = entry_doc.get("budget") {
+                        let budget = Money::from_major(*value, base.as_str());
+                        entry_doc.insert("budget", budget.to_bson());
+                    }
+                    Bson::Document(entry_doc)
+                },
+                other => other.clone(),
+            }).collect();
+            if &entries != events {
+                update.insert("events", entries);
+            }
+        }
+
+        if !update.is_empty() {
+            trip_collection.update_one(doc! {"_id": document.get("_id").cloned().unwrap_or(Bson::Null)},
+                                       doc! {"$set": update},
+                                       UpdateOptions::default()
+            ).await.map_err(|_| "Error updating trip".to_string())?;
+            migrated += 1;
+        }
+    }
+
+    Ok(format!("Migrated {} documents to {} minor units", migrated, base))
+}
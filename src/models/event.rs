@@ -1,19 +1,28 @@
 use crate::MongoDb;
-use crate::utils::custom_visitors::ObjectIdVisitor;
+use crate::utils::custom_visitors::{ObjectIdVisitor, DurationVisitor, OptionalDurationVisitor};
+use crate::models::opening_hours::{OpeningHours, Closure, validate_duration, visit_warnings};
+use crate::models::currency::{Money, CurrencyConverter, base_currency};
+use crate::models::tag::{Taxonomy, TagKind};
+use crate::models::place::{Country, City};
+use crate::models::report::{ContentType, ModerationStatus, set_moderation_status};
+use crate::models::trash::soft_delete;
+use crate::models::trip::{Trip, EventSnapshot};
+use crate::models::user::User;
+use crate::models::audit::{AuditAction, AuditEntry, AuditRecord};
+use crate::utils::pagination::{find_page, Page, PageRequest};
+use crate::utils::etag::{VERSION_MISMATCH, version_filter};
 
 use serde::{de, Deserialize, Serialize};
 use bson::oid::ObjectId;
 use mongodb::bson::{Bson, doc, Document};
+use chrono::{NaiveDateTime, Utc};
 use mongodb::options::{
     InsertOneOptions,
-    FindOptions,
     FindOneOptions,
     FindOneAndUpdateOptions,
     CountOptions,
-    UpdateOptions,
     ReturnDocument
 };
-use futures::stream::StreamExt;
 
 // Event struct to Retrieve and Create
 #[derive(Serialize, Deserialize, Debug)]
@@ -24,13 +33,39 @@ pub struct Event {
     pub tags: Vec<String>,
     pub personal_type: String,
     pub rating: Option<f32>,
+    #[serde(default)]
+    pub review_count: i32,
+    #[serde(default)]
+    pub save_count: i32,
+    // Only filled for authenticated requests, never stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saved: Option<bool>,
     pub country: String,
     pub city: String,
-    pub price: f32,
-    pub duration: String,
+    pub price: Money,
+    // Price converted to the base currency, used to filter and sort
+    #[serde(default)]
+    pub price_base: i64,
+    // Price in the requester's currency, never stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub converted_price: Option<Money>,
+    // Minutes
+    #[serde(deserialize_with = "duration_minutes")]
+    pub duration: i32,
+    #[serde(default)]
+    pub opening_hours: Option<OpeningHours>,
+    #[serde(default)]
+    pub closures: Vec<Closure>,
     pub location: Option<Vec<f64>>,
     pub image: String,
     pub private: bool,
+    #[serde(default)]
+    pub moderation_status: ModerationStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moderation_reason: Option<String>,
+    // Grows with every change to the event, counters left aside
+    #[serde(default)]
+    pub version: i64,
     pub user_id: ObjectId,
 }
 
@@ -43,11 +78,13 @@ pub struct EventUpdate {
     description: Option<String>,
     tags: Option<Vec<String>>,
     personal_type: Option<String>,
-    rating: Option<f32>,
     country: Option<String>,
     city: Option<String>,
-    price: Option<f32>,
-    duration: Option<String>,
+    price: Option<Money>,
+    #[serde(default, deserialize_with = "optional_duration_minutes")]
+    duration: Option<i32>,
+    opening_hours: Option<OpeningHours>,
+    closures: Option<Vec<Closure>>,
     location: Option<Vec<f64>>,
     image: Option<String>,
     private: Option<bool>,
@@ -55,17 +92,62 @@ pub struct EventUpdate {
     user_id: ObjectId,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct EventFilter {
-    pub offset: i64,
-    pub limit: i64,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
     pub tags: Option<String>,
     pub personal_type: Option<String>,
     pub rating: Option<f32>,
+    pub rating_min: Option<f32>,
+    // Minor units of `currency`, or of the base currency when it is not given
+    pub price_min: Option<i64>,
+    pub price_max: Option<i64>,
+    pub currency: Option<String>,
     pub country: Option<String>,
     pub city: Option<String>,
     pub user_id: Option<String>,
     pub include_private: Option<bool>,
+    pub sort: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    // Authenticated user, owners also see their hidden events
+    #[serde(skip)]
+    pub requester_id: Option<String>,
+}
+
+// Sort orders accepted by the event listing
+#[derive(Debug, PartialEq)]
+pub enum EventSort {
+    Rating,
+    Price,
+    Newest,
+    Distance,
+    Saves,
+}
+
+impl EventSort {
+    pub fn parse(sort_key: &str) -> Result<EventSort, String> {
+        match sort_key {
+            "rating" => Ok(EventSort::Rating),
+            "price" => Ok(EventSort::Price),
+            "newest" => Ok(EventSort::Newest),
+            "distance" => Ok(EventSort::Distance),
+            "saves" => Ok(EventSort::Saves),
+            _ => Err(format!("Unknown sort key: {}", sort_key)),
+        }
+    }
+
+    // Distance is resolved by a $near query instead of a sort document
+    pub fn to_sort_doc(&self) -> Option<Document> {
+        match self {
+            EventSort::Rating => Some(doc! {"rating": -1, "_id": -1}),
+            EventSort::Price => Some(doc! {"price_base": 1, "_id": 1}),
+            EventSort::Newest => Some(doc! {"_id": -1}),
+            EventSort::Saves => Some(doc! {"save_count": -1, "_id": -1}),
+            EventSort::Distance => None,
+        }
+    }
 }
 
 impl Event {
@@ -74,7 +156,7 @@ impl Event {
 
         match ObjectId::with_string(event_id.as_str().as_ref()) {
             Ok(oi) => {
-                match event_collection.find_one(doc! {"_id": oi}, FindOneOptions::default())
+                match event_collection.find_one(doc! {"_id": oi, "deleted_at": Bson::Null}, FindOneOptions::default())
                     .await.expect("Error finding event") {
 
                     Some(event_found) => {
@@ -90,101 +172,166 @@ impl Event {
         }
     }
 
-    pub async fn get_filtered_events(event_filter: EventFilter, db: &MongoDb) -> Vec<Event> {
+    pub async fn get_filtered_events(mut event_filter: EventFilter, db: &MongoDb) -> Result<Page<Event>, String> {
         let event_collection = db.collection("events");
 
-        // Create a custom find option
-        let find_options = FindOptions::builder()
-                            .limit(event_filter.limit)
-                            .skip(event_filter.offset)
-                            .build();
+        let sort = match event_filter.sort.as_ref() {
+            Some(s) => Some(EventSort::parse(s.as_str())?),
+            None => None,
+        };
+        let page_request = PageRequest::new(event_filter.cursor.take(), event_filter.limit)?;
+        price_range_to_base(&mut event_filter, db).await?;
+        canonical_filter_terms(&mut event_filter, db).await?;
+
+        // Sorting by distance needs a reference point to run the $near query
+        let near = match sort {
+            Some(EventSort::Distance) => match (event_filter.lng, event_filter.lat) {
+                (Some(lng), Some(lat)) => Some(vec![lng, lat]),
+                _ => return Err("Sorting by distance requires lat and lng".to_string()),
+            },
+            _ => None,
+        };
 
         // Get custom filter
-        let filter = get_find_filter(event_filter);
-
-        let mut cursor = event_collection.find(filter, find_options).await.expect("Error finding collection");
-        let mut events = Vec::new();
-        while let Some(result) = cursor.next().await {
-            match result {
-                Ok(document) =>
-                    match bson::from_bson::<Event>(bson::Bson::Document(document)) {
-                        Ok(event) => events.push(event),
-                        Err(e) => println!("{:?}", e),
-                    },
-                Err(_) => println!("Error retrieving Document"),
-            }
+        let count_filter = get_find_filter(event_filter)?;
+        let mut filter = count_filter.clone();
+        if let Some(point) = near {
+            filter.insert("location", doc! {"$near": point});
         }
 
-        events
+        find_page(&event_collection,
+                  filter,
+                  count_filter,
+                  sort.and_then(|s| s.to_sort_doc()),
+                  page_request
+        ).await
     }
 
-    pub async fn count_filtered_events(event_filter: EventFilter, db: &MongoDb) -> Result<i64, String> {
+    pub async fn count_filtered_events(mut event_filter: EventFilter, db: &MongoDb) -> Result<i64, String> {
         let event_collection = db.collection("events");
-
-        // Create a custom find option
-        let count_options = CountOptions::builder()
-            .limit(event_filter.limit)
-            .skip(event_filter.offset)
-            .build();
+        price_range_to_base(&mut event_filter, db).await?;
+        canonical_filter_terms(&mut event_filter, db).await?;
 
         // Get custom filter
-        let filter = get_find_filter(event_filter);
+        let filter = get_find_filter(event_filter)?;
 
-        match event_collection.count_documents(filter, count_options).await {
+        match event_collection.count_documents(filter, CountOptions::default()).await {
             Ok(count) => Ok(count),
             Err(_) => Err("Error counting document".to_string()),
         }
     }
 
-    pub async fn create(mut event: Event, db: &MongoDb) -> ObjectId {
+    pub async fn create(mut event: Event, db: &MongoDb) -> Result<ObjectId, String> {
         let event_collection = db.collection("events");
 
-        // If the Event location is empty create a default one
+        validate_duration(event.duration)?;
+        validate_schedule(event.opening_hours.as_ref(), &event.closures)?;
+        event.price.validate()?;
+        event.price_base = CurrencyConverter::load(db).await?.to_base(&event.price)?.amount;
+
+        // Only canonical terms are stored so filters match every spelling
+        let taxonomy = Taxonomy::load(db).await?;
+        event.tags = taxonomy.canonical_tags(&event.tags)?;
+        event.personal_type = taxonomy.canonical(event.personal_type.as_str(), TagKind::PersonalType)?;
+
+        let country = Country::resolve(event.country.as_str(), db).await?;
+        let city = City::resolve(event.city.as_str(), Some(country.code.as_str()), db).await?;
+        event.country = country.code;
+        event.city = city.name;
+
+        // If the Event location is empty default to the city's coordinates
         match event.location.clone() {
-            None => event.location = Some(vec![0.0, 0.0]),
+            None => event.location = Some(city.location),
             Some(_) => (),
         }
 
-        (*event_collection
+        Ok((*event_collection
             .insert_one(event.to_doc().await, InsertOneOptions::default())
             .await
             .expect("Error inserting Event")
             .inserted_id
             .as_object_id()
             .unwrap()
-        ).clone()
+        ).clone())
     }
 
-    pub async fn force_private(event_id: String, admin_id: String, db: &MongoDb) -> Result<String, String> {
-        let event_collection = db.collection("events");
-        let user_collection = db.collection("users");
-        let event_oid = ObjectId::with_string(event_id.as_str().as_ref())
-            .expect("Cannot convert given string to ObjectId");
-        let admin_oid = ObjectId::with_string(admin_id.as_str().as_ref())
-            .expect("Cannot convert given string to ObjectId");
-
-        match user_collection.find_one(
-            doc!{"_id": admin_oid},
-            FindOneOptions::default()
-        ).await.expect("Error finding user") {
-            Some(admin_found) => {
-                let admin_role = admin_found.get_str("role").expect("Error getting admin role");
-
-                match admin_role {
-                    "superadmin" | "admin" => {
-                        match event_collection.update_one(
-                            doc!{"_id": event_oid},
-                            doc!{"$set": {"private": true}},
-                            UpdateOptions::default()
-                        ).await {
-                            Ok(_) => Ok("Successfully changed event to private".to_string()),
-                            Err(_) => Err("Error changing event to private".to_string())
-                        }
-                    },
-                    _ => Err("Access Denied: user don't have sufficient privileges".to_string())
-                }
-            },
-            None => Err("User not found".to_string())
+    // Fill converted_price for the requester's currency
+    pub async fn convert_prices(events: &mut [Event], currency: String, db: &MongoDb) -> Result<(), String> {
+        let converter = CurrencyConverter::load(db).await?;
+        for event in events.iter_mut() {
+            event.converted_price = Some(converter.convert(&event.price, currency.as_str())?);
+        }
+
+        Ok(())
+    }
+
+    // Warnings for a visit to this event starting at the given local time
+    pub fn visit_warnings(&self, start: NaiveDateTime, duration: i32) -> Vec<String> {
+        visit_warnings(self.opening_hours.as_ref(), &self.closures, start, duration)
+    }
+
+    pub async fn force_private(event_id: String, reason: Option<String>, admin_id: String, ip: Option<String>, db: &MongoDb) -> Result<String, String> {
+        match set_moderation_status(ContentType::Event, event_id, ModerationStatus::Hidden, reason, admin_id, ip, db).await {
+            Ok(_) => Ok("Successfully hid event".to_string()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn unhide(event_id: String, admin_id: String, ip: Option<String>, db: &MongoDb) -> Result<String, String> {
+        match set_moderation_status(ContentType::Event, event_id, ModerationStatus::Visible, None, admin_id, ip, db).await {
+            Ok(_) => Ok("Successfully restored event".to_string()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Owners and admins can delete. Trip entries pointing to the event keep a snapshot
+    // of it and are flagged as orphaned
+    pub async fn delete_event(event_id: String, user_id: String, ip: Option<String>, db: &MongoDb) -> Result<String, String> {
+        let user_oid = ObjectId::with_string(user_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        let event = Event::get_event(event_id, db).await?;
+        let event_oid = event._id.clone().expect("Stored event without _id");
+        let owner_id = if event.user_id == user_oid {
+            Some(user_oid.clone())
+        } else {
+            User::check_admin(user_id, db).await?;
+            None
+        };
+
+        let event_deleted = soft_delete(ContentType::Event, event_oid.clone(), owner_id, user_oid.clone(), db).await?;
+        let snapshot = EventSnapshot {
+            name: event.name,
+            price: event.price,
+            deleted_at: Utc::now().to_rfc3339(),
+        };
+        let trips_affected = Trip::orphan_entries(event_oid.clone(), snapshot, db).await?;
+
+        AuditEntry::record(AuditRecord {
+            action: AuditAction::DeleteEvent,
+            actor_id: Some(user_oid),
+            target_type: "event",
+            target_id: Some(event_oid),
+            before: Some(event_deleted),
+            after: None,
+            ip,
+        }, db).await?;
+
+        Ok(format!("Event moved to trash, {} trips affected", trips_affected))
+    }
+
+    // The distance sort relies on a 2d index over the stored coordinates
+    pub async fn create_indexes(db: &MongoDb) -> Result<(), String> {
+        let command = doc! {
+            "createIndexes": "events",
+            "indexes": [
+                {"key": {"location": "2d"}, "name": "location_2d"},
+            ]
+        };
+
+        match db.run_command(command, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Error creating event indexes".to_string()),
         }
     }
 
@@ -194,21 +341,36 @@ impl Event {
             "description": self.description.clone(),
             "tags": self.tags.clone(),
             "personal_type": self.personal_type.clone(),
-            "rating": self.rating.unwrap_or_else(|| 5.0).clone(),
+            // Ratings are computed from reviews, never taken from the client
+            "rating": Bson::Null,
+            "review_count": 0,
+            "save_count": 0,
             "country": self.country.clone(),
             "city": self.city.clone(),
-            "price": self.price.clone(),
-            "duration": self.duration.clone(),
+            "price": self.price.to_bson(),
+            "price_base": self.price_base,
+            "duration": self.duration,
+            "opening_hours": bson::to_bson(&self.opening_hours).unwrap_or(Bson::Null),
+            "closures": bson::to_bson(&self.closures).unwrap_or(Bson::Null),
             "location": self.location.as_ref().unwrap().clone(),
             "image": self.image.clone(),
             "private": self.private.clone(),
+            "moderation_status": "visible",
+            "version": 1,
             "user_id": self.user_id.clone(),
         }
     }
+
+    // Hidden events are only shown to their owner
+    pub fn visible_to(&self, user_id: Option<&String>) -> bool {
+        self.moderation_status == ModerationStatus::Visible
+            || user_id.is_some_and(|id| *id == self.user_id.to_hex())
+    }
 }
 
 impl EventUpdate {
-    pub async fn update(event: EventUpdate, db: &MongoDb) -> Result<Event, String> {
+    // With an expected version the update only goes through if nobody changed the event since
+    pub async fn update(event: EventUpdate, expected_version: Option<i64>, db: &MongoDb) -> Result<Event, String> {
         let event_collection = db.collection("events");
 
         // Check which field is being updated
@@ -221,32 +383,61 @@ impl EventUpdate {
             Some(s) => update.insert("description", s),
             None => Some(Bson::default())
         };
-        match event.tags {
-            Some(v) => update.insert("tags", v),
-            None => Some(Bson::default())
+        let taxonomy = if event.tags.is_some() || event.personal_type.is_some() {
+            Some(Taxonomy::load(db).await?)
+        } else {
+            None
         };
-        match event.personal_type {
-            Some(s) => update.insert("personal_type", s),
-            None => Some(Bson::default())
+        match (event.tags, taxonomy.as_ref()) {
+            (Some(v), Some(t)) => update.insert("tags", t.canonical_tags(&v)?),
+            _ => Some(Bson::default())
         };
-        match event.rating {
-            Some(f) => update.insert("rating", f),
-            None => Some(Bson::default())
+        match (event.personal_type, taxonomy.as_ref()) {
+            (Some(s), Some(t)) => update.insert("personal_type", t.canonical(s.as_str(), TagKind::PersonalType)?),
+            _ => Some(Bson::default())
         };
-        match event.country {
-            Some(s) => update.insert("country", s),
+        // Country and city are validated together, the missing one comes from the stored event
+        if event.country.is_some() || event.city.is_some() {
+            let (country_name, city_name) = match (event.country, event.city) {
+                (Some(country), Some(city)) => (country, city),
+                (country, city) => {
+                    let stored = Event::get_event(event._id.to_hex(), db).await?;
+                    (country.unwrap_or(stored.country), city.unwrap_or(stored.city))
+                },
+            };
+            let country = Country::resolve(country_name.as_str(), db).await?;
+            let city = City::resolve(city_name.as_str(), Some(country.code.as_str()), db).await?;
+            update.insert("country", country.code);
+            update.insert("city", city.name);
+        }
+        match event.price {
+            Some(m) => {
+                m.validate()?;
+                let price_base = CurrencyConverter::load(db).await?.to_base(&m)?;
+                update.insert("price_base", price_base.amount);
+                update.insert("price", m.to_bson())
+            },
             None => Some(Bson::default())
         };
-        match event.city {
-            Some(s) => update.insert("city", s),
+        match event.duration {
+            Some(i) => {
+                validate_duration(i)?;
+                update.insert("duration", i)
+            },
             None => Some(Bson::default())
         };
-        match event.price {
-            Some(f) => update.insert("price", f),
+        match event.opening_hours {
+            Some(o) => {
+                validate_schedule(Some(&o), &[])?;
+                update.insert("opening_hours", bson::to_bson(&o).unwrap_or(Bson::Null))
+            },
             None => Some(Bson::default())
         };
-        match event.duration {
-            Some(s) => update.insert("duration", s),
+        match event.closures {
+            Some(v) => {
+                validate_schedule(None, &v)?;
+                update.insert("closures", bson::to_bson(&v).unwrap_or(Bson::Null))
+            },
             None => Some(Bson::default())
         };
         match event.location {
@@ -266,7 +457,12 @@ impl EventUpdate {
             .return_document(ReturnDocument::After)
             .build();
 
-        match event_collection.find_one_and_update(doc! {"_id": event._id}, doc! {"$set": update},
+        let mut filter = doc! {"_id": event._id.clone(), "deleted_at": Bson::Null};
+        if let Some(version) = expected_version {
+            filter.insert("version", version_filter(version));
+        }
+
+        match event_collection.find_one_and_update(filter, doc! {"$set": update, "$inc": {"version": 1}},
                                                    find_update_options)
             .await.expect("Error updating Event") {
 
@@ -276,14 +472,21 @@ impl EventUpdate {
                     Err(_e) => Err("Incorrect struct, expecting event struct".to_string()),
                 }
             },
+            // The event is still there, someone else changed it first
+            None if expected_version.is_some() => match Event::get_event(event._id.to_hex(), db).await {
+                Ok(_) => Err(VERSION_MISMATCH.to_string()),
+                Err(e) => Err(e),
+            },
             None => Err("Event not found".to_string())
         }
     }
 }
 
 // Generate find's filter
-fn get_find_filter(event_filter: EventFilter) -> Document {
+pub(crate) fn get_find_filter(event_filter: EventFilter) -> Result<Document, String> {
     let mut filter = doc! {};
+    let own_events = event_filter.requester_id.is_some() && event_filter.requester_id == event_filter.user_id;
+    filter.insert("deleted_at", Bson::Null);
     match event_filter.user_id {
         Some(s) => {
             match ObjectId::with_string(s.as_str().as_ref()) {
@@ -304,16 +507,41 @@ fn get_find_filter(event_filter: EventFilter) -> Document {
         Some(s) => filter.insert("personal_type", s),
         None => Some(Bson::default()),
     };
-    match event_filter.rating {
-        Some(f) => filter.insert("rating", f),
+    match (event_filter.rating, event_filter.rating_min) {
+        (Some(_), Some(_)) => return Err("rating and rating_min cannot be combined".to_string()),
+        (Some(f), None) => filter.insert("rating", f),
+        (None, Some(f)) => filter.insert("rating", doc! {"$gte": f}),
+        (None, None) => Some(Bson::default()),
+    };
+    let mut price_range = doc! {};
+    match event_filter.price_min {
+        Some(f) => price_range.insert("$gte", f),
         None => Some(Bson::default()),
     };
+    match event_filter.price_max {
+        Some(f) => price_range.insert("$lte", f),
+        None => Some(Bson::default()),
+    };
+    if let (Some(min), Some(max)) = (event_filter.price_min, event_filter.price_max) {
+        if min > max {
+            return Err("price_min cannot be greater than price_max".to_string());
+        }
+    }
+    if !price_range.is_empty() {
+        filter.insert("price_base", price_range);
+    }
     match event_filter.country {
-        Some(s) => filter.insert("country", s),
+        Some(v) => {
+            let country_list: Vec<&str> = v.split(",").collect();
+            filter.insert("country", doc! {"$in": country_list})
+        },
         None => Some(Bson::default()),
     };
     match event_filter.city {
-        Some(s) => filter.insert("city", s),
+        Some(v) => {
+            let city_list: Vec<&str> = v.split(",").collect();
+            filter.insert("city", doc! {"$in": city_list})
+        },
         None => Some(Bson::default()),
     };
     match event_filter.include_private {
@@ -324,8 +552,99 @@ fn get_find_filter(event_filter: EventFilter) -> Document {
         },
         None => (),
     }
+    // Hidden events only show up when owners list their own events
+    if !own_events {
+        filter.insert("moderation_status", doc! {"$ne": "hidden"});
+    }
+
+    Ok(filter)
+}
+
+// Filters accept synonyms, translations and place names, events only store canonical values
+async fn canonical_filter_terms(event_filter: &mut EventFilter, db: &MongoDb) -> Result<(), String> {
+    if let Some(countries) = event_filter.country.as_ref() {
+        let mut codes = Vec::new();
+        for country in countries.split(',') {
+            match Country::resolve(country, db).await {
+                Ok(found) => codes.push(found.code),
+                Err(_) => codes.push(country.to_string()),
+            }
+        }
+        event_filter.country = Some(codes.join(","));
+    }
+    if let Some(cities) = event_filter.city.as_ref() {
+        let mut names = Vec::new();
+        for city in cities.split(',') {
+            match City::resolve(city, None, db).await {
+                Ok(found) => names.push(found.name),
+                Err(_) => names.push(city.to_string()),
+            }
+        }
+        event_filter.city = Some(names.join(","));
+    }
+    if event_filter.tags.is_none() && event_filter.personal_type.is_none() {
+        return Ok(());
+    }
+
+    let taxonomy = Taxonomy::load(db).await?;
+    if let Some(tags) = event_filter.tags.as_ref() {
+        let canonical_tags: Vec<String> = tags.split(',')
+            .map(|tag| taxonomy.canonical_or_term(tag, TagKind::Tag))
+            .collect();
+        event_filter.tags = Some(canonical_tags.join(","));
+    }
+    if let Some(personal_type) = event_filter.personal_type.as_ref() {
+        event_filter.personal_type = Some(taxonomy.canonical_or_term(personal_type, TagKind::PersonalType));
+    }
+
+    Ok(())
+}
+
+// Price bounds are given in the requester's currency but stored prices are compared in the base one
+async fn price_range_to_base(event_filter: &mut EventFilter, db: &MongoDb) -> Result<(), String> {
+    let currency = match event_filter.currency.clone() {
+        Some(c) if c != base_currency() => c,
+        _ => return Ok(()),
+    };
+    if event_filter.price_min.is_none() && event_filter.price_max.is_none() {
+        return Ok(());
+    }
+
+    let converter = CurrencyConverter::load(db).await?;
+    if let Some(min) = event_filter.price_min {
+        event_filter.price_min = Some(converter.to_base(&Money::new(min, currency.as_str()))?.amount);
+    }
+    if let Some(max) = event_filter.price_max {
+        event_filter.price_max = Some(converter.to_base(&Money::new(max, currency.as_str()))?.amount);
+    }
+
+    Ok(())
+}
+
+fn validate_schedule(opening_hours: Option<&OpeningHours>, closures: &[Closure]) -> Result<(), String> {
+    if let Some(o) = opening_hours {
+        o.validate()?;
+    }
+    for closure in closures {
+        closure.validate()?;
+    }
+
+    Ok(())
+}
+
+// Deserialize numbers or legacy strings into minutes
+fn duration_minutes<'de, D>(deserializer: D) -> Result<i32, D::Error>
+    where
+        D: de::Deserializer<'de>,
+{
+    deserializer.deserialize_any(DurationVisitor)
+}
 
-    filter
+fn optional_duration_minutes<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
+    where
+        D: de::Deserializer<'de>,
+{
+    deserializer.deserialize_option(OptionalDurationVisitor)
 }
 
 // Deserialize the String and convert it to ObjectId
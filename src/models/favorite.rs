@@ -0,0 +1,201 @@
+use crate::MongoDb;
+use crate::models::event::Event;
+use crate::utils::pagination::{find_page, Page, PageRequest};
+
+use serde::{Deserialize, Serialize};
+use bson::oid::ObjectId;
+use mongodb::bson::{doc, Bson};
+use mongodb::options::{
+    InsertOneOptions,
+    FindOneOptions,
+    FindOptions,
+    DeleteOptions,
+    UpdateOptions
+};
+use futures::stream::StreamExt;
+use chrono::Utc;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Favorite {
+    pub _id: ObjectId,
+    pub user_id: ObjectId,
+    pub event_id: ObjectId,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct FavoriteFilter {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+impl Favorite {
+    pub async fn save(event_id: String, user_id: String, db: &MongoDb) -> Result<String, String> {
+        let favorite_collection = db.collection("favorites");
+        let event_collection = db.collection("events");
+        let event_oid = ObjectId::with_string(event_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+        let user_oid = ObjectId::with_string(user_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        match event_collection.find_one(doc! {"_id": event_oid.clone(), "deleted_at": Bson::Null}, FindOneOptions::default())
+            .await.expect("Error finding event") {
+            Some(_) => (),
+            None => return Err("Event not found".to_string()),
+        }
+
+        // Saving twice is a no-op so the counter stays accurate
+        if favorite_collection.find_one(
+            doc! {"user_id": user_oid.clone(), "event_id": event_oid.clone()},
+            FindOneOptions::default()
+        ).await.expect("Error finding favorite").is_some() {
+            return Ok("Event already saved".to_string());
+        }
+
+        let favorite_doc = doc! {
+            "user_id": user_oid,
+            "event_id": event_oid.clone(),
+            "created_at": Utc::now().to_rfc3339(),
+        };
+
+        match favorite_collection.insert_one(favorite_doc, InsertOneOptions::default()).await {
+            Ok(_) => (),
+            Err(_) => return Err("Error saving event".to_string()),
+        }
+
+        match event_collection.update_one(doc! {"_id": event_oid},
+                                          doc! {"$inc": {"save_count": 1}},
+                                          UpdateOptions::default()
+        ).await {
+            Ok(_) => Ok("Event successfully saved".to_string()),
+            Err(_) => Err("Error updating save count".to_string()),
+        }
+    }
+
+    pub async fn unsave(event_id: String, user_id: String, db: &MongoDb) -> Result<String, String> {
+        let favorite_collection = db.collection("favorites");
+        let event_collection = db.collection("events");
+        let event_oid = ObjectId::with_string(event_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+        let user_oid = ObjectId::with_string(user_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        let deleted_count = match favorite_collection.delete_one(
+            doc! {"user_id": user_oid, "event_id": event_oid.clone()},
+            DeleteOptions::default()
+        ).await {
+            Ok(result) => result.deleted_count,
+            Err(_) => return Err("Error removing saved event".to_string()),
+        };
+
+        if deleted_count == 0 {
+            return Err("Event was not saved".to_string());
+        }
+
+        match event_collection.update_one(doc! {"_id": event_oid},
+                                          doc! {"$inc": {"save_count": -1}},
+                                          UpdateOptions::default()
+        ).await {
+            Ok(_) => Ok("Event successfully removed from saved".to_string()),
+            Err(_) => Err("Error updating save count".to_string()),
+        }
+    }
+
+    pub async fn get_saved_events(user_id: String, mut favorite_filter: FavoriteFilter, db: &MongoDb) -> Result<Page<Event>, String> {
+        let favorite_collection = db.collection("favorites");
+        let event_collection = db.collection("events");
+        let user_oid = ObjectId::with_string(user_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        let page_request = PageRequest::new(favorite_filter.cursor.take(), favorite_filter.limit)?;
+        let filter = doc! {"user_id": user_oid};
+
+        let favorites: Page<Favorite> = find_page(&favorite_collection,
+                                                  filter.clone(),
+                                                  filter,
+                                                  Some(doc! {"_id": -1}),
+                                                  page_request
+        ).await?;
+
+        let event_ids: Vec<ObjectId> = favorites.items.iter()
+            .map(|favorite| favorite.event_id.clone())
+            .collect();
+
+        let mut cursor = event_collection.find(doc! {"_id": {"$in": event_ids.clone()}, "deleted_at": Bson::Null}, FindOptions::default())
+            .await.expect("Error finding collection");
+        let mut found_events = Vec::new();
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) =>
+                    match bson::from_bson::<Event>(bson::Bson::Document(document)) {
+                        Ok(event) => found_events.push(event),
+                        Err(e) => println!("{:?}", e),
+                    },
+                Err(_) => println!("Error retrieving Document"),
+            }
+        }
+
+        // Keep the order in which the events were saved
+        let mut events = Vec::new();
+        for event_id in event_ids {
+            if let Some(position) = found_events.iter().position(|event| event._id.as_ref() == Some(&event_id)) {
+                let mut event = found_events.swap_remove(position);
+                event.saved = Some(true);
+                events.push(event);
+            }
+        }
+
+        Ok(Page {
+            items: events,
+            next_cursor: favorites.next_cursor,
+            total: favorites.total,
+        })
+    }
+
+    // Fill the saved flag of the given events for the requesting user
+    pub async fn mark_saved(events: &mut [Event], user_id: String, db: &MongoDb) -> Result<(), String> {
+        let favorite_collection = db.collection("favorites");
+        let user_oid = ObjectId::with_string(user_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        let event_ids: Vec<ObjectId> = events.iter()
+            .filter_map(|event| event._id.clone())
+            .collect();
+
+        let mut cursor = match favorite_collection.find(
+            doc! {"user_id": user_oid, "event_id": {"$in": event_ids}},
+            FindOptions::default()
+        ).await {
+            Ok(cursor) => cursor,
+            Err(_) => return Err("Error finding saved events".to_string()),
+        };
+        let mut saved_ids = Vec::new();
+        while let Some(result) = cursor.next().await {
+            if let Ok(document) = result {
+                if let Ok(event_id) = document.get_object_id("event_id") {
+                    saved_ids.push(event_id.clone());
+                }
+            }
+        }
+
+        for event in events.iter_mut() {
+            event.saved = Some(event._id.as_ref().is_some_and(|id| saved_ids.contains(id)));
+        }
+
+        Ok(())
+    }
+
+    pub async fn create_indexes(db: &MongoDb) -> Result<(), String> {
+        let command = doc! {
+            "createIndexes": "favorites",
+            "indexes": [
+                {"key": {"user_id": 1, "event_id": 1}, "name": "user_event_unique", "unique": true},
+            ]
+        };
+
+        match db.run_command(command, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Error creating favorite indexes".to_string()),
+        }
+    }
+}
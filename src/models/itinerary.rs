@@ -0,0 +1,276 @@
+use crate::MongoDb;
+use crate::models::trip::{Trip, EventEntry};
+use crate::models::currency::{Money, CurrencyConverter};
+use crate::models::opening_hours::parse_time;
+use crate::models::routing::{Router, TravelProfile};
+
+use serde::{Deserialize, Serialize};
+use bson::oid::ObjectId;
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
+
+pub const DEFAULT_DAY_START: &str = "08:00";
+pub const DEFAULT_DAY_END: &str = "22:00";
+
+pub(crate) const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ItineraryQuery {
+    // "HH:MM" bounds of each day, used to compute free time
+    pub day_start: Option<String>,
+    pub day_end: Option<String>,
+    // How the traveller moves between stops, walking by default
+    pub profile: Option<TravelProfile>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DayWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Itinerary {
+    pub trip_id: ObjectId,
+    pub days: Vec<ItineraryDay>,
+    // Entries outside the trip dates or with a start that cannot be read
+    pub unscheduled: Vec<ItineraryStop>,
+    pub total_spend: Money,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ItineraryDay {
+    // YYYY-MM-DD in the trip's local time
+    pub date: String,
+    pub stops: Vec<ItineraryStop>,
+    pub gaps: Vec<FreeGap>,
+    pub transfers: Vec<Transfer>,
+    pub spend: Money,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ItineraryStop {
+    pub event_id: ObjectId,
+    pub name: Option<String>,
+    // Local "YYYY-MM-DDTHH:MM", missing when the entry start cannot be read
+    pub start: Option<String>,
+    pub end: Option<String>,
+    // Minutes
+    pub duration: i32,
+    pub budget: Money,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Vec<f64>>,
+    pub unavailable: bool,
+    #[serde(skip)]
+    pub start_at: Option<NaiveDateTime>,
+    #[serde(skip)]
+    pub end_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct FreeGap {
+    pub from: String,
+    pub to: String,
+    pub minutes: i64,
+}
+
+// Moving from a stop to the next one of the same day
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Transfer {
+    pub from_event_id: ObjectId,
+    pub to_event_id: ObjectId,
+    pub profile: TravelProfile,
+    pub distance_meters: i64,
+    pub minutes: i64,
+    // Between the end of a stop and the start of the next, negative when they overlap
+    pub available_minutes: i64,
+    // False when the next stop cannot be reached in time
+    pub feasible: bool,
+}
+
+impl ItineraryQuery {
+    pub fn window(&self) -> Result<DayWindow, String> {
+        let start = parse_time(self.day_start.as_deref().unwrap_or(DEFAULT_DAY_START))?;
+        let end = parse_time(self.day_end.as_deref().unwrap_or(DEFAULT_DAY_END))?;
+        if start >= end {
+            return Err("day_start must be before day_end".to_string());
+        }
+
+        Ok(DayWindow { start, end })
+    }
+}
+
+impl Itinerary {
+    pub async fn get(trip_id: String,
+                     query: ItineraryQuery,
+                     viewer_id: Option<String>,
+                     router: &dyn Router,
+                     db: &MongoDb
+    ) -> Result<Itinerary, String> {
+        let window = query.window()?;
+        let profile = query.profile.unwrap_or_default();
+        let trip = Trip::get_trip(trip_id, db).await?.visible(viewer_id.as_ref())?;
+
+        // Event names, durations and locations come from the expanded entries
+        let mut trips = vec![trip];
+        Trip::expand_events(&mut trips, viewer_id.as_ref(), db).await?;
+        let converter = CurrencyConverter::load(db).await?;
+
+        Itinerary::build(&trips[0], window, profile, &converter, router)
+    }
+
+    // Entries grouped per local day and sorted by start, spend is in the trip's currency
+    pub fn build(trip: &Trip,
+                 window: DayWindow,
+                 profile: TravelProfile,
+                 converter: &CurrencyConverter,
+                 router: &dyn Router
+    ) -> Result<Itinerary, String> {
+        let first_day = trip.first_day();
+        let last_day = trip.last_day();
+        if last_day < first_day {
+            return Err("Trip ends before it starts".to_string());
+        }
+        let currency = trip.budget.currency.as_str();
+
+        let mut days: Vec<(NaiveDate, Vec<ItineraryStop>)> = Vec::new();
+        let mut date = first_day;
+        while date <= last_day {
+            days.push((date, Vec::new()));
+            date = date.succ();
+        }
+
+        let mut unscheduled = Vec::new();
+        for event_entry in trip.events.iter() {
+            let stop = ItineraryStop::from_entry(event_entry, trip.tz());
+            match stop.start_at.map(|start| (start.date() - first_day).num_days()) {
+                Some(index) if index >= 0 && (index as usize) < days.len() => days[index as usize].1.push(stop),
+                _ => unscheduled.push(stop),
+            }
+        }
+
+        let mut itinerary_days = Vec::new();
+        for (date, mut stops) in days {
+            stops.sort_by_key(|stop| (stop.start_at, stop.end_at));
+            let budgets: Vec<Money> = stops.iter().map(|stop| stop.budget.clone()).collect();
+
+            itinerary_days.push(ItineraryDay {
+                date: date.format("%Y-%m-%d").to_string(),
+                gaps: free_gaps(date, &stops, window),
+                transfers: transfers(&stops, profile, router)?,
+                spend: converter.sum(&budgets, currency)?,
+                stops,
+            });
+        }
+
+        let budgets: Vec<Money> = trip.events.iter().map(|event_entry| event_entry.budget.clone()).collect();
+
+        Ok(Itinerary {
+            trip_id: trip._id.clone(),
+            days: itinerary_days,
+            unscheduled,
+            total_spend: converter.sum(&budgets, currency)?,
+        })
+    }
+}
+
+impl ItineraryStop {
+    pub fn from_entry(event_entry: &EventEntry, tz: Tz) -> ItineraryStop {
+        // Entries without their own duration take the event's
+        let duration = match (event_entry.duration, event_entry.event.as_ref()) {
+            (0, Some(event)) => event.duration,
+            (duration, _) => duration,
+        };
+        let name = event_entry.event.as_ref().map(|event| event.name.clone())
+            .or_else(|| event_entry.snapshot.as_ref().map(|snapshot| snapshot.name.clone()));
+        let start_at = event_entry.start_datetime(tz);
+        let end_at = start_at.map(|start| start + Duration::minutes(duration as i64));
+
+        ItineraryStop {
+            event_id: event_entry.event_id.clone(),
+            name,
+            start: start_at.map(|start| start.format(TIME_FORMAT).to_string()),
+            end: end_at.map(|end| end.format(TIME_FORMAT).to_string()),
+            duration,
+            budget: event_entry.budget.clone(),
+            location: event_entry.event.as_ref().and_then(|event| event.location.clone()),
+            unavailable: event_entry.unavailable.unwrap_or(false),
+            start_at,
+            end_at,
+        }
+    }
+
+    // Used when the entry's event was not expanded
+    pub fn with_duration(mut self, duration: i32) -> ItineraryStop {
+        self.duration = duration;
+        self.end_at = self.start_at.map(|start| start + Duration::minutes(duration as i64));
+        self.end = self.end_at.map(|end| end.format(TIME_FORMAT).to_string());
+        self
+    }
+}
+
+// Free time between the day bounds and the stops, overlapping stops leave no gap
+pub fn free_gaps(date: NaiveDate, stops: &[ItineraryStop], window: DayWindow) -> Vec<FreeGap> {
+    let day_end = date.and_time(window.end);
+    let mut cursor = date.and_time(window.start);
+    let mut gaps = Vec::new();
+
+    for stop in stops.iter() {
+        let (start, end) = match (stop.start_at, stop.end_at) {
+            (Some(start), Some(end)) => (start, end),
+            _ => continue,
+        };
+        if start > cursor {
+            gaps.push(free_gap(cursor, start.min(day_end)));
+        }
+        cursor = cursor.max(end);
+        if cursor >= day_end {
+            break;
+        }
+    }
+    if cursor < day_end {
+        gaps.push(free_gap(cursor, day_end));
+    }
+
+    gaps.retain(|gap| gap.minutes > 0);
+    gaps
+}
+
+// Stops without a location are skipped, the transfer goes to the next located one
+pub fn transfers(stops: &[ItineraryStop], profile: TravelProfile, router: &dyn Router) -> Result<Vec<Transfer>, String> {
+    let located: Vec<(&ItineraryStop, &Vec<f64>)> = stops.iter()
+        .filter(|stop| stop.start_at.is_some())
+        .filter_map(|stop| stop.location.as_ref().map(|location| (stop, location)))
+        .collect();
+
+    let mut transfers = Vec::new();
+    for pair in located.windows(2) {
+        let ((from, from_location), (to, to_location)) = (pair[0], pair[1]);
+        let (from_end, to_start) = match (from.end_at, to.start_at) {
+            (Some(from_end), Some(to_start)) => (from_end, to_start),
+            _ => continue,
+        };
+        let route = router.route(from_location, to_location, profile)?;
+        let available_minutes = (to_start - from_end).num_minutes();
+
+        transfers.push(Transfer {
+            from_event_id: from.event_id.clone(),
+            to_event_id: to.event_id.clone(),
+            profile,
+            distance_meters: route.distance_meters,
+            minutes: route.minutes,
+            available_minutes,
+            feasible: route.minutes <= available_minutes,
+        });
+    }
+
+    Ok(transfers)
+}
+
+fn free_gap(from: NaiveDateTime, to: NaiveDateTime) -> FreeGap {
+    FreeGap {
+        from: from.format(TIME_FORMAT).to_string(),
+        to: to.format(TIME_FORMAT).to_string(),
+        minutes: (to - from).num_minutes(),
+    }
+}
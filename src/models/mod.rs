@@ -1,3 +1,19 @@
 pub(crate) mod user;
 pub(crate) mod event;
-pub(crate) mod trip;
\ No newline at end of file
+pub(crate) mod trip;
+pub(crate) mod review;
+pub(crate) mod favorite;
+pub(crate) mod opening_hours;
+pub(crate) mod currency;
+pub(crate) mod tag;
+pub(crate) mod place;
+pub(crate) mod report;
+pub(crate) mod audit;
+pub(crate) mod trash;
+pub(crate) mod itinerary;
+pub(crate) mod conflict;
+pub(crate) mod routing;
+pub(crate) mod optimizer;
+pub(crate) mod collaborator;
+pub(crate) mod share_link;
+pub(crate) mod trip_hub;
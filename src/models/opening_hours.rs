@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use regex::Regex;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+pub const MAX_EVENT_DURATION_MINUTES: i32 = 7 * 24 * 60;
+
+// Opening hours per weekday, a day without ranges is a closed day
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct OpeningHours {
+    #[serde(default)]
+    pub monday: Vec<TimeRange>,
+    #[serde(default)]
+    pub tuesday: Vec<TimeRange>,
+    #[serde(default)]
+    pub wednesday: Vec<TimeRange>,
+    #[serde(default)]
+    pub thursday: Vec<TimeRange>,
+    #[serde(default)]
+    pub friday: Vec<TimeRange>,
+    #[serde(default)]
+    pub saturday: Vec<TimeRange>,
+    #[serde(default)]
+    pub sunday: Vec<TimeRange>,
+}
+
+// Times use the "HH:MM" format in the event's local time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TimeRange {
+    pub open: String,
+    pub close: String,
+}
+
+// Dates use "MM-DD" for closures repeating every year or "YYYY-MM-DD" for a single season
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Closure {
+    pub from: String,
+    pub to: String,
+    pub reason: Option<String>,
+}
+
+impl OpeningHours {
+    pub fn for_weekday(&self, weekday: Weekday) -> &Vec<TimeRange> {
+        match weekday {
+            Weekday::Mon => &self.monday,
+            Weekday::Tue => &self.tuesday,
+            Weekday::Wed => &self.wednesday,
+            Weekday::Thu => &self.thursday,
+            Weekday::Fri => &self.friday,
+            Weekday::Sat => &self.saturday,
+            Weekday::Sun => &self.sunday,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let days = [
+            Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu,
+            Weekday::Fri, Weekday::Sat, Weekday::Sun
+        ];
+        for day in days.iter() {
+            for range in self.for_weekday(*day) {
+                range.bounds()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Warnings for a visit starting at `start` and lasting `duration` minutes
+    pub fn check_visit(&self, start: NaiveDateTime, duration: i32) -> Vec<String> {
+        let end = start + Duration::minutes(duration as i64);
+        // Ranges of the previous day crossing midnight still cover the early hours
+        let fits = self.opening_windows(start.date()).into_iter()
+            .chain(self.opening_windows(start.date().pred()))
+            .any(|(open_at, close_at)| start >= open_at && end <= close_at);
+
+        if fits {
+            Vec::new()
+        } else if self.for_weekday(start.weekday()).is_empty() {
+            vec![format!("Event is closed on {}", weekday_name(start.weekday()))]
+        } else {
+            vec![format!("Visit from {} to {} falls outside opening hours on {}",
+                         start.format("%H:%M"),
+                         end.format("%H:%M"),
+                         weekday_name(start.weekday()))]
+        }
+    }
+
+    // Opening and closing times of the ranges of a date, a range closing before it opens
+    // ends on the next day
+    fn opening_windows(&self, date: NaiveDate) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+        self.for_weekday(date.weekday()).iter()
+            .filter_map(|range| range.bounds().ok())
+            .map(|(open, close)| {
+                let close_at = if close <= open {
+                    date.and_time(close) + Duration::days(1)
+                } else {
+                    date.and_time(close)
+                };
+                (date.and_time(open), close_at)
+            })
+            .collect()
+    }
+}
+
+impl TimeRange {
+    pub fn bounds(&self) -> Result<(NaiveTime, NaiveTime), String> {
+        let open = parse_time(self.open.as_str())?;
+        let close = parse_time(self.close.as_str())?;
+        if open == close {
+            return Err(format!("Opening range {}-{} is empty", self.open, self.close));
+        }
+
+        Ok((open, close))
+    }
+}
+
+impl Closure {
+    pub fn validate(&self) -> Result<(), String> {
+        match (parse_closure_date(self.from.as_str()), parse_closure_date(self.to.as_str())) {
+            (Ok(ClosureDate::Yearly(_, _)), Ok(ClosureDate::Yearly(_, _))) => Ok(()),
+            (Ok(ClosureDate::Fixed(from)), Ok(ClosureDate::Fixed(to))) => {
+                if from <= to {
+                    Ok(())
+                } else {
+                    Err(format!("Closure starts after it ends: {} - {}", self.from, self.to))
+                }
+            },
+            (Err(e), _) | (_, Err(e)) => Err(e),
+            _ => Err("Closure dates must both be MM-DD or YYYY-MM-DD".to_string()),
+        }
+    }
+
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        match (parse_closure_date(self.from.as_str()), parse_closure_date(self.to.as_str())) {
+            (Ok(ClosureDate::Fixed(from)), Ok(ClosureDate::Fixed(to))) => from <= date && date <= to,
+            (Ok(ClosureDate::Yearly(from_month, from_day)), Ok(ClosureDate::Yearly(to_month, to_day))) => {
+                let day = (date.month(), date.day());
+                let from = (from_month, from_day);
+                let to = (to_month, to_day);
+                // Yearly closures may wrap around new year, e.g. 12-20 to 01-06
+                if from <= to {
+                    from <= day && day <= to
+                } else {
+                    day >= from || day <= to
+                }
+            },
+            _ => false,
+        }
+    }
+}
+
+enum ClosureDate {
+    Yearly(u32, u32),
+    Fixed(NaiveDate),
+}
+
+fn parse_closure_date(date: &str) -> Result<ClosureDate, String> {
+    if let Ok(fixed) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        return Ok(ClosureDate::Fixed(fixed));
+    }
+
+    // Validate month and day against a leap year so 02-29 is accepted
+    match NaiveDate::parse_from_str(format!("2000-{}", date).as_str(), "%Y-%m-%d") {
+        Ok(yearly) if date.len() == 5 => Ok(ClosureDate::Yearly(yearly.month(), yearly.day())),
+        _ => Err(format!("Invalid closure date: {}", date)),
+    }
+}
+
+// Warnings for a visit starting at the given local time and lasting `duration` minutes
+pub fn visit_warnings(opening_hours: Option<&OpeningHours>,
+                      closures: &[Closure],
+                      start: NaiveDateTime,
+                      duration: i32
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for closure in closures.iter().filter(|closure| closure.covers(start.date())) {
+        warnings.push(match closure.reason.as_ref() {
+            Some(reason) => format!("Event is closed from {} to {}: {}", closure.from, closure.to, reason),
+            None => format!("Event is closed from {} to {}", closure.from, closure.to),
+        });
+    }
+    if let Some(opening_hours) = opening_hours {
+        warnings.append(&mut opening_hours.check_visit(start, duration));
+    }
+
+    warnings
+}
+
+pub fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|_| format!("Invalid time, expecting HH:MM: {}", time))
+}
+
+pub fn validate_duration(duration: i32) -> Result<(), String> {
+    if (1..=MAX_EVENT_DURATION_MINUTES).contains(&duration) {
+        Ok(())
+    } else {
+        Err(format!("Duration must be between 1 and {} minutes", MAX_EVENT_DURATION_MINUTES))
+    }
+}
+
+// Durations used to be free text, accept "90", "1h30m", "2 hours" or "45 min"
+pub fn parse_duration(duration: &str) -> Option<i32> {
+    let cleaned = duration.trim().to_lowercase();
+    if let Ok(minutes) = cleaned.parse::<i32>() {
+        return Some(minutes);
+    }
+
+    let re = Regex::new(r"^(?:(\d+)\s*h(?:ours?|rs?)?)?\s*(?:(\d+)\s*m(?:in(?:ute)?s?)?)?$").unwrap();
+    let captures = re.captures(cleaned.as_str())?;
+    let hours = captures.get(1).and_then(|h| h.as_str().parse::<i32>().ok());
+    let minutes = captures.get(2).and_then(|m| m.as_str().parse::<i32>().ok());
+
+    match (hours, minutes) {
+        (None, None) => None,
+        (h, m) => Some(h.unwrap_or(0) * 60 + m.unwrap_or(0)),
+    }
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
@@ -0,0 +1,403 @@
+use crate::MongoDb;
+use crate::models::trip::Trip;
+use crate::models::itinerary::{ItineraryQuery, ItineraryStop, DayWindow, TIME_FORMAT};
+use crate::models::opening_hours::{OpeningHours, Closure, visit_warnings};
+use crate::models::routing::{Router, TravelProfile};
+use crate::models::conflict::entry_conflicts;
+use crate::models::currency::CurrencyConverter;
+use crate::utils::timezone::localize;
+
+use serde::{Deserialize, Serialize};
+use bson::oid::ObjectId;
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::options::{FindOptions, UpdateOptions};
+use futures::stream::StreamExt;
+use chrono::{Duration, NaiveDate, NaiveDateTime, Timelike};
+use std::collections::HashMap;
+
+// Candidate start times are tried on this grid
+pub const SLOT_STEP_MINUTES: i64 = 15;
+
+// What limits when an event can be visited
+#[derive(Deserialize, Debug, Default)]
+pub struct EventSchedule {
+    #[serde(default)]
+    pub opening_hours: Option<OpeningHours>,
+    #[serde(default)]
+    pub closures: Vec<Closure>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Proposal {
+    pub trip_id: ObjectId,
+    pub profile: TravelProfile,
+    pub days: Vec<ProposedDay>,
+    // Entries no slot could be found for, they keep their current time
+    pub unplaced: Vec<UnplacedEntry>,
+    pub distance_meters: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ProposedDay {
+    // YYYY-MM-DD in the trip's local time
+    pub date: String,
+    pub stops: Vec<ProposedStop>,
+    pub distance_meters: i64,
+    pub travel_minutes: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ProposedStop {
+    // Position of the entry in the trip, sent back when accepting
+    pub index: usize,
+    pub event_id: ObjectId,
+    pub name: Option<String>,
+    // Local "YYYY-MM-DDTHH:MM"
+    pub start: String,
+    pub end: String,
+    pub duration: i32,
+    pub fixed: bool,
+    // The entry did not start at this time before
+    pub moved: bool,
+    // From the previous stop of the day
+    pub distance_meters: i64,
+    pub travel_minutes: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct UnplacedEntry {
+    pub index: usize,
+    pub event_id: ObjectId,
+    pub name: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProposalAccept {
+    pub slots: Vec<ProposedSlot>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProposedSlot {
+    pub index: usize,
+    pub event_id: String,
+    // Local "YYYY-MM-DDTHH:MM"
+    pub start: String,
+}
+
+// A stop already on the day's timeline
+struct Placed {
+    end: NaiveDateTime,
+    location: Option<Vec<f64>>,
+}
+
+impl Proposal {
+    // Only the owner can plan a trip, nothing is saved
+    pub async fn get(trip_id: String,
+                     query: ItineraryQuery,
+                     user_id: String,
+                     router: &dyn Router,
+                     db: &MongoDb
+    ) -> Result<Proposal, String> {
+        let window = query.window()?;
+        let profile = query.profile.unwrap_or_default();
+        let trip = Trip::get_trip(trip_id, db).await?;
+        if !trip.owned_by(user_id.as_str()) {
+            return Err("Trip not found".to_string());
+        }
+
+        let mut trips = vec![trip];
+        Trip::expand_events(&mut trips, Some(&user_id), db).await?;
+        let event_ids: Vec<ObjectId> = trips[0].events.iter().map(|event_entry| event_entry.event_id.clone()).collect();
+        let schedules = load_schedules(event_ids, db).await?;
+
+        Proposal::build(&trips[0], &schedules, window, profile, router)
+    }
+
+    // Fixed entries stay where they are, the others are placed day by day going to the
+    // closest event that can still be visited, in the first slot it is open
+    pub fn build(trip: &Trip,
+                 schedules: &HashMap<ObjectId, EventSchedule>,
+                 window: DayWindow,
+                 profile: TravelProfile,
+                 router: &dyn Router
+    ) -> Result<Proposal, String> {
+        let first_day = trip.first_day();
+        let last_day = trip.last_day();
+        if last_day < first_day {
+            return Err("Trip ends before it starts".to_string());
+        }
+
+        let mut fixed: Vec<(usize, ItineraryStop)> = Vec::new();
+        let mut flexible: Vec<(usize, ItineraryStop)> = Vec::new();
+        let mut unplaced = Vec::new();
+        for (index, event_entry) in trip.events.iter().enumerate() {
+            let stop = ItineraryStop::from_entry(event_entry, trip.tz());
+            if stop.unavailable {
+                unplaced.push(unplaced_entry(index, &stop, "Event is unavailable"));
+            } else if event_entry.fixed && stop.start_at.is_some() {
+                let date = stop.start_at.unwrap().date();
+                if date < first_day || date > last_day {
+                    unplaced.push(unplaced_entry(index, &stop, "Fixed outside the trip dates"));
+                } else {
+                    fixed.push((index, stop));
+                }
+            } else {
+                flexible.push((index, stop));
+            }
+        }
+        fixed.sort_by_key(|(_, stop)| stop.start_at);
+
+        let mut days = Vec::new();
+        let mut date = first_day;
+        while date <= last_day {
+            let day_fixed: Vec<&(usize, ItineraryStop)> = fixed.iter()
+                .filter(|(_, stop)| stop.start_at.map(|start| start.date()) == Some(date))
+                .collect();
+            days.push(plan_day(date, day_fixed, &mut flexible, schedules, window, profile, router)?);
+            date = date.succ();
+        }
+
+        for (index, stop) in flexible.iter() {
+            unplaced.push(unplaced_entry(*index, stop, "No free slot within the day bounds and opening hours"));
+        }
+        unplaced.sort_by_key(|entry| entry.index);
+
+        Ok(Proposal {
+            trip_id: trip._id.clone(),
+            profile,
+            distance_meters: days.iter().map(|day| day.distance_meters).sum(),
+            days,
+            unplaced,
+        })
+    }
+
+    // Entries are matched by position, the event check catches trips changed in between.
+    // Slots follow the same rules as entries added with /trip/add
+    pub async fn accept(trip_id: String, accept: ProposalAccept, user_id: String, db: &MongoDb) -> Result<String, String> {
+        let trip_collection = db.collection("trips");
+
+        let trip = Trip::get_trip(trip_id, db).await?;
+        if !trip.owned_by(user_id.as_str()) {
+            return Err("Trip not found".to_string());
+        }
+        let tz = trip.tz();
+
+        let mut filter = doc! {"_id": trip._id.clone(), "deleted_at": Bson::Null};
+        let mut update_doc = Document::new();
+        let mut starts = Vec::new();
+        for slot in accept.slots.iter() {
+            let event_oid = ObjectId::with_string(slot.event_id.as_str())
+                .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+            if trip.events.get(slot.index).map(|event_entry| &event_entry.event_id) != Some(&event_oid) {
+                return Err(outdated());
+            }
+            let start = NaiveDateTime::parse_from_str(slot.start.as_str(), TIME_FORMAT)
+                .map_err(|_| format!("Invalid start, expecting YYYY-MM-DDTHH:MM: {}", slot.start))?;
+
+            filter.insert(format!("events.{}.event_id", slot.index), event_oid);
+            update_doc.insert(format!("events.{}.start_date", slot.index), localize(start, tz));
+            update_doc.insert(format!("events.{}.start_hour", slot.index), start.format("%H:%M").to_string());
+            starts.push((slot.index, start));
+        }
+        if update_doc.is_empty() {
+            return Err("Nothing to schedule".to_string());
+        }
+
+        // The owner's view gives the durations of the entries
+        let mut trips = vec![trip];
+        Trip::expand_events(&mut trips, Some(&user_id), db).await?;
+        check_slots(&trips[0], &starts, &CurrencyConverter::load(db).await?)?;
+
+        match trip_collection.update_one(filter, doc! {"$set": update_doc, "$inc": {"version": 1}}, UpdateOptions::default()).await {
+            Ok(result) if result.matched_count > 0 => Ok("Itinerary successfully saved".to_string()),
+            Ok(_) => Err(outdated()),
+            Err(_) => Err("Error updating Trip".to_string()),
+        }
+    }
+}
+
+// Every moved entry has to stay within the trip dates and clear of the other entries, as
+// they are once all the slots are applied
+pub fn check_slots(trip: &Trip, starts: &[(usize, NaiveDateTime)], converter: &CurrencyConverter) -> Result<(), String> {
+    let tz = trip.tz();
+    let mut planned = trip.clone();
+    for (index, start) in starts.iter() {
+        if let Some(event_entry) = planned.events.get_mut(*index) {
+            event_entry.start_date = localize(*start, tz);
+            event_entry.start_hour = start.format("%H:%M").to_string();
+        }
+    }
+
+    for (index, start) in starts.iter().filter(|(index, _)| *index < planned.events.len()) {
+        let mut others = planned.clone();
+        let event_entry = others.events.remove(*index);
+        let stop = ItineraryStop::from_entry(&event_entry, tz);
+        if let Some(conflict) = entry_conflicts(&others, &stop, converter)?.into_iter().find(|conflict| conflict.is_error()) {
+            return Err(format!("Slot at {} conflicts with the trip: {}", start.format(TIME_FORMAT), conflict.message));
+        }
+    }
+
+    Ok(())
+}
+
+fn plan_day(date: NaiveDate,
+            day_fixed: Vec<&(usize, ItineraryStop)>,
+            flexible: &mut Vec<(usize, ItineraryStop)>,
+            schedules: &HashMap<ObjectId, EventSchedule>,
+            window: DayWindow,
+            profile: TravelProfile,
+            router: &dyn Router
+) -> Result<ProposedDay, String> {
+    let day_start = date.and_time(window.start);
+    let day_end = date.and_time(window.end);
+    let mut previous = Placed { end: day_start, location: None };
+    let mut stops = Vec::new();
+    let mut next_fixed = day_fixed.into_iter().peekable();
+
+    loop {
+        // The next fixed stop has to be reached in time
+        let (limit, limit_location) = match next_fixed.peek() {
+            Some((_, stop)) => (stop.start_at.unwrap_or(day_end), stop.location.clone()),
+            None => (day_end, None),
+        };
+
+        // Closest event first, then the earliest slot, then trip order
+        let mut best: Option<(usize, NaiveDateTime, i64, i64)> = None;
+        for (position, (_, stop)) in flexible.iter().enumerate() {
+            let (distance_meters, travel_minutes) = travel(previous.location.as_ref(), stop.location.as_ref(), profile, router)?;
+            let (_, travel_out) = travel(stop.location.as_ref(), limit_location.as_ref(), profile, router)?;
+            let earliest = previous.end + Duration::minutes(travel_minutes);
+            let latest_end = limit - Duration::minutes(travel_out);
+
+            if let Some(start) = first_open_slot(schedules.get(&stop.event_id), earliest, stop.duration, latest_end) {
+                let better = match best {
+                    Some((_, best_start, best_distance, _)) => (distance_meters, start) < (best_distance, best_start),
+                    None => true,
+                };
+                if better {
+                    best = Some((position, start, distance_meters, travel_minutes));
+                }
+            }
+        }
+
+        if let Some((position, start, distance_meters, travel_minutes)) = best {
+            let (index, stop) = flexible.remove(position);
+            let end = start + Duration::minutes(stop.duration as i64);
+            previous = Placed { end, location: stop.location.clone().or(previous.location) };
+            stops.push(proposed_stop(index, &stop, start, false, distance_meters, travel_minutes));
+        } else if let Some((index, stop)) = next_fixed.next() {
+            let (distance_meters, travel_minutes) = travel(previous.location.as_ref(), stop.location.as_ref(), profile, router)?;
+            let start = stop.start_at.unwrap_or(day_start);
+            let end = stop.end_at.unwrap_or(start);
+            previous = Placed { end: end.max(previous.end), location: stop.location.clone().or(previous.location) };
+            stops.push(proposed_stop(*index, stop, start, true, distance_meters, travel_minutes));
+        } else {
+            break;
+        }
+    }
+
+    Ok(ProposedDay {
+        date: date.format("%Y-%m-%d").to_string(),
+        distance_meters: stops.iter().map(|stop| stop.distance_meters).sum(),
+        travel_minutes: stops.iter().map(|stop| stop.travel_minutes).sum(),
+        stops,
+    })
+}
+
+// Events without a location can be reached from anywhere
+fn travel(from: Option<&Vec<f64>>,
+          to: Option<&Vec<f64>>,
+          profile: TravelProfile,
+          router: &dyn Router
+) -> Result<(i64, i64), String> {
+    match (from, to) {
+        (Some(from), Some(to)) => {
+            let route = router.route(from, to, profile)?;
+            Ok((route.distance_meters, route.minutes))
+        },
+        _ => Ok((0, 0)),
+    }
+}
+
+fn first_open_slot(schedule: Option<&EventSchedule>,
+                   earliest: NaiveDateTime,
+                   duration: i32,
+                   latest_end: NaiveDateTime
+) -> Option<NaiveDateTime> {
+    let mut start = round_up(earliest);
+    while start + Duration::minutes(duration as i64) <= latest_end {
+        let open = match schedule {
+            Some(schedule) => visit_warnings(schedule.opening_hours.as_ref(), &schedule.closures, start, duration).is_empty(),
+            None => true,
+        };
+        if open {
+            return Some(start);
+        }
+        start += Duration::minutes(SLOT_STEP_MINUTES);
+    }
+
+    None
+}
+
+fn round_up(time: NaiveDateTime) -> NaiveDateTime {
+    let minutes = time.hour() as i64 * 60 + time.minute() as i64;
+    let rounded = (minutes + SLOT_STEP_MINUTES - 1) / SLOT_STEP_MINUTES * SLOT_STEP_MINUTES;
+    time.date().and_hms(0, 0, 0) + Duration::minutes(rounded)
+}
+
+fn proposed_stop(index: usize,
+                 stop: &ItineraryStop,
+                 start: NaiveDateTime,
+                 fixed: bool,
+                 distance_meters: i64,
+                 travel_minutes: i64
+) -> ProposedStop {
+    ProposedStop {
+        index,
+        event_id: stop.event_id.clone(),
+        name: stop.name.clone(),
+        start: start.format(TIME_FORMAT).to_string(),
+        end: (start + Duration::minutes(stop.duration as i64)).format(TIME_FORMAT).to_string(),
+        duration: stop.duration,
+        fixed,
+        moved: stop.start_at != Some(start),
+        distance_meters,
+        travel_minutes,
+    }
+}
+
+fn unplaced_entry(index: usize, stop: &ItineraryStop, reason: &str) -> UnplacedEntry {
+    UnplacedEntry {
+        index,
+        event_id: stop.event_id.clone(),
+        name: stop.name.clone(),
+        reason: reason.to_string(),
+    }
+}
+
+async fn load_schedules(event_ids: Vec<ObjectId>, db: &MongoDb) -> Result<HashMap<ObjectId, EventSchedule>, String> {
+    let event_collection = db.collection("events");
+
+    let find_options = FindOptions::builder()
+        .projection(doc! {"opening_hours": 1, "closures": 1})
+        .build();
+    let mut cursor = event_collection.find(doc! {"_id": {"$in": event_ids}}, find_options)
+        .await.map_err(|_| "Error finding events".to_string())?;
+
+    let mut schedules = HashMap::new();
+    while let Some(Ok(document)) = cursor.next().await {
+        if let Ok(event_id) = document.get_object_id("_id") {
+            let event_id = event_id.clone();
+            if let Ok(schedule) = bson::from_bson::<EventSchedule>(Bson::Document(document)) {
+                schedules.insert(event_id, schedule);
+            }
+        }
+    }
+
+    Ok(schedules)
+}
+
+fn outdated() -> String {
+    "Trip changed since the proposal, optimize it again".to_string()
+}
@@ -0,0 +1,337 @@
+use crate::MongoDb;
+use crate::utils::timezone::DEFAULT_TIMEZONE;
+
+use serde::{Deserialize, Serialize};
+use bson::oid::ObjectId;
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::options::{FindOneOptions, FindOptions, UpdateOptions};
+use futures::stream::StreamExt;
+
+pub const DEFAULT_AUTOCOMPLETE_LIMIT: i64 = 10;
+
+// Reference data bundled with the server, loaded with the `load-places` command
+const PLACES_DATASET: &str = include_str!("../../data/places.json");
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Country {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    // ISO 3166-1 alpha-2 code
+    pub code: String,
+    pub name: String,
+    #[serde(default)]
+    pub variants: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct City {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    pub name: String,
+    pub country_code: String,
+    #[serde(default)]
+    pub variants: Vec<String>,
+    // [lng, lat] like event locations
+    pub location: Vec<f64>,
+    // IANA timezone name
+    pub timezone: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlaceDataset {
+    pub countries: Vec<Country>,
+    pub cities: Vec<City>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PlaceQuery {
+    pub q: String,
+    pub country: Option<String>,
+    pub limit: Option<i64>,
+}
+
+impl Country {
+    // Lowercase terms the country can be found by
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys = vec![normalize_place(self.code.as_str()), normalize_place(self.name.as_str())];
+        keys.extend(self.variants.iter().map(|variant| normalize_place(variant)));
+        keys.dedup();
+        keys
+    }
+
+    pub async fn resolve(value: &str, db: &MongoDb) -> Result<Country, String> {
+        let country_collection = db.collection("countries");
+
+        match country_collection.find_one(doc! {"keys": normalize_place(value)}, FindOneOptions::default())
+            .await.expect("Error finding country") {
+            Some(country_found) => match bson::from_bson::<Country>(Bson::Document(country_found)) {
+                Ok(country) => Ok(country),
+                Err(_) => Err("Incorrect Struct".to_string()),
+            },
+            None => Err(format!("Unknown country: {}", value)),
+        }
+    }
+
+    pub async fn autocomplete(query: PlaceQuery, db: &MongoDb) -> Result<Vec<Country>, String> {
+        let country_collection = db.collection("countries");
+
+        let find_options = FindOptions::builder()
+            .limit(autocomplete_limit(query.limit)?)
+            .sort(doc! {"name": 1})
+            .build();
+
+        find_places(&country_collection, prefix_filter(query.q.as_str()), find_options).await
+    }
+}
+
+impl City {
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys = vec![normalize_place(self.name.as_str())];
+        keys.extend(self.variants.iter().map(|variant| normalize_place(variant)));
+        keys.dedup();
+        keys
+    }
+
+    // Name variants are shared between countries, e.g. Paris in France and in Texas
+    pub async fn resolve(value: &str, country_code: Option<&str>, db: &MongoDb) -> Result<City, String> {
+        let city_collection = db.collection("cities");
+
+        let mut filter = doc! {"keys": normalize_place(value)};
+        if let Some(code) = country_code {
+            filter.insert("country_code", code);
+        }
+
+        let mut cities: Vec<City> = find_places(&city_collection, filter, FindOptions::default()).await?;
+        match cities.len() {
+            0 => Err(format!("Unknown city: {}", value)),
+            1 => Ok(cities.remove(0)),
+            _ => Err(format!("Ambiguous city {}, a country is required", value)),
+        }
+    }
+
+    pub async fn autocomplete(query: PlaceQuery, db: &MongoDb) -> Result<Vec<City>, String> {
+        let city_collection = db.collection("cities");
+
+        let mut filter = prefix_filter(query.q.as_str());
+        if let Some(country) = query.country.as_ref() {
+            filter.insert("country_code", Country::resolve(country.as_str(), db).await?.code);
+        }
+        let find_options = FindOptions::builder()
+            .limit(autocomplete_limit(query.limit)?)
+            .sort(doc! {"name": 1})
+            .build();
+
+        find_places(&city_collection, filter, find_options).await
+    }
+}
+
+pub fn normalize_place(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+pub fn dataset() -> Result<PlaceDataset, String> {
+    serde_json::from_str(PLACES_DATASET).map_err(|e| format!("Invalid places dataset: {}", e))
+}
+
+// Destinations are a city, "city, country" or a whole country
+pub async fn resolve_destination(value: &str, db: &MongoDb) -> Result<String, String> {
+    let (city_name, country_name) = match value.rsplit_once(',') {
+        Some((city, country)) => (city, Some(country)),
+        None => (value, None),
+    };
+
+    let country = match country_name {
+        Some(name) => Some(Country::resolve(name, db).await?),
+        None => None,
+    };
+    let city = City::resolve(city_name, country.as_ref().map(|c| c.code.as_str()), db).await;
+
+    match (city, country) {
+        (Ok(city), Some(country)) => Ok(format!("{}, {}", city.name, country.name)),
+        (Ok(city), None) => {
+            let country = Country::resolve(city.country_code.as_str(), db).await?;
+            Ok(format!("{}, {}", city.name, country.name))
+        },
+        (Err(e), Some(_)) => Err(e),
+        (Err(e), None) => match Country::resolve(value, db).await {
+            Ok(country) => Ok(country.name),
+            Err(_) => Err(e),
+        },
+    }
+}
+
+// Timezone of a resolved destination's city. Countries can span several timezones so
+// destinations without a city use UTC
+pub async fn destination_timezone(destination: &str, db: &MongoDb) -> String {
+    let (city_name, country_name) = match destination.rsplit_once(',') {
+        Some((city, country)) => (city, country),
+        None => return DEFAULT_TIMEZONE.to_string(),
+    };
+
+    let country_code = match Country::resolve(country_name, db).await {
+        Ok(country) => country.code,
+        Err(_) => return DEFAULT_TIMEZONE.to_string(),
+    };
+    match City::resolve(city_name, Some(country_code.as_str()), db).await {
+        Ok(city) => city.timezone,
+        Err(_) => DEFAULT_TIMEZONE.to_string(),
+    }
+}
+
+// Upsert the bundled countries and cities
+pub async fn load_places(db: &MongoDb) -> Result<String, String> {
+    let country_collection = db.collection("countries");
+    let city_collection = db.collection("cities");
+    let dataset = dataset()?;
+    let upsert_options = UpdateOptions::builder()
+        .upsert(true)
+        .build();
+
+    for country in dataset.countries.iter() {
+        country_collection.update_one(
+            doc! {"code": country.code.clone()},
+            doc! {"$set": {
+                "name": country.name.clone(),
+                "variants": country.variants.clone(),
+                "keys": country.keys(),
+            }},
+            upsert_options.clone()
+        ).await.map_err(|_| "Error loading countries".to_string())?;
+    }
+    for city in dataset.cities.iter() {
+        city_collection.update_one(
+            doc! {"name": city.name.clone(), "country_code": city.country_code.clone()},
+            doc! {"$set": {
+                "variants": city.variants.clone(),
+                "location": city.location.clone(),
+                "timezone": city.timezone.clone(),
+                "keys": city.keys(),
+            }},
+            upsert_options.clone()
+        ).await.map_err(|_| "Error loading cities".to_string())?;
+    }
+
+    Ok(format!("Loaded {} countries and {} cities", dataset.countries.len(), dataset.cities.len()))
+}
+
+// Rewrite stored countries, cities and destinations to their canonical form
+pub async fn migrate_places(db: &MongoDb) -> Result<String, String> {
+    let event_collection = db.collection("events");
+    let trip_collection = db.collection("trips");
+    let mut migrated = 0;
+    let mut unresolved: Vec<String> = Vec::new();
+
+    let mut cursor = event_collection.find(doc! {}, FindOptions::default())
+        .await.map_err(|_| "Error finding events".to_string())?;
+    while let Some(Ok(document)) = cursor.next().await {
+        let country_name = document.get_str("country").unwrap_or("").to_string();
+        let city_name = document.get_str("city").unwrap_or("").to_string();
+
+        let country = match Country::resolve(country_name.as_str(), db).await {
+            Ok(country) => country,
+            Err(e) => {
+                unresolved.push(e);
+                continue;
+            },
+        };
+        let city = match City::resolve(city_name.as_str(), Some(country.code.as_str()), db).await {
+            Ok(city) => city,
+            Err(e) => {
+                unresolved.push(e);
+                continue;
+            },
+        };
+
+        if country.code != country_name || city.name != city_name {
+            event_collection.update_one(doc! {"_id": document.get("_id").cloned().unwrap_or(Bson::Null)},
+                                        doc! {"$set": {"country": country.code, "city": city.name}},
+                                        UpdateOptions::default()
+            ).await.map_err(|_| "Error updating event".to_string())?;
+            migrated += 1;
+        }
+    }
+
+    let mut cursor = trip_collection.find(doc! {}, FindOptions::default())
+        .await.map_err(|_| "Error finding trips".to_string())?;
+    while let Some(Ok(document)) = cursor.next().await {
+        let destination = document.get_str("destination").unwrap_or("").to_string();
+
+        match resolve_destination(destination.as_str(), db).await {
+            Ok(canonical) if canonical != destination => {
+                trip_collection.update_one(doc! {"_id": document.get("_id").cloned().unwrap_or(Bson::Null)},
+                                           doc! {"$set": {"destination": canonical}},
+                                           UpdateOptions::default()
+                ).await.map_err(|_| "Error updating trip".to_string())?;
+                migrated += 1;
+            },
+            Ok(_) => (),
+            Err(e) => unresolved.push(e),
+        }
+    }
+
+    unresolved.sort();
+    unresolved.dedup();
+    Ok(format!("Migrated {} documents, unresolved: {}", migrated, unresolved.join(", ")))
+}
+
+pub async fn create_indexes(db: &MongoDb) -> Result<(), String> {
+    let commands = vec![
+        doc! {
+            "createIndexes": "countries",
+            "indexes": [
+                {"key": {"code": 1}, "name": "code_unique", "unique": true},
+                {"key": {"keys": 1}, "name": "keys"},
+            ]
+        },
+        doc! {
+            "createIndexes": "cities",
+            "indexes": [
+                {"key": {"country_code": 1, "name": 1}, "name": "country_name_unique", "unique": true},
+                {"key": {"keys": 1, "country_code": 1}, "name": "keys_country"},
+            ]
+        },
+    ];
+
+    for command in commands {
+        if db.run_command(command, None).await.is_err() {
+            return Err("Error creating place indexes".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+fn prefix_filter(q: &str) -> Document {
+    doc! {"keys": {"$regex": format!("^{}", regex::escape(normalize_place(q).as_str()))}}
+}
+
+fn autocomplete_limit(limit: Option<i64>) -> Result<i64, String> {
+    match limit {
+        Some(l) if l < 1 => Err("Limit must be greater than zero".to_string()),
+        Some(l) => Ok(l.min(DEFAULT_AUTOCOMPLETE_LIMIT * 5)),
+        None => Ok(DEFAULT_AUTOCOMPLETE_LIMIT),
+    }
+}
+
+async fn find_places<T: serde::de::DeserializeOwned>(collection: &mongodb::Collection,
+                                                     filter: Document,
+                                                     find_options: FindOptions
+) -> Result<Vec<T>, String> {
+    let mut cursor = match collection.find(filter, find_options).await {
+        Ok(cursor) => cursor,
+        Err(_) => return Err("Error finding places".to_string()),
+    };
+    let mut places = Vec::new();
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) =>
+                match bson::from_bson::<T>(Bson::Document(document)) {
+                    Ok(place) => places.push(place),
+                    Err(e) => println!("{:?}", e),
+                },
+            Err(_) => println!("Error retrieving Document"),
+        }
+    }
+
+    Ok(places)
+}
@@ -0,0 +1,430 @@
+use crate::MongoDb;
+use crate::models::event::Event;
+use crate::models::trip::Trip;
+use crate::models::user::User;
+use crate::models::audit::{AuditAction, AuditEntry, AuditRecord};
+use crate::utils::pagination::{find_page, Page, PageRequest};
+
+use serde::{Deserialize, Serialize};
+use bson::oid::ObjectId;
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::options::{
+    InsertOneOptions,
+    FindOneOptions,
+    FindOneAndUpdateOptions,
+    UpdateOptions,
+    ReturnDocument
+};
+use chrono::Utc;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentType {
+    Event,
+    Trip,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    Spam,
+    Offensive,
+    Misleading,
+    Inappropriate,
+    Other,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportStatus {
+    Open,
+    Resolved,
+    Dismissed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationAction {
+    Dismiss,
+    ForcePrivate,
+    Delete,
+    WarnOwner,
+}
+
+// Visibility decided by moderators, independent from the owner's private flag
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationStatus {
+    #[default]
+    Visible,
+    Hidden,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ModerationReason {
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Report {
+    pub _id: ObjectId,
+    pub content_type: ContentType,
+    pub content_id: ObjectId,
+    pub reporter_id: ObjectId,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+    pub status: ReportStatus,
+    pub decision: Option<ReportDecision>,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReportDecision {
+    pub action: ModerationAction,
+    pub admin_id: ObjectId,
+    pub note: Option<String>,
+    pub decided_at: String,
+}
+
+// Every moderation decision, kept even when the content is gone
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ModerationLog {
+    pub _id: ObjectId,
+    pub report_id: ObjectId,
+    pub content_type: ContentType,
+    pub content_id: ObjectId,
+    pub action: ModerationAction,
+    pub admin_id: ObjectId,
+    pub note: Option<String>,
+    pub reports_closed: i64,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReportCreate {
+    pub content_type: ContentType,
+    pub content_id: String,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReportResolve {
+    pub action: ModerationAction,
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ReportFilter {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    pub status: Option<ReportStatus>,
+    pub content_type: Option<ContentType>,
+}
+
+// Report in the admin queue along with the reported content
+#[derive(Serialize, Deserialize, Debug)]
+pub struct QueuedReport {
+    #[serde(flatten)]
+    pub report: Report,
+    pub content: Option<Document>,
+}
+
+impl ContentType {
+    pub fn collection_name(&self) -> &'static str {
+        match self {
+            ContentType::Event => "events",
+            ContentType::Trip => "trips",
+        }
+    }
+
+    pub fn audit_name(&self) -> &'static str {
+        match self {
+            ContentType::Event => "event",
+            ContentType::Trip => "trip",
+        }
+    }
+}
+
+impl Report {
+    pub async fn create(report: ReportCreate, reporter_id: String, db: &MongoDb) -> Result<ObjectId, String> {
+        let report_collection = db.collection("reports");
+        let content_collection = db.collection(report.content_type.collection_name());
+        let content_oid = ObjectId::with_string(report.content_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+        let reporter_oid = ObjectId::with_string(reporter_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        match content_collection.find_one(doc! {"_id": content_oid.clone(), "deleted_at": Bson::Null}, FindOneOptions::default())
+            .await.expect("Error finding content") {
+            Some(_) => (),
+            None => return Err("Reported content not found".to_string()),
+        }
+
+        // A user keeps a single open report per content
+        if report_collection.find_one(
+            doc! {"content_id": content_oid.clone(), "reporter_id": reporter_oid.clone(), "status": "open"},
+            FindOneOptions::default()
+        ).await.expect("Error finding report").is_some() {
+            return Err("Content already reported".to_string());
+        }
+
+        let report_doc = doc! {
+            "content_type": bson::to_bson(&report.content_type).unwrap_or(Bson::Null),
+            "content_id": content_oid,
+            "reporter_id": reporter_oid,
+            "reason": bson::to_bson(&report.reason).unwrap_or(Bson::Null),
+            "details": bson::to_bson(&report.details).unwrap_or(Bson::Null),
+            "status": "open",
+            "decision": Bson::Null,
+            "created_at": Utc::now().to_rfc3339(),
+        };
+
+        match report_collection.insert_one(report_doc, InsertOneOptions::default()).await {
+            Ok(result) => Ok(result.inserted_id.as_object_id().unwrap().clone()),
+            Err(_) => Err("Error inserting report".to_string()),
+        }
+    }
+
+    // Oldest reports first so the queue is worked in order
+    pub async fn get_queue(mut report_filter: ReportFilter, admin_id: String, db: &MongoDb) -> Result<Page<QueuedReport>, String> {
+        let report_collection = db.collection("reports");
+
+        User::check_admin(admin_id, db).await?;
+
+        let page_request = PageRequest::new(report_filter.cursor.take(), report_filter.limit)?;
+        let status = report_filter.status.unwrap_or(ReportStatus::Open);
+        let mut filter = doc! {"status": bson::to_bson(&status).unwrap_or(Bson::Null)};
+        if let Some(content_type) = report_filter.content_type {
+            filter.insert("content_type", bson::to_bson(&content_type).unwrap_or(Bson::Null));
+        }
+
+        let reports: Page<Report> = find_page(&report_collection,
+                                              filter.clone(),
+                                              filter,
+                                              Some(doc! {"_id": 1}),
+                                              page_request
+        ).await?;
+
+        let mut queued = Vec::new();
+        for report in reports.items {
+            let content = db.collection(report.content_type.collection_name())
+                .find_one(doc! {"_id": report.content_id.clone()}, FindOneOptions::default())
+                .await.expect("Error finding content");
+            queued.push(QueuedReport { report, content });
+        }
+
+        Ok(Page {
+            items: queued,
+            next_cursor: reports.next_cursor,
+            total: reports.total,
+        })
+    }
+
+    // Applies the action and closes every open report on the same content
+    pub async fn resolve(report_id: String,
+                         resolution: ReportResolve,
+                         admin_id: String,
+                         ip: Option<String>,
+                         db: &MongoDb
+    ) -> Result<String, String> {
+        let report_collection = db.collection("reports");
+        let log_collection = db.collection("moderation_log");
+        let report_oid = ObjectId::with_string(report_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+        let admin_oid = ObjectId::with_string(admin_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        User::check_admin(admin_id.clone(), db).await?;
+
+        let report = match report_collection.find_one(doc! {"_id": report_oid.clone()}, FindOneOptions::default())
+            .await.expect("Error finding report") {
+            Some(report_found) => match bson::from_bson::<Report>(Bson::Document(report_found)) {
+                Ok(report) => report,
+                Err(_) => return Err("Incorrect Struct".to_string()),
+            },
+            None => return Err("Report not found".to_string()),
+        };
+        if report.status != ReportStatus::Open {
+            return Err("Report already closed".to_string());
+        }
+
+        apply_action(&report, resolution.action, resolution.note.clone(), admin_id, ip, db).await?;
+
+        let now = Utc::now().to_rfc3339();
+        let status = match resolution.action {
+            ModerationAction::Dismiss => ReportStatus::Dismissed,
+            _ => ReportStatus::Resolved,
+        };
+        let decision = doc! {
+            "action": bson::to_bson(&resolution.action).unwrap_or(Bson::Null),
+            "admin_id": admin_oid.clone(),
+            "note": bson::to_bson(&resolution.note).unwrap_or(Bson::Null),
+            "decided_at": now.clone(),
+        };
+
+        let reports_closed = match report_collection.update_many(
+            doc! {"content_id": report.content_id.clone(), "status": "open"},
+            doc! {"$set": {"status": bson::to_bson(&status).unwrap_or(Bson::Null), "decision": decision}},
+            UpdateOptions::default()
+        ).await {
+            Ok(result) => result.modified_count,
+            Err(_) => return Err("Error closing reports".to_string()),
+        };
+
+        let log_doc = doc! {
+            "report_id": report_oid,
+            "content_type": bson::to_bson(&report.content_type).unwrap_or(Bson::Null),
+            "content_id": report.content_id,
+            "action": bson::to_bson(&resolution.action).unwrap_or(Bson::Null),
+            "admin_id": admin_oid,
+            "note": bson::to_bson(&resolution.note).unwrap_or(Bson::Null),
+            "reports_closed": reports_closed,
+            "created_at": now,
+        };
+        match log_collection.insert_one(log_doc, InsertOneOptions::default()).await {
+            Ok(_) => Ok(format!("Closed {} reports", reports_closed)),
+            Err(_) => Err("Error saving moderation log".to_string()),
+        }
+    }
+
+    pub async fn get_log(mut report_filter: ReportFilter, admin_id: String, db: &MongoDb) -> Result<Page<ModerationLog>, String> {
+        let log_collection = db.collection("moderation_log");
+
+        User::check_admin(admin_id, db).await?;
+
+        let page_request = PageRequest::new(report_filter.cursor.take(), report_filter.limit)?;
+        let mut filter = doc! {};
+        if let Some(content_type) = report_filter.content_type {
+            filter.insert("content_type", bson::to_bson(&content_type).unwrap_or(Bson::Null));
+        }
+
+        find_page(&log_collection, filter.clone(), filter, Some(doc! {"_id": -1}), page_request).await
+    }
+
+    pub async fn create_indexes(db: &MongoDb) -> Result<(), String> {
+        let command = doc! {
+            "createIndexes": "reports",
+            "indexes": [
+                {"key": {"status": 1, "_id": 1}, "name": "status_queue"},
+                {"key": {"content_id": 1, "status": 1}, "name": "content_status"},
+            ]
+        };
+
+        match db.run_command(command, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Error creating report indexes".to_string()),
+        }
+    }
+}
+
+// Hide or restore content, the reason is shown to the owner
+pub async fn set_moderation_status(content_type: ContentType,
+                                   content_id: String,
+                                   status: ModerationStatus,
+                                   reason: Option<String>,
+                                   admin_id: String,
+                                   ip: Option<String>,
+                                   db: &MongoDb
+) -> Result<(), String> {
+    let content_collection = db.collection(content_type.collection_name());
+    let content_oid = ObjectId::with_string(content_id.as_str())
+        .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+    let admin_oid = ObjectId::with_string(admin_id.as_str())
+        .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+    User::check_admin(admin_id, db).await?;
+
+    let reason = match status {
+        ModerationStatus::Visible => Bson::Null,
+        ModerationStatus::Hidden => bson::to_bson(&reason).unwrap_or(Bson::Null),
+    };
+    let after = doc! {
+        "moderation_status": bson::to_bson(&status).unwrap_or(Bson::Null),
+        "moderation_reason": reason,
+    };
+    let find_update_options = FindOneAndUpdateOptions::builder()
+        .return_document(ReturnDocument::Before)
+        .build();
+
+    let content_before = match content_collection.find_one_and_update(
+        doc! {"_id": content_oid.clone()},
+        doc! {"$set": after.clone(), "$inc": {"version": 1}},
+        find_update_options
+    ).await {
+        Ok(Some(content)) => content,
+        Ok(None) => return match content_type {
+            ContentType::Event => Err("Event not found".to_string()),
+            ContentType::Trip => Err("Trip not found".to_string()),
+        },
+        Err(_) => return Err("Error updating moderation status".to_string()),
+    };
+
+    // Content created before moderation existed has no status yet
+    let before = doc! {
+        "moderation_status": content_before.get("moderation_status").cloned().unwrap_or(Bson::String("visible".to_string())),
+        "moderation_reason": content_before.get("moderation_reason").cloned().unwrap_or(Bson::Null),
+    };
+
+    AuditEntry::record(AuditRecord {
+        action: match status {
+            ModerationStatus::Hidden => AuditAction::ForcePrivate,
+            ModerationStatus::Visible => AuditAction::Unhide,
+        },
+        actor_id: Some(admin_oid),
+        target_type: content_type.audit_name(),
+        target_id: Some(content_oid),
+        before: Some(before),
+        after: Some(after),
+        ip,
+    }, db).await
+}
+
+async fn apply_action(report: &Report,
+                      action: ModerationAction,
+                      note: Option<String>,
+                      admin_id: String,
+                      ip: Option<String>,
+                      db: &MongoDb
+) -> Result<(), String> {
+    let content_collection = db.collection(report.content_type.collection_name());
+    let content_id = report.content_id.to_hex();
+
+    match (action, report.content_type) {
+        (ModerationAction::Dismiss, _) => Ok(()),
+        (ModerationAction::ForcePrivate, ContentType::Event) =>
+            Event::force_private(content_id, note, admin_id, ip, db).await.map(|_| ()),
+        (ModerationAction::ForcePrivate, ContentType::Trip) =>
+            Trip::force_private(content_id, note, admin_id, ip, db).await.map(|_| ()),
+        // Goes through the regular deletion so trips referencing an event are handled
+        (ModerationAction::Delete, ContentType::Event) =>
+            Event::delete_event(content_id, admin_id, ip, db).await.map(|_| ()),
+        (ModerationAction::Delete, ContentType::Trip) =>
+            Trip::delete_trip(content_id, admin_id, ip, db).await.map(|_| ()),
+        (ModerationAction::WarnOwner, _) => {
+            let user_collection = db.collection("users");
+            let owner_id = match content_collection.find_one(doc! {"_id": report.content_id.clone()}, FindOneOptions::default())
+                .await.expect("Error finding content") {
+                Some(content) => content.get_object_id("user_id")
+                    .map_err(|_| "Reported content has no owner".to_string())?
+                    .clone(),
+                None => return Err("Reported content not found".to_string()),
+            };
+
+            let warning = doc! {
+                "report_id": report._id.clone(),
+                "content_type": bson::to_bson(&report.content_type).unwrap_or(Bson::Null),
+                "content_id": report.content_id.clone(),
+                "reason": bson::to_bson(&report.reason).unwrap_or(Bson::Null),
+                "created_at": Utc::now().to_rfc3339(),
+            };
+            match user_collection.update_one(doc! {"_id": owner_id},
+                                             doc! {"$push": {"warnings": warning}},
+                                             UpdateOptions::default()
+            ).await {
+                Ok(_) => Ok(()),
+                Err(_) => Err("Error warning owner".to_string()),
+            }
+        },
+    }
+}
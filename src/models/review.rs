@@ -0,0 +1,291 @@
+use crate::MongoDb;
+use crate::utils::pagination::{find_page, Page, PageRequest};
+
+use serde::{Deserialize, Serialize};
+use bson::oid::ObjectId;
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::options::{
+    InsertOneOptions,
+    FindOneOptions,
+    FindOneAndUpdateOptions,
+    UpdateOptions,
+    DeleteOptions,
+    AggregateOptions,
+    ReturnDocument
+};
+use futures::stream::StreamExt;
+use chrono::Utc;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Review {
+    pub _id: ObjectId,
+    pub event_id: ObjectId,
+    pub user_id: ObjectId,
+    pub rating: i32,
+    pub text: String,
+    pub reply: Option<ReviewReply>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReviewReply {
+    pub user_id: ObjectId,
+    pub text: String,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReviewCreate {
+    pub rating: i32,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReviewEdit {
+    pub rating: Option<i32>,
+    pub text: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReviewReplyCreate {
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ReviewFilter {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+impl Review {
+    pub async fn create(review: ReviewCreate, event_id: String, user_id: String, db: &MongoDb) -> Result<ObjectId, String> {
+        let review_collection = db.collection("reviews");
+        let event_collection = db.collection("events");
+        let event_oid = ObjectId::with_string(event_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+        let user_oid = ObjectId::with_string(user_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        validate_rating(review.rating)?;
+
+        match event_collection.find_one(doc! {"_id": event_oid.clone(), "deleted_at": Bson::Null}, FindOneOptions::default())
+            .await.expect("Error finding event") {
+            Some(_) => (),
+            None => return Err("Event not found".to_string()),
+        }
+
+        // Each user gets a single review per event
+        if review_collection.find_one(
+            doc! {"event_id": event_oid.clone(), "user_id": user_oid.clone()},
+            FindOneOptions::default()
+        ).await.expect("Error finding review").is_some() {
+            return Err("User already reviewed this event".to_string());
+        }
+
+        let now = Utc::now().to_rfc3339();
+        let review_doc = doc! {
+            "event_id": event_oid.clone(),
+            "user_id": user_oid,
+            "rating": review.rating,
+            "text": review.text,
+            "reply": Bson::Null,
+            "created_at": now.clone(),
+            "updated_at": now,
+        };
+
+        let review_id = match review_collection.insert_one(review_doc, InsertOneOptions::default()).await {
+            Ok(result) => result.inserted_id.as_object_id().unwrap().clone(),
+            Err(_) => return Err("Error inserting review".to_string()),
+        };
+
+        recompute_event_rating(event_oid, db).await?;
+
+        Ok(review_id)
+    }
+
+    pub async fn get_event_reviews(event_id: String, mut review_filter: ReviewFilter, db: &MongoDb) -> Result<Page<Review>, String> {
+        let review_collection = db.collection("reviews");
+        let event_oid = ObjectId::with_string(event_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        let page_request = PageRequest::new(review_filter.cursor.take(), review_filter.limit)?;
+        let filter = doc! {"event_id": event_oid};
+
+        find_page(&review_collection, filter.clone(), filter, Some(doc! {"_id": -1}), page_request).await
+    }
+
+    pub async fn update(review_id: String, edit_info: ReviewEdit, user_id: String, db: &MongoDb) -> Result<Review, String> {
+        let review_collection = db.collection("reviews");
+        let review_oid = ObjectId::with_string(review_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+        let user_oid = ObjectId::with_string(user_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        let mut update_doc = doc! {"updated_at": Utc::now().to_rfc3339()};
+        match edit_info.rating {
+            Some(i) => {
+                validate_rating(i)?;
+                update_doc.insert("rating", i)
+            },
+            None => Some(Bson::default()),
+        };
+        match edit_info.text {
+            Some(s) => update_doc.insert("text", s),
+            None => Some(Bson::default()),
+        };
+
+        let find_update_options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        // Only the author can edit a review
+        match review_collection.find_one_and_update(doc! {"_id": review_oid, "user_id": user_oid},
+                                                    doc! {"$set": update_doc},
+                                                    find_update_options
+        ).await.expect("Error updating Review") {
+            Some(review_updated) => {
+                match bson::from_bson::<Review>(bson::Bson::Document(review_updated)) {
+                    Ok(review) => {
+                        recompute_event_rating(review.event_id.clone(), db).await?;
+                        Ok(review)
+                    },
+                    Err(_) => Err("Incorrect struct, expecting review struct".to_string()),
+                }
+            },
+            None => Err("Review not found".to_string()),
+        }
+    }
+
+    pub async fn delete(review_id: String, user_id: String, db: &MongoDb) -> Result<String, String> {
+        let review_collection = db.collection("reviews");
+        let review_oid = ObjectId::with_string(review_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+        let user_oid = ObjectId::with_string(user_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        let review_found = review_collection.find_one(
+            doc! {"_id": review_oid.clone(), "user_id": user_oid},
+            FindOneOptions::default()
+        ).await.expect("Error finding review");
+
+        match review_found {
+            Some(review_doc) => {
+                let event_oid = review_doc.get_object_id("event_id")
+                    .map_err(|_| "Incorrect struct, expecting review struct".to_string())?
+                    .clone();
+
+                match review_collection.delete_one(doc! {"_id": review_oid}, DeleteOptions::default()).await {
+                    Ok(_) => {
+                        recompute_event_rating(event_oid, db).await?;
+                        Ok("Review successfully deleted".to_string())
+                    },
+                    Err(_) => Err("Error deleting review".to_string()),
+                }
+            },
+            None => Err("Review not found".to_string()),
+        }
+    }
+
+    pub async fn reply(review_id: String, reply: ReviewReplyCreate, user_id: String, db: &MongoDb) -> Result<Review, String> {
+        let review_collection = db.collection("reviews");
+        let event_collection = db.collection("events");
+        let review_oid = ObjectId::with_string(review_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+        let user_oid = ObjectId::with_string(user_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        let review = match review_collection.find_one(doc! {"_id": review_oid.clone()}, FindOneOptions::default())
+            .await.expect("Error finding review") {
+            Some(review_found) => match bson::from_bson::<Review>(bson::Bson::Document(review_found)) {
+                Ok(review) => review,
+                Err(_) => return Err("Incorrect Struct".to_string()),
+            },
+            None => return Err("Review not found".to_string()),
+        };
+
+        // Only the owner of the reviewed event can reply
+        match event_collection.find_one(
+            doc! {"_id": review.event_id, "user_id": user_oid.clone()},
+            FindOneOptions::default()
+        ).await.expect("Error finding event") {
+            Some(_) => (),
+            None => return Err("Access Denied: only the event owner can reply".to_string()),
+        }
+
+        let reply_doc = doc! {
+            "user_id": user_oid,
+            "text": reply.text,
+            "created_at": Utc::now().to_rfc3339(),
+        };
+
+        let find_update_options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        match review_collection.find_one_and_update(doc! {"_id": review_oid},
+                                                    doc! {"$set": {"reply": reply_doc}},
+                                                    find_update_options
+        ).await.expect("Error updating Review") {
+            Some(review_updated) => {
+                match bson::from_bson::<Review>(bson::Bson::Document(review_updated)) {
+                    Ok(review) => Ok(review),
+                    Err(_) => Err("Incorrect struct, expecting review struct".to_string()),
+                }
+            },
+            None => Err("Review not found".to_string()),
+        }
+    }
+
+    pub async fn create_indexes(db: &MongoDb) -> Result<(), String> {
+        let command = doc! {
+            "createIndexes": "reviews",
+            "indexes": [
+                {"key": {"event_id": 1, "user_id": 1}, "name": "event_user_unique", "unique": true},
+            ]
+        };
+
+        match db.run_command(command, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Error creating review indexes".to_string()),
+        }
+    }
+}
+
+pub fn validate_rating(rating: i32) -> Result<(), String> {
+    if (1..=5).contains(&rating) {
+        Ok(())
+    } else {
+        Err("Rating must be between 1 and 5 stars".to_string())
+    }
+}
+
+// Recalculate the event's rating and review_count from its reviews
+pub async fn recompute_event_rating(event_id: ObjectId, db: &MongoDb) -> Result<(), String> {
+    let review_collection = db.collection("reviews");
+    let event_collection = db.collection("events");
+
+    let pipeline = vec![
+        doc! {"$match": {"event_id": event_id.clone()}},
+        doc! {"$group": {"_id": Bson::Null, "rating": {"$avg": "$rating"}, "review_count": {"$sum": 1}}},
+    ];
+
+    let mut cursor = match review_collection.aggregate(pipeline, AggregateOptions::default()).await {
+        Ok(cursor) => cursor,
+        Err(_) => return Err("Error computing event rating".to_string()),
+    };
+
+    let stats: Document = match cursor.next().await {
+        Some(Ok(document)) => doc! {
+            "rating": document.get("rating").cloned().unwrap_or(Bson::Null),
+            "review_count": document.get("review_count").cloned().unwrap_or(Bson::Int32(0)),
+        },
+        Some(Err(_)) => return Err("Error computing event rating".to_string()),
+        None => doc! {"rating": Bson::Null, "review_count": 0},
+    };
+
+    match event_collection.update_one(doc! {"_id": event_id}, doc! {"$set": stats}, UpdateOptions::default()).await {
+        Ok(_) => Ok(()),
+        Err(_) => Err("Error updating event rating".to_string()),
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TravelProfile {
+    #[default]
+    Walking,
+    Driving,
+    Transit,
+}
+
+impl TravelProfile {
+    // Average door to door speed in a city
+    pub fn speed_kmh(&self) -> f64 {
+        match self {
+            TravelProfile::Walking => 4.5,
+            TravelProfile::Driving => 30.0,
+            TravelProfile::Transit => 20.0,
+        }
+    }
+
+    // Fixed minutes added to every trip: parking or waiting for a ride
+    pub fn overhead_minutes(&self) -> i64 {
+        match self {
+            TravelProfile::Walking => 0,
+            TravelProfile::Driving => 5,
+            TravelProfile::Transit => 10,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Route {
+    pub distance_meters: i64,
+    pub minutes: i64,
+}
+
+// Implemented by anything able to tell how long it takes to go between two [lng, lat]
+// points, a routing engine can replace the straight line estimate
+pub trait Router {
+    fn route(&self, from: &[f64], to: &[f64], profile: TravelProfile) -> Result<Route, String>;
+}
+
+// Great-circle distance covered at the profile's average speed
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StraightLineRouter;
+
+impl Router for StraightLineRouter {
+    fn route(&self, from: &[f64], to: &[f64], profile: TravelProfile) -> Result<Route, String> {
+        let distance_meters = great_circle_distance(from, to)?;
+        if distance_meters == 0.0 {
+            return Ok(Route { distance_meters: 0, minutes: 0 });
+        }
+        let minutes = (distance_meters / 1000.0 / profile.speed_kmh() * 60.0).ceil() as i64;
+
+        Ok(Route {
+            distance_meters: distance_meters.round() as i64,
+            minutes: minutes + profile.overhead_minutes(),
+        })
+    }
+}
+
+// Haversine distance in meters between two [lng, lat] points
+pub fn great_circle_distance(from: &[f64], to: &[f64]) -> Result<f64, String> {
+    let (from_lng, from_lat) = coordinates(from)?;
+    let (to_lng, to_lat) = coordinates(to)?;
+
+    let delta_lat = (to_lat - from_lat).to_radians();
+    let delta_lng = (to_lng - from_lng).to_radians();
+    let a = (delta_lat / 2.0).sin().powi(2)
+        + from_lat.to_radians().cos() * to_lat.to_radians().cos() * (delta_lng / 2.0).sin().powi(2);
+
+    Ok(2.0 * EARTH_RADIUS_METERS * a.sqrt().min(1.0).asin())
+}
+
+fn coordinates(location: &[f64]) -> Result<(f64, f64), String> {
+    match location {
+        [lng, lat] if lng.abs() <= 180.0 && lat.abs() <= 90.0 => Ok((*lng, *lat)),
+        _ => Err(format!("Invalid location, expecting [lng, lat]: {:?}", location)),
+    }
+}
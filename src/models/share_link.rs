@@ -0,0 +1,190 @@
+use crate::MongoDb;
+use crate::models::trip::{Trip, SharedTrip, datetime_or_rfc3339, optional_datetime_or_rfc3339, datetime_to_rfc3339};
+
+use serde::{Deserialize, Serialize};
+use bson::oid::ObjectId;
+use mongodb::bson::{doc, Bson};
+use mongodb::options::{DeleteOptions, FindOneOptions, FindOptions, InsertOneOptions};
+use futures::stream::StreamExt;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+// Read-only access to a trip for anyone holding the token. Only its hash is stored, the
+// token is shown once when the link is created and never read back
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ShareLink {
+    pub _id: ObjectId,
+    pub trip_id: ObjectId,
+    #[serde(deserialize_with = "datetime_or_rfc3339", serialize_with = "datetime_to_rfc3339")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default, deserialize_with = "optional_datetime_or_rfc3339", serialize_with = "optional_datetime_to_rfc3339")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ShareLinkCreate {
+    // Never expires when missing
+    #[serde(default, deserialize_with = "optional_datetime_or_rfc3339")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ShareLinkCreated {
+    pub _id: ObjectId,
+    pub token: String,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "optional_datetime_to_rfc3339")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl ShareLink {
+    pub async fn create(trip_id: String, link_create: ShareLinkCreate, owner_id: String, db: &MongoDb) -> Result<ShareLinkCreated, String> {
+        let share_link_collection = db.collection("share_links");
+
+        let trip = Trip::get_trip(trip_id, db).await?;
+        if !trip.owned_by(owner_id.as_str()) {
+            return Err("Only the owner can share the trip".to_string());
+        }
+        let now = Utc::now();
+        if link_create.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err("expires_at must be in the future".to_string());
+        }
+
+        let token = new_token();
+        let mut link_doc = doc! {
+            "trip_id": trip._id,
+            "token_hash": hash_token(token.as_str()),
+            "created_at": now,
+        };
+        if let Some(expires_at) = link_create.expires_at {
+            link_doc.insert("expires_at", expires_at);
+        }
+
+        match share_link_collection.insert_one(link_doc, InsertOneOptions::default()).await {
+            Ok(result) => match result.inserted_id.as_object_id() {
+                Some(link_id) => Ok(ShareLinkCreated {
+                    _id: link_id.clone(),
+                    token,
+                    expires_at: link_create.expires_at,
+                }),
+                None => Err("Error creating share link".to_string()),
+            },
+            Err(_) => Err("Error creating share link".to_string()),
+        }
+    }
+
+    pub async fn get_links(trip_id: String, owner_id: String, db: &MongoDb) -> Result<Vec<ShareLink>, String> {
+        let share_link_collection = db.collection("share_links");
+
+        let trip = Trip::get_trip(trip_id, db).await?;
+        if !trip.owned_by(owner_id.as_str()) {
+            return Err("Only the owner can see the trip's share links".to_string());
+        }
+
+        let find_options = FindOptions::builder()
+            .sort(doc! {"_id": 1})
+            .build();
+        let mut cursor = share_link_collection.find(doc! {"trip_id": trip._id}, find_options)
+            .await.map_err(|_| "Error finding share links".to_string())?;
+        let mut links = Vec::new();
+        while let Some(result) = cursor.next().await {
+            match result.map(|document| bson::from_bson::<ShareLink>(Bson::Document(document))) {
+                Ok(Ok(link)) => links.push(link),
+                _ => return Err("Error reading share links".to_string()),
+            }
+        }
+
+        Ok(links)
+    }
+
+    pub async fn revoke(trip_id: String, link_id: String, owner_id: String, db: &MongoDb) -> Result<String, String> {
+        let share_link_collection = db.collection("share_links");
+
+        let trip = Trip::get_trip(trip_id, db).await?;
+        if !trip.owned_by(owner_id.as_str()) {
+            return Err("Only the owner can revoke share links".to_string());
+        }
+        let link_oid = ObjectId::with_string(link_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        match share_link_collection.delete_one(doc! {"_id": link_oid, "trip_id": trip._id}, DeleteOptions::default()).await {
+            Ok(result) if result.deleted_count > 0 => Ok("Share link successfully revoked".to_string()),
+            Ok(_) => Err("Share link not found".to_string()),
+            Err(_) => Err("Error revoking share link".to_string()),
+        }
+    }
+
+    // Unknown, expired and revoked tokens give the same error so they cannot be told apart
+    pub async fn get_shared_trip(token: String, db: &MongoDb) -> Result<SharedTrip, String> {
+        let share_link_collection = db.collection("share_links");
+
+        let link = match share_link_collection.find_one(
+            doc! {"token_hash": hash_token(token.as_str())},
+            FindOneOptions::default()
+        ).await.map_err(|_| "Error finding share link".to_string())? {
+            Some(document) => bson::from_bson::<ShareLink>(Bson::Document(document))
+                .map_err(|_| "Incorrect Struct".to_string())?,
+            None => return Err("Shared trip not found".to_string()),
+        };
+        if !link.is_active(Utc::now()) {
+            return Err("Shared trip not found".to_string());
+        }
+
+        let trip = Trip::get_trip(link.trip_id.to_hex(), db).await
+            .map_err(|_| "Shared trip not found".to_string())?;
+        // The link is what gives access to private trips, moderation still applies
+        if trip.hidden() {
+            return Err("Shared trip not found".to_string());
+        }
+
+        // Events are expanded as an anonymous viewer sees them
+        let mut trips = vec![trip];
+        Trip::expand_events(&mut trips, None, db).await?;
+
+        Ok(trips.remove(0).shared_view())
+    }
+
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+
+    pub async fn create_indexes(db: &MongoDb) -> Result<(), String> {
+        let command = doc! {
+            "createIndexes": "share_links",
+            "indexes": [
+                {"key": {"token_hash": 1}, "name": "token_hash_unique", "unique": true},
+                {"key": {"trip_id": 1}, "name": "trip_id"},
+            ]
+        };
+
+        match db.run_command(command, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Error creating share link indexes".to_string()),
+        }
+    }
+}
+
+// Two random UUIDs, 244 random bits, URL safe
+pub fn new_token() -> String {
+    let mut bytes = Vec::with_capacity(32);
+    bytes.extend_from_slice(Uuid::new_v4().as_bytes());
+    bytes.extend_from_slice(Uuid::new_v4().as_bytes());
+
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn optional_datetime_to_rfc3339<S>(date: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+{
+    match date {
+        Some(date) => serializer.serialize_str(date.to_rfc3339().as_str()),
+        None => serializer.serialize_none(),
+    }
+}
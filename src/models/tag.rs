@@ -0,0 +1,346 @@
+use crate::MongoDb;
+use crate::models::user::User;
+
+use serde::{Deserialize, Serialize};
+use bson::oid::ObjectId;
+use mongodb::bson::{doc, Bson};
+use mongodb::options::{
+    InsertOneOptions,
+    FindOptions,
+    FindOneAndUpdateOptions,
+    UpdateOptions,
+    AggregateOptions,
+    ReturnDocument
+};
+use futures::stream::StreamExt;
+use chrono::Utc;
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TagKind {
+    Tag,
+    PersonalType,
+}
+
+// Canonical term, events only store the canonical name
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Tag {
+    pub _id: ObjectId,
+    pub name: String,
+    pub kind: TagKind,
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+    // Label per language code, e.g. {"es": "playa"}
+    #[serde(default)]
+    pub translations: HashMap<String, String>,
+    pub updated_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TagCreate {
+    pub name: String,
+    pub kind: TagKind,
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+    #[serde(default)]
+    pub translations: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TagEdit {
+    pub synonyms: Option<Vec<String>>,
+    pub translations: Option<HashMap<String, String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TagFilter {
+    pub kind: Option<TagKind>,
+    pub lang: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TagUsage {
+    pub name: String,
+    pub kind: TagKind,
+    // Translated name when a language was requested
+    pub label: String,
+    pub synonyms: Vec<String>,
+    pub translations: HashMap<String, String>,
+    pub count: i64,
+}
+
+impl Tag {
+    pub async fn get_tags(tag_filter: TagFilter, db: &MongoDb) -> Result<Vec<TagUsage>, String> {
+        let taxonomy = Taxonomy::load(db).await?;
+        let tag_counts = count_usage("$tags", true, db).await?;
+        let type_counts = count_usage("$personal_type", false, db).await?;
+
+        let usages = taxonomy.tags.into_iter()
+            .filter(|tag| tag_filter.kind.is_none_or(|kind| kind == tag.kind))
+            .map(|tag| {
+                let counts = match tag.kind {
+                    TagKind::Tag => &tag_counts,
+                    TagKind::PersonalType => &type_counts,
+                };
+                let label = tag_filter.lang.as_ref()
+                    .and_then(|lang| tag.translations.get(lang))
+                    .cloned()
+                    .unwrap_or_else(|| tag.name.clone());
+
+                TagUsage {
+                    count: counts.get(&tag.name).cloned().unwrap_or(0),
+                    name: tag.name,
+                    kind: tag.kind,
+                    label,
+                    synonyms: tag.synonyms,
+                    translations: tag.translations,
+                }
+            })
+            .collect();
+
+        Ok(usages)
+    }
+
+    pub async fn create(tag: TagCreate, admin_id: String, db: &MongoDb) -> Result<ObjectId, String> {
+        let tag_collection = db.collection("tags");
+
+        User::check_admin(admin_id, db).await?;
+
+        let name = normalize_term(tag.name.as_str());
+        let synonyms: Vec<String> = tag.synonyms.iter().map(|s| normalize_term(s)).collect();
+        if name.is_empty() {
+            return Err("Tag name cannot be empty".to_string());
+        }
+
+        // A term can only point to a single canonical tag
+        let taxonomy = Taxonomy::load(db).await?;
+        taxonomy.check_free(std::iter::once(&name).chain(synonyms.iter()).chain(tag.translations.values()), tag.kind, None)?;
+
+        let tag_doc = doc! {
+            "name": name,
+            "kind": bson::to_bson(&tag.kind).unwrap_or(Bson::Null),
+            "synonyms": synonyms,
+            "translations": bson::to_bson(&tag.translations).unwrap_or(Bson::Null),
+            "updated_at": Utc::now().to_rfc3339(),
+        };
+
+        match tag_collection.insert_one(tag_doc, InsertOneOptions::default()).await {
+            Ok(result) => Ok(result.inserted_id.as_object_id().unwrap().clone()),
+            Err(_) => Err("Error inserting tag".to_string()),
+        }
+    }
+
+    pub async fn update(tag_id: String, edit_info: TagEdit, admin_id: String, db: &MongoDb) -> Result<Tag, String> {
+        let tag_collection = db.collection("tags");
+        let tag_oid = ObjectId::with_string(tag_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        User::check_admin(admin_id, db).await?;
+
+        let taxonomy = Taxonomy::load(db).await?;
+        let tag = match taxonomy.tags.iter().find(|tag| tag._id == tag_oid) {
+            Some(tag) => tag,
+            None => return Err("Tag not found".to_string()),
+        };
+
+        let mut update_doc = doc! {"updated_at": Utc::now().to_rfc3339()};
+        match edit_info.synonyms {
+            Some(v) => {
+                let synonyms: Vec<String> = v.iter().map(|s| normalize_term(s)).collect();
+                taxonomy.check_free(synonyms.iter(), tag.kind, Some(&tag_oid))?;
+                update_doc.insert("synonyms", synonyms)
+            },
+            None => Some(Bson::default()),
+        };
+        match edit_info.translations {
+            Some(m) => {
+                taxonomy.check_free(m.values(), tag.kind, Some(&tag_oid))?;
+                update_doc.insert("translations", bson::to_bson(&m).unwrap_or(Bson::Null))
+            },
+            None => Some(Bson::default()),
+        };
+
+        let find_update_options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        match tag_collection.find_one_and_update(doc! {"_id": tag_oid},
+                                                 doc! {"$set": update_doc},
+                                                 find_update_options
+        ).await.expect("Error updating Tag") {
+            Some(tag_updated) => {
+                match bson::from_bson::<Tag>(bson::Bson::Document(tag_updated)) {
+                    Ok(tag) => Ok(tag),
+                    Err(_) => Err("Incorrect struct, expecting tag struct".to_string()),
+                }
+            },
+            None => Err("Tag not found".to_string()),
+        }
+    }
+
+    pub async fn create_indexes(db: &MongoDb) -> Result<(), String> {
+        let command = doc! {
+            "createIndexes": "tags",
+            "indexes": [
+                {"key": {"kind": 1, "name": 1}, "name": "kind_name_unique", "unique": true},
+            ]
+        };
+
+        match db.run_command(command, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Error creating tag indexes".to_string()),
+        }
+    }
+}
+
+pub struct Taxonomy {
+    pub tags: Vec<Tag>,
+}
+
+impl Taxonomy {
+    pub async fn load(db: &MongoDb) -> Result<Taxonomy, String> {
+        let tag_collection = db.collection("tags");
+
+        let mut cursor = match tag_collection.find(doc! {}, FindOptions::default()).await {
+            Ok(cursor) => cursor,
+            Err(_) => return Err("Error finding tags".to_string()),
+        };
+        let mut tags = Vec::new();
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) =>
+                    match bson::from_bson::<Tag>(bson::Bson::Document(document)) {
+                        Ok(tag) => tags.push(tag),
+                        Err(e) => println!("{:?}", e),
+                    },
+                Err(_) => println!("Error retrieving Document"),
+            }
+        }
+
+        Ok(Taxonomy { tags })
+    }
+
+    // Matches the canonical name, a synonym or a translation, ignoring case
+    pub fn find(&self, term: &str, kind: TagKind) -> Option<&Tag> {
+        let term = normalize_term(term);
+        self.tags.iter()
+            .filter(|tag| tag.kind == kind)
+            .find(|tag| {
+                tag.name == term
+                    || tag.synonyms.iter().any(|synonym| normalize_term(synonym) == term)
+                    || tag.translations.values().any(|label| normalize_term(label) == term)
+            })
+    }
+
+    // Fails when another tag of the kind already claims one of the terms
+    pub fn check_free<'a>(&self, terms: impl Iterator<Item = &'a String>, kind: TagKind, tag_id: Option<&ObjectId>) -> Result<(), String> {
+        for term in terms {
+            if let Some(existing) = self.find(term, kind).filter(|t| Some(&t._id) != tag_id) {
+                return Err(format!("Term {} is already used by tag {}", normalize_term(term), existing.name));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Until the taxonomy has terms of a kind, any term of that kind is accepted as it is so
+    // fresh deployments can create events. migrate-tags maps them once it is filled
+    pub fn canonical(&self, term: &str, kind: TagKind) -> Result<String, String> {
+        match self.find(term, kind) {
+            Some(tag) => Ok(tag.name.clone()),
+            None if !self.tags.iter().any(|tag| tag.kind == kind) => Ok(normalize_term(term)),
+            None => match kind {
+                TagKind::Tag => Err(format!("Unknown tag: {}", term)),
+                TagKind::PersonalType => Err(format!("Unknown personal type: {}", term)),
+            },
+        }
+    }
+
+    pub fn canonical_tags(&self, tags: &[String]) -> Result<Vec<String>, String> {
+        let mut canonical_tags: Vec<String> = Vec::new();
+        for tag in tags {
+            let canonical = self.canonical(tag.as_str(), TagKind::Tag)?;
+            if !canonical_tags.contains(&canonical) {
+                canonical_tags.push(canonical);
+            }
+        }
+
+        Ok(canonical_tags)
+    }
+
+    // Used for filters, unknown terms are kept so they simply match nothing
+    pub fn canonical_or_term(&self, term: &str, kind: TagKind) -> String {
+        self.canonical(term, kind).unwrap_or_else(|_| normalize_term(term))
+    }
+}
+
+pub fn normalize_term(term: &str) -> String {
+    term.trim().to_lowercase()
+}
+
+// Map every stored tag and personal_type to its canonical name
+pub async fn migrate_tags(db: &MongoDb) -> Result<String, String> {
+    let event_collection = db.collection("events");
+    let taxonomy = Taxonomy::load(db).await?;
+    let mut migrated = 0;
+    let mut unmapped: Vec<String> = Vec::new();
+
+    let mut cursor = event_collection.find(doc! {}, FindOptions::default())
+        .await.map_err(|_| "Error finding events".to_string())?;
+    while let Some(Ok(document)) = cursor.next().await {
+        let tags: Vec<String> = document.get_array("tags")
+            .map(|tags| tags.iter().filter_map(|tag| tag.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default();
+        let personal_type = document.get_str("personal_type").unwrap_or("").to_string();
+
+        let mut canonical_tags: Vec<String> = Vec::new();
+        for tag in tags.iter() {
+            if taxonomy.find(tag, TagKind::Tag).is_none() && !unmapped.contains(tag) {
+                unmapped.push(tag.clone());
+            }
+            let canonical = taxonomy.canonical_or_term(tag, TagKind::Tag);
+            if !canonical_tags.contains(&canonical) {
+                canonical_tags.push(canonical);
+            }
+        }
+        if taxonomy.find(personal_type.as_str(), TagKind::PersonalType).is_none() && !unmapped.contains(&personal_type) {
+            unmapped.push(personal_type.clone());
+        }
+        let canonical_type = taxonomy.canonical_or_term(personal_type.as_str(), TagKind::PersonalType);
+
+        if canonical_tags != tags || canonical_type != personal_type {
+            event_collection.update_one(doc! {"_id": document.get("_id").cloned().unwrap_or(Bson::Null)},
+                                        doc! {"$set": {"tags": canonical_tags, "personal_type": canonical_type}},
+                                        UpdateOptions::default()
+            ).await.map_err(|_| "Error updating event".to_string())?;
+            migrated += 1;
+        }
+    }
+
+    Ok(format!("Migrated {} events, terms without a canonical tag: {}", migrated, unmapped.join(", ")))
+}
+
+// Number of events per value of the given field
+async fn count_usage(field: &str, unwind: bool, db: &MongoDb) -> Result<HashMap<String, i64>, String> {
+    let event_collection = db.collection("events");
+
+    let mut pipeline = vec![doc! {"$match": {"deleted_at": Bson::Null}}];
+    if unwind {
+        pipeline.push(doc! {"$unwind": field});
+    }
+    pipeline.push(doc! {"$group": {"_id": field, "count": {"$sum": 1}}});
+
+    let mut cursor = match event_collection.aggregate(pipeline, AggregateOptions::default()).await {
+        Ok(cursor) => cursor,
+        Err(_) => return Err("Error counting tag usage".to_string()),
+    };
+    let mut counts = HashMap::new();
+    while let Some(Ok(document)) = cursor.next().await {
+        if let (Ok(name), Ok(count)) = (document.get_str("_id"), document.get_i32("count")) {
+            counts.insert(name.to_string(), count as i64);
+        }
+    }
+
+    Ok(counts)
+}
@@ -0,0 +1,204 @@
+use crate::MongoDb;
+use crate::models::report::ContentType;
+use crate::models::trip::Trip;
+
+use serde::{Deserialize, Serialize};
+use bson::oid::ObjectId;
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::options::{
+    FindOptions,
+    FindOneAndUpdateOptions,
+    UpdateOptions,
+    DeleteOptions,
+    ReturnDocument
+};
+use futures::stream::StreamExt;
+use chrono::{DateTime, Duration, Utc};
+
+pub const DEFAULT_RETENTION_DAYS: i64 = 30;
+// Minutes between two runs of the background purge
+pub const PURGE_INTERVAL_MINUTES: u64 = 60;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TrashItem {
+    pub content_type: ContentType,
+    pub _id: ObjectId,
+    pub name: String,
+    pub deleted_at: String,
+    // After this date the item is removed for good
+    pub purge_at: String,
+}
+
+// Days a deleted trip or event can still be restored
+pub fn retention_days() -> i64 {
+    std::env::var("TRASH_RETENTION_DAYS").ok()
+        .and_then(|days| days.parse::<i64>().ok())
+        .filter(|days| *days >= 0)
+        .unwrap_or(DEFAULT_RETENTION_DAYS)
+}
+
+// Without an owner any content matches, used when admins delete. Returns the content
+// as it was before
+pub async fn soft_delete(content_type: ContentType,
+                         content_id: ObjectId,
+                         owner_id: Option<ObjectId>,
+                         deleted_by: ObjectId,
+                         db: &MongoDb
+) -> Result<Document, String> {
+    let content_collection = db.collection(content_type.collection_name());
+
+    let find_update_options = FindOneAndUpdateOptions::builder()
+        .return_document(ReturnDocument::Before)
+        .build();
+
+    let mut filter = doc! {"_id": content_id, "deleted_at": Bson::Null};
+    if let Some(owner_id) = owner_id {
+        filter.insert("user_id", owner_id);
+    }
+
+    match content_collection.find_one_and_update(
+        filter,
+        doc! {"$set": {"deleted_at": Utc::now().to_rfc3339(), "deleted_by": deleted_by}, "$inc": {"version": 1}},
+        find_update_options
+    ).await {
+        Ok(Some(content)) => Ok(content),
+        Ok(None) => Err(not_found(content_type)),
+        Err(_) => Err("Error deleting content".to_string()),
+    }
+}
+
+// Content deleted by an admin is not in the owner's trash
+pub async fn get_trash(user_id: String, db: &MongoDb) -> Result<Vec<TrashItem>, String> {
+    let user_oid = ObjectId::with_string(user_id.as_str())
+        .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+    let retention = Duration::days(retention_days());
+
+    let find_options = FindOptions::builder()
+        .projection(doc! {"name": 1, "deleted_at": 1})
+        .sort(doc! {"deleted_at": -1})
+        .build();
+
+    let mut items = Vec::new();
+    for content_type in [ContentType::Trip, ContentType::Event] {
+        let mut cursor = match db.collection(content_type.collection_name()).find(
+            doc! {"user_id": user_oid.clone(), "deleted_by": user_oid.clone(), "deleted_at": {"$ne": Bson::Null}},
+            find_options.clone()
+        ).await {
+            Ok(cursor) => cursor,
+            Err(_) => return Err("Error finding deleted content".to_string()),
+        };
+
+        while let Some(Ok(document)) = cursor.next().await {
+            let deleted_at = document.get_str("deleted_at").unwrap_or("").to_string();
+            let purge_at = match DateTime::parse_from_rfc3339(deleted_at.as_str()) {
+                Ok(date) => (date.with_timezone(&Utc) + retention).to_rfc3339(),
+                Err(_) => continue,
+            };
+            if let Ok(content_id) = document.get_object_id("_id") {
+                items.push(TrashItem {
+                    content_type,
+                    _id: content_id.clone(),
+                    name: document.get_str("name").unwrap_or("").to_string(),
+                    deleted_at,
+                    purge_at,
+                });
+            }
+        }
+    }
+
+    Ok(items)
+}
+
+pub async fn restore(content_type: ContentType, content_id: String, user_id: String, db: &MongoDb) -> Result<String, String> {
+    let content_collection = db.collection(content_type.collection_name());
+    let content_oid = ObjectId::with_string(content_id.as_str())
+        .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+    let user_oid = ObjectId::with_string(user_id.as_str())
+        .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+    match content_collection.update_one(
+        doc! {"_id": content_oid.clone(), "user_id": user_oid.clone(), "deleted_by": user_oid, "deleted_at": {"$ne": Bson::Null}},
+        doc! {"$unset": {"deleted_at": "", "deleted_by": ""}, "$inc": {"version": 1}},
+        UpdateOptions::default()
+    ).await {
+        Ok(result) if result.modified_count > 0 => (),
+        Ok(_) => return Err(not_found(content_type)),
+        Err(_) => return Err("Error restoring content".to_string()),
+    }
+
+    if content_type == ContentType::Event {
+        Trip::restore_entries(content_oid, db).await?;
+    }
+
+    Ok("Successfully restored".to_string())
+}
+
+// Remove everything deleted before the retention period. Entries of other users'
+// trips pointing to a purged event are kept and show up as unavailable
+pub async fn purge_expired(db: &MongoDb) -> Result<String, String> {
+    let event_collection = db.collection("events");
+    let trip_collection = db.collection("trips");
+    let cutoff = (Utc::now() - Duration::days(retention_days())).to_rfc3339();
+    let expired = doc! {"deleted_at": {"$ne": Bson::Null, "$lt": cutoff}};
+
+    let find_options = FindOptions::builder()
+        .projection(doc! {"_id": 1})
+        .build();
+    let mut cursor = event_collection.find(expired.clone(), find_options)
+        .await.map_err(|_| "Error finding expired events".to_string())?;
+    let mut event_ids: Vec<ObjectId> = Vec::new();
+    while let Some(Ok(document)) = cursor.next().await {
+        if let Ok(event_id) = document.get_object_id("_id") {
+            event_ids.push(event_id.clone());
+        }
+    }
+
+    for collection_name in ["reviews", "favorites"] {
+        db.collection(collection_name).delete_many(doc! {"event_id": {"$in": event_ids.clone()}}, DeleteOptions::default())
+            .await.map_err(|_| format!("Error purging {}", collection_name))?;
+    }
+    let events_purged = event_collection.delete_many(doc! {"_id": {"$in": event_ids}}, DeleteOptions::default())
+        .await.map_err(|_| "Error purging events".to_string())?
+        .deleted_count;
+    let trips_purged = trip_collection.delete_many(expired, DeleteOptions::default())
+        .await.map_err(|_| "Error purging trips".to_string())?
+        .deleted_count;
+
+    Ok(format!("Purged {} trips and {} events", trips_purged, events_purged))
+}
+
+// Runs for the lifetime of the server
+pub async fn purge_periodically(db: MongoDb) {
+    let mut interval = actix_rt::time::interval(std::time::Duration::from_secs(PURGE_INTERVAL_MINUTES * 60));
+    loop {
+        interval.tick().await;
+        match purge_expired(&db).await {
+            Ok(msg) => log::info!("{}", msg),
+            Err(e) => log::error!("{}", e),
+        }
+    }
+}
+
+pub async fn create_indexes(db: &MongoDb) -> Result<(), String> {
+    for collection_name in ["events", "trips"] {
+        let command = doc! {
+            "createIndexes": collection_name,
+            "indexes": [
+                {"key": {"user_id": 1, "deleted_at": 1}, "name": "user_deleted_at"},
+                {"key": {"deleted_at": 1}, "name": "deleted_at"},
+            ]
+        };
+        if db.run_command(command, None).await.is_err() {
+            return Err("Error creating trash indexes".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+fn not_found(content_type: ContentType) -> String {
+    match content_type {
+        ContentType::Event => "Event not found".to_string(),
+        ContentType::Trip => "Trip not found".to_string(),
+    }
+}
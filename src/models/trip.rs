@@ -1,55 +1,207 @@
 use crate::MongoDb;
 use crate::utils::custom_visitors::ObjectIdVisitor;
+use crate::utils::pagination::{find_page, Page, PageRequest};
+use crate::models::event::Event;
+use crate::models::opening_hours::parse_time;
+use crate::models::currency::{Money, CurrencyConverter, base_currency};
+use crate::models::place::{resolve_destination, destination_timezone};
+use crate::models::report::{ContentType, ModerationStatus, set_moderation_status};
+use crate::models::audit::{AuditAction, AuditEntry, AuditRecord};
+use crate::models::trash::soft_delete;
+use crate::models::user::User;
+use crate::models::itinerary::ItineraryStop;
+use crate::models::conflict::{EntryConflict, entry_conflicts};
+use crate::models::collaborator::{Collaborator, CollaboratorRole, InvitationStatus};
+use crate::models::trip_hub::{TripChange, TripHub};
+use crate::utils::etag::{VERSION_MISMATCH, version_filter};
+use crate::utils::timezone::{DEFAULT_TIMEZONE, parse_timezone, parse_rfc3339, localize, local_datetime, local_date};
 
 use serde::{de, Deserialize, Serialize};
 use bson::oid::ObjectId;
 use mongodb::bson::{doc, Bson, Document};
 use mongodb::options::{
+    AggregateOptions,
     FindOneOptions,
-    FindOptions,
     InsertOneOptions,
     FindOneAndUpdateOptions,
     UpdateOptions,
     CountOptions,
-    DeleteOptions,
+    FindOptions,
     ReturnDocument
 };
+use futures::stream::StreamExt;
 use std::borrow::Borrow;
+use std::collections::HashMap;
 use std::clone::Clone;
-use futures::stream::StreamExt;
 use std::error::Error;
-use chrono::{DateTime, NaiveDateTime, Utc, TimeZone};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc, TimeZone};
+use chrono_tz::Tz;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct EventEntry {
     _id: ObjectId,
-    event_id: ObjectId,
-    start_date: String,
-    start_hour: String,
-    budget: f32,
-    duration: i32,
+    pub event_id: ObjectId,
+    // Start of the entry, start_hour repeats its time in the trip's timezone
+    #[serde(deserialize_with = "datetime_or_rfc3339", serialize_with = "datetime_to_rfc3339")]
+    pub start_date: DateTime<Utc>,
+    pub start_hour: String,
+    pub budget: Money,
+    pub duration: i32,
+    // Pinned by the owner, the optimizer never moves it
+    #[serde(default)]
+    pub fixed: bool,
+    // The event was deleted, the snapshot keeps what the entry pointed to
+    #[serde(default)]
+    pub orphaned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<EventSnapshot>,
+    // Set when the event was deleted or cannot be shown to the viewer, never stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unavailable: Option<bool>,
+    // Filled with expand=events, never stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<EntryEvent>,
+}
+
+// Event details embedded in an entry
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EntryEvent {
+    pub name: String,
+    pub image: String,
+    pub city: String,
+    pub location: Option<Vec<f64>>,
+    pub price: Money,
+    // Minutes
+    #[serde(default)]
+    pub duration: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ExpandQuery {
+    // Comma separated, only "events" is supported
+    pub expand: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EventSnapshot {
+    pub name: String,
+    pub price: Money,
+    pub deleted_at: String,
+}
+
+// Result of adding an entry, warnings and warning conflicts do not prevent the entry
+// from being added
 #[derive(Serialize, Deserialize, Debug)]
+pub struct EntryResult {
+    pub message: String,
+    pub added: bool,
+    pub warnings: Vec<String>,
+    pub conflicts: Vec<EntryConflict>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Trip {
-    _id: ObjectId,
+    pub _id: ObjectId,
     pub name: String,
-    start_date: String,
-    end_date: String,
-    budget: f32,
+    #[serde(deserialize_with = "datetime_or_rfc3339", serialize_with = "datetime_to_rfc3339")]
+    pub start_date: DateTime<Utc>,
+    #[serde(deserialize_with = "datetime_or_rfc3339", serialize_with = "datetime_to_rfc3339")]
+    pub end_date: DateTime<Utc>,
+    // IANA name, days and entry hours are local to it
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    pub budget: Money,
+    // Budget converted to the base currency, used to filter
+    #[serde(default)]
+    pub budget_base: i64,
+    // Spending cap set by the owner, entries going over it get a warning
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_limit: Option<Money>,
+    // Budget in the requester's currency, never stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    converted_budget: Option<Money>,
     destination: String,
-    events: Vec<EventEntry>,
+    pub events: Vec<EventEntry>,
     private: bool,
+    #[serde(default)]
+    moderation_status: ModerationStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    moderation_reason: Option<String>,
+    // Trip this one was forked from and the original the chain of forks started at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_from: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fork_root: Option<ObjectId>,
+    #[serde(default)]
+    pub fork_count: i64,
+    #[serde(default)]
+    pub collaborators: Vec<Collaborator>,
+    // Source of a fork as the viewer can see it, never stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribution: Option<ForkAttribution>,
+    // Grows with every change to the trip or its entries, counters left aside
+    #[serde(default)]
+    pub version: i64,
     user_id: ObjectId
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+// What a share link shows, nothing about the owner or the other members
+#[derive(Serialize, Debug)]
+pub struct SharedTrip {
+    pub name: String,
+    #[serde(serialize_with = "datetime_to_rfc3339")]
+    pub start_date: DateTime<Utc>,
+    #[serde(serialize_with = "datetime_to_rfc3339")]
+    pub end_date: DateTime<Utc>,
+    pub timezone: String,
+    pub budget: Money,
+    pub destination: String,
+    pub events: Vec<SharedEntry>,
+}
+
+// An entry without its _id, which is the id of the trip
+#[derive(Serialize, Debug)]
+pub struct SharedEntry {
+    pub event_id: ObjectId,
+    #[serde(serialize_with = "datetime_to_rfc3339")]
+    pub start_date: DateTime<Utc>,
+    pub start_hour: String,
+    pub budget: Money,
+    pub duration: i32,
+    pub orphaned: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<EventSnapshot>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unavailable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<EntryEvent>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ForkAttribution {
+    pub trip_id: ObjectId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<ObjectId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct TripFilter {
-    pub offset: i64,
-    pub limit: i64,
-    pub budget_gt: Option<f32>,
-    pub budget_lt: Option<f32>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    // Minor units of `currency`, or of the base currency when it is not given
+    pub budget_gt: Option<i64>,
+    pub budget_lt: Option<i64>,
+    pub currency: Option<String>,
+    // Listing your own trips also gives the ones you collaborate on
     pub user_id: Option<String>,
+    pub forked_from: Option<String>,
+    // Authenticated user, owners also see their hidden trips
+    #[serde(skip)]
+    pub requester_id: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -66,7 +218,7 @@ impl Trip {
         match ObjectId::with_string(trip_id.as_str().as_ref()) {
             Ok(oi) => {
                 match trip_collection.find_one(
-                    doc! {"_id": oi},
+                    doc! {"_id": oi, "deleted_at": Bson::Null},
                     FindOneOptions::default()
                 ).await.expect("Error finding trip"){
                     Some(trip_found) => {
@@ -82,86 +234,117 @@ impl Trip {
         }
     }
 
-    pub async fn get_filtered_trips(trip_filter: TripFilter, db: &MongoDb) -> Vec<Trip> {
+    pub async fn get_filtered_trips(mut trip_filter: TripFilter, db: &MongoDb) -> Result<Page<Trip>, String> {
         let trip_collection = db.collection("trips");
 
-        // Create a custom find option
-        let find_options = FindOptions::builder()
-            .limit(trip_filter.limit)
-            .skip(trip_filter.offset)
-            .build();
+        let page_request = PageRequest::new(trip_filter.cursor.take(), trip_filter.limit)?;
+        budget_range_to_base(&mut trip_filter, db).await?;
+        let requester_id = trip_filter.requester_id.clone();
 
         // Get custom filter
         let filter = get_find_filter(trip_filter);
 
-        let mut cursor = trip_collection.find(filter, find_options).await.expect("Error finding collection");
-        let mut trips = Vec::new();
-        while let Some(result) = cursor.next().await {
-            match result {
-                Ok(document) =>
-                    match bson::from_bson::<Trip>(bson::Bson::Document(document)) {
-                        Ok(trip) => trips.push(trip),
-                        Err(e) => println!("{:?}", e),
-                    },
-                Err(_) => println!("Error retrieving Document"),
-            }
+        let mut trips: Page<Trip> = find_page(&trip_collection, filter.clone(), filter, None, page_request).await?;
+        for trip in trips.items.iter_mut() {
+            trip.hide_collaborators_from(requester_id.as_ref());
         }
 
-        trips
+        Ok(trips)
     }
 
-    pub async fn count_filtered_trips(trip_filter: TripFilter, db: &MongoDb) -> Result<i64, String> {
+    pub async fn count_filtered_trips(mut trip_filter: TripFilter, db: &MongoDb) -> Result<i64, String> {
         let trip_collection = db.collection("trips");
-
-        // Create a custom find option
-        let count_options = CountOptions::builder()
-            .limit(trip_filter.limit)
-            .skip(trip_filter.offset)
-            .build();
+        budget_range_to_base(&mut trip_filter, db).await?;
 
         // Get custom filter
         let filter = get_find_filter(trip_filter);
 
-        match trip_collection.count_documents(filter, count_options).await {
+        match trip_collection.count_documents(filter, CountOptions::default()).await {
             Ok(count) => Ok(count),
             Err(_) => Err("Error counting document".to_string()),
         }
     }
 
-    pub async fn create(trip: TripCreate, db: &MongoDb) -> ObjectId {
+    pub async fn create(mut trip: TripCreate, db: &MongoDb) -> Result<ObjectId, String> {
         let trip_collection = db.collection("trips");
 
-        (*trip_collection
-            .insert_one(trip.to_doc(), InsertOneOptions::default())
+        trip.budget.validate()?;
+        if let Some(budget_limit) = trip.budget_limit.as_ref() {
+            budget_limit.validate()?;
+        }
+        validate_dates(&trip.start_date, &trip.end_date)?;
+        trip.destination = resolve_destination(trip.destination.as_str(), db).await?;
+        trip.timezone = match trip.timezone.take() {
+            Some(timezone) => Some(parse_timezone(timezone.as_str())?.name().to_string()),
+            None => Some(destination_timezone(trip.destination.as_str(), db).await),
+        };
+        let mut trip_doc = trip.to_doc();
+        trip_doc.insert("budget_base", CurrencyConverter::load(db).await?.to_base(&trip.budget)?.amount);
+
+        Ok((*trip_collection
+            .insert_one(trip_doc, InsertOneOptions::default())
             .await
             .expect("Error in insert user")
             .inserted_id
             .as_object_id()
             .unwrap())
-            .clone()
+            .clone())
     }
 
-    pub async fn update(edit_info: TripEdit, db: &MongoDb) -> Result<Trip, String> {
+    // With an expected version the update only goes through if nobody changed the trip since
+    pub async fn update(edit_info: TripEdit, expected_version: Option<i64>, user_id: String, hub: &TripHub, db: &MongoDb) -> Result<Trip, String> {
         let trip_collection = db.collection("trips");
+        let trip = Trip::get_trip(edit_info._id.to_hex(), db).await?;
+        if !trip.editable_by(user_id.as_str()) {
+            return Err("Only the owner and editors can edit the trip".to_string());
+        }
+        check_version(&trip, expected_version)?;
         let mut update_doc = doc!{};
         match edit_info.name {
             Some(s) => update_doc.insert("name", s),
             None => Some(Bson::default()),
         };
-        match edit_info.start_date {
-            Some(s) => update_doc.insert("start_date", s),
-            None => Some(Bson::default()),
+        let destination = match edit_info.destination {
+            Some(s) => Some(resolve_destination(s.as_str(), db).await?),
+            None => None,
         };
-        match edit_info.end_date {
-            Some(s) => update_doc.insert("end_date", s),
-            None => Some(Bson::default()),
+        let timezone = match (edit_info.timezone, destination.as_ref()) {
+            (Some(s), _) => Some(parse_timezone(s.as_str())?),
+            (None, Some(destination)) => Some(parse_timezone(destination_timezone(destination.as_str(), db).await.as_str())?),
+            (None, None) => None,
         };
+        // Entries are moved by position, so the trip must not have changed since it was read
+        let moved = timezone.is_some_and(|tz| tz != trip.tz());
+        if let Some(tz) = timezone {
+            if moved {
+                update_doc.extend(trip.moved_to_timezone(tz));
+            }
+            update_doc.insert("timezone", tz.name());
+        }
+        if let Some(destination) = destination {
+            update_doc.insert("destination", destination);
+        }
+        if edit_info.start_date.is_some() || edit_info.end_date.is_some() {
+            // Dates are checked together with the one not being edited
+            let start_date = edit_info.start_date.unwrap_or(*update_doc.get_datetime("start_date").unwrap_or(&trip.start_date));
+            let end_date = edit_info.end_date.unwrap_or(*update_doc.get_datetime("end_date").unwrap_or(&trip.end_date));
+            validate_dates(&start_date, &end_date)?;
+            update_doc.insert("start_date", start_date);
+            update_doc.insert("end_date", end_date);
+        }
         match edit_info.budget {
-            Some(f) => update_doc.insert("budget", f),
+            Some(m) => {
+                m.validate()?;
+                update_doc.insert("budget_base", CurrencyConverter::load(db).await?.to_base(&m)?.amount);
+                update_doc.insert("budget", m.to_bson())
+            },
             None => Some(Bson::default()),
         };
-        match edit_info.destination {
-            Some(s) => update_doc.insert("destination", s),
+        match edit_info.budget_limit {
+            Some(m) => {
+                m.validate()?;
+                update_doc.insert("budget_limit", m.to_bson())
+            },
             None => Some(Bson::default()),
         };
         match edit_info.private {
@@ -173,175 +356,735 @@ impl Trip {
             .return_document(ReturnDocument::After)
             .build();
 
-        match trip_collection.find_one_and_update(doc!{"_id": edit_info._id},
-                                                  doc!{"$set": update_doc},
+        let checked_version = match moved {
+            true => expected_version.or(Some(trip.version)),
+            false => expected_version,
+        };
+        let mut filter = doc!{"_id": edit_info._id, "deleted_at": Bson::Null};
+        if let Some(version) = checked_version {
+            filter.insert("version", version_filter(version));
+        }
+
+        match trip_collection.find_one_and_update(filter,
+                                                  doc!{"$set": update_doc, "$inc": {"version": 1}},
                                                         find_update_options
         ).await.expect("Error updating Trip") {
             Some(trip_updated) => {
                 match bson::from_bson::<Trip>(bson::Bson::Document(trip_updated)) {
-                    Ok(trip) => Ok(trip),
+                    Ok(trip) => {
+                        hub.publish(&trip._id, user_id.as_str(), TripChange::TripUpdated { trip: trip.clone() });
+                        Ok(trip)
+                    },
                     Err(_) => Err("Incorrect struct, expecting trip struct".to_string())
                 }
             },
+            None if checked_version.is_some() => Err(VERSION_MISMATCH.to_string()),
             None => Err("Trip not found".to_string()),
         }
     }
 
-    pub async fn push_event_entry(event_entry: EventEntry, db: &MongoDb) -> Result<String, String> {
+    // Fields to set so the trip's dates and entries keep their local time in another
+    // timezone, entries are set by position
+    pub fn moved_to_timezone(&self, tz: Tz) -> Document {
+        let old_tz = self.tz();
+        let mut moved = doc! {
+            "start_date": localize(local_datetime(&self.start_date, old_tz), tz),
+            "end_date": localize(local_datetime(&self.end_date, old_tz), tz),
+        };
+        for (i, event_entry) in self.events.iter().enumerate() {
+            let start = event_entry.start_datetime(old_tz)
+                .unwrap_or_else(|| local_datetime(&event_entry.start_date, old_tz));
+            moved.insert(format!("events.{}.start_date", i), localize(start, tz));
+            moved.insert(format!("events.{}.start_hour", i), start.format("%H:%M").to_string());
+        }
+
+        moved
+    }
+
+    pub async fn push_event_entry(mut event_entry: EventEntry, expected_version: Option<i64>, user_id: String, hub: &TripHub, db: &MongoDb) -> Result<EntryResult, String> {
         let trip_collection = db.collection("trips");
 
-        let update_query = doc! {
-            "$push": {"events": event_entry.borrow().to_doc()},
-            "$inc": {"budget": event_entry.budget}
+        let trip = Trip::get_trip(event_entry._id.to_hex(), db).await?;
+        if !trip.editable_by(user_id.as_str()) {
+            return Err("Only the owner and editors can change the trip's entries".to_string());
+        }
+        check_version(&trip, expected_version)?;
+        let event = Event::get_event(event_entry.event_id.to_hex(), db).await?;
+        let tz = trip.tz();
+        let duration = if event_entry.duration > 0 { event_entry.duration } else { event.duration };
+        let warnings = match event_entry.start_datetime(tz) {
+            Some(start) => {
+                // Stored as the exact start, the hour as local time
+                event_entry.start_date = localize(start, tz);
+                event_entry.start_hour = start.format("%H:%M").to_string();
+                event.visit_warnings(start, duration)
+            },
+            None => Vec::new(),
         };
 
-        match trip_collection.update_one(doc! {"_id": event_entry._id.clone()},
-                                         update_query,
+        // Fail before adding an entry the budget could not be computed with
+        event_entry.budget.validate()?;
+        let converter = CurrencyConverter::load(db).await?;
+        converter.convert(&event_entry.budget, trip.budget.currency.as_str())?;
+
+        // The owner's view gives the durations of the entries already there
+        let owner_id = trip.user_id.to_hex();
+        let mut trips = vec![trip];
+        Trip::expand_events(&mut trips, Some(&owner_id), db).await?;
+        let stop = ItineraryStop::from_entry(&event_entry, tz).with_duration(duration);
+        let conflicts = entry_conflicts(&trips[0], &stop, &converter)?;
+        if conflicts.iter().any(|conflict| conflict.is_error()) {
+            return Ok(EntryResult {
+                message: "Event not added, the entry conflicts with the trip".to_string(),
+                added: false,
+                warnings,
+                conflicts,
+            });
+        }
+
+        // Left out of to_doc so entries can still be pulled without it
+        let mut entry_doc = event_entry.to_doc();
+        entry_doc.insert("fixed", event_entry.fixed);
+
+        let mut filter = doc! {"_id": event_entry._id.clone()};
+        if let Some(version) = expected_version {
+            filter.insert("version", version_filter(version));
+        }
+
+        match trip_collection.update_one(filter,
+                                         doc! {"$push": {"events": entry_doc}, "$inc": {"version": 1}},
                                          UpdateOptions::default()
         ).await {
-            Ok(_) => Ok("Event successfully added".to_string()),
+            Ok(result) if result.matched_count == 0 => match expected_version {
+                Some(_) => Err(VERSION_MISMATCH.to_string()),
+                None => Err("Trip not found".to_string()),
+            },
+            Ok(_) => {
+                Trip::recompute_budget(event_entry._id.clone(), db).await?;
+                hub.publish(&event_entry._id, user_id.as_str(), TripChange::EntryAdded { entry: event_entry.clone() });
+                Ok(EntryResult {
+                    message: "Event successfully added".to_string(),
+                    added: true,
+                    warnings,
+                    conflicts,
+                })
+            },
             Err(_) => Err("Event not found".to_string())
         }
     }
 
-    pub async fn pull_event_entry(event_entry: EventEntry, db: &MongoDb) -> Result<String, String> {
+    pub async fn pull_event_entry(event_entry: EventEntry, expected_version: Option<i64>, user_id: String, hub: &TripHub, db: &MongoDb) -> Result<String, String> {
         let trip_collection = db.collection("trips");
 
-        let update_query = doc! {
-            "$pull": {"events": event_entry.borrow().to_doc()},
-            "$inc": {"budget": -event_entry.budget}
-        };
+        let trip = Trip::get_trip(event_entry._id.to_hex(), db).await?;
+        if !trip.editable_by(user_id.as_str()) {
+            return Err("Only the owner and editors can change the trip's entries".to_string());
+        }
+        check_version(&trip, expected_version)?;
 
-        match trip_collection.update_one(doc! {"_id": event_entry._id.clone()},
-                                         update_query,
+        // Only entries that are there bump the version
+        let mut filter = doc! {"_id": event_entry._id.clone(), "events": {"$elemMatch": event_entry.to_doc()}};
+        if let Some(version) = expected_version {
+            filter.insert("version", version_filter(version));
+        }
+
+        match trip_collection.update_one(filter,
+                                         doc! {"$pull": {"events": event_entry.borrow().to_doc()}, "$inc": {"version": 1}},
                                          UpdateOptions::default()
         ).await {
-            Ok(_) => Ok("Event successfully removed".to_string()),
+            // Only touch the budget when an entry was actually removed
+            Ok(result) if result.modified_count > 0 => {
+                Trip::recompute_budget(event_entry._id.clone(), db).await?;
+                hub.publish(&event_entry._id, user_id.as_str(), TripChange::EntryRemoved { entry: event_entry.clone() });
+                Ok("Event successfully removed".to_string())
+            },
+            // Either the entry is not there or the trip changed in the meantime
+            Ok(_) => {
+                let trip = Trip::get_trip(event_entry._id.to_hex(), db).await?;
+                check_version(&trip, expected_version)?;
+                Err("Event entry not found".to_string())
+            },
             Err(_) => Err("Event not found".to_string())
         }
     }
 
-    pub async fn delete_trip(trip_id: String, db: &MongoDb) -> Result<i64, String> {
+    // Budget is the sum of the entries' budgets in the trip's currency
+    pub fn entries_budget(&self, converter: &CurrencyConverter) -> Result<Money, String> {
+        let budgets: Vec<Money> = self.events.iter()
+            .map(|event_entry| event_entry.budget.clone())
+            .collect();
+
+        converter.sum(&budgets, self.budget.currency.as_str())
+    }
+
+    pub async fn recompute_budget(trip_id: ObjectId, db: &MongoDb) -> Result<Money, String> {
         let trip_collection = db.collection("trips");
 
-        match ObjectId::with_string(trip_id.as_str().as_ref()) {
-            Ok(oi) => {
-                match trip_collection.delete_one(doc! {"_id": oi}, DeleteOptions::default()).await {
-                    Ok(result) => Ok(result.deleted_count),
-                    Err(_) => Err("Trip not found".to_string()),
-                }
-            },
-            Err(_) => Err("Cannot convert given string to ObjectId".to_string()),
+        let trip = Trip::get_trip(trip_id.to_hex(), db).await?;
+        let converter = CurrencyConverter::load(db).await?;
+        let budget = trip.entries_budget(&converter)?;
+        let budget_base = converter.to_base(&budget)?.amount;
+
+        match trip_collection.update_one(doc! {"_id": trip_id},
+                                         doc! {"$set": {"budget": budget.to_bson(), "budget_base": budget_base}},
+                                         UpdateOptions::default()
+        ).await {
+            Ok(_) => Ok(budget),
+            Err(_) => Err("Error updating trip budget".to_string()),
         }
     }
 
-    pub async fn fork(trip_fork: TripFork, user_id: String, db: &MongoDb) -> Result<ObjectId, String> {
+    // Recalculate every stored budget and its base amount, used to fix drift left by older versions
+    pub async fn repair_budgets(db: &MongoDb) -> Result<String, String> {
         let trip_collection = db.collection("trips");
+        let converter = CurrencyConverter::load(db).await?;
+        let mut checked = 0;
+        let mut repaired = 0;
 
-        match trip_collection.find_one(
-            doc! {"_id": trip_fork.to_fork_trip_id},
-            FindOneOptions::default()
-        ).await.expect("Error finding trip") {
-            Some(trip_found) => {
-                match bson::from_bson::<Trip>(bson::Bson::Document(trip_found)) {
-                    Ok(trip) => {
-                        println!("{}", user_id);
-                        let new_user_id = ObjectId::with_string(user_id.as_str().as_ref())
-                            .expect("Cannot convert given string to ObjectId");
-
-                        let original_start_date = DateTime::parse_from_rfc3339(trip.start_date.as_str())
-                            .unwrap();
-                        let original_end_date = DateTime::parse_from_rfc3339(trip.end_date.as_str())
-                            .unwrap();
-
-                        let new_start_date = DateTime::parse_from_rfc3339(trip_fork.start_date.as_str())
-                            .unwrap();
-
-                        let diff_seconds = new_start_date.timestamp() - original_start_date.timestamp() - (3600 * 6);
-
-                        let new_trip = TripCreate {
-                            name: trip_fork.name,
-                            start_date: trip_fork.start_date,
-                            end_date: NaiveDateTime::from_timestamp(original_end_date.timestamp() + diff_seconds, 0)
-                                .format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
-                            budget: trip.budget,
-                            destination: trip.destination,
-                            private: trip.private,
-                            user_id: new_user_id.clone(),
-                        };
-
-                        let new_trip_id = (*trip_collection.insert_one(
-                            new_trip.to_doc(),
-                            InsertOneOptions::default(),
-                        ).await
-                           .expect("Error inserting Trip")
-                           .inserted_id
-                           .as_object_id()
-                           .unwrap()
-                        ).clone();
-
-                        let new_events = trip.events.into_iter()
-                                                                     .map(|event_entry| event_entry
-                                                                         .to_doc_with_time_diff(diff_seconds)
-                                                                     )
-                                                                     .collect::<Vec<Document>>();
-                        match trip_collection.update_one(doc! {"_id": new_trip_id.clone()},
-                                         doc! {"$set": {"events": new_events}},
-                                         UpdateOptions::default()
-                        ).await {
-                            Ok(_) => Ok(new_trip_id),
-                            Err(_e) => Err("Error updating Trip".to_string()),
-                        }
+        let mut cursor = trip_collection.find(doc! {}, FindOptions::default())
+            .await.map_err(|_| "Error finding trips".to_string())?;
+        while let Some(result) = cursor.next().await {
+            let trip = match result.map(|document| bson::from_bson::<Trip>(Bson::Document(document))) {
+                Ok(Ok(trip)) => trip,
+                _ => continue,
+            };
+            checked += 1;
+
+            let budget = match trip.entries_budget(&converter)
+                .and_then(|budget| converter.to_base(&budget).map(|budget_base| (budget, budget_base.amount))) {
+                Ok(budget) => budget,
+                Err(e) => {
+                    println!("Skipping trip {}: {}", trip._id, e);
+                    continue;
+                },
+            };
+            if budget != (trip.budget.clone(), trip.budget_base) {
+                let (budget, budget_base) = budget;
+                trip_collection.update_one(doc! {"_id": trip._id.clone()},
+                                           doc! {"$set": {"budget": budget.to_bson(), "budget_base": budget_base}},
+                                           UpdateOptions::default()
+                ).await.map_err(|_| "Error updating trip budget".to_string())?;
+                repaired += 1;
+            }
+        }
+
+        Ok(format!("Checked {} trips, repaired {} budgets", checked, repaired))
+    }
+
+    // Older trips stored RFC 3339 strings without a timezone. Entries keep their local
+    // start, read the way it used to be, now anchored in the destination's timezone
+    pub async fn migrate_dates(db: &MongoDb) -> Result<String, String> {
+        let trip_collection = db.collection("trips");
+        let mut migrated = 0;
+
+        let mut cursor = trip_collection.find(
+            doc! {"$or": [{"start_date": {"$type": "string"}}, {"timezone": {"$exists": false}}]},
+            FindOptions::default()
+        ).await.map_err(|_| "Error finding trips".to_string())?;
+        while let Some(Ok(document)) = cursor.next().await {
+            let trip_id = match document.get_object_id("_id") {
+                Ok(trip_id) => trip_id.clone(),
+                Err(_) => continue,
+            };
+            let timezone = match document.get_str("timezone") {
+                Ok(timezone) => timezone.to_string(),
+                Err(_) => destination_timezone(document.get_str("destination").unwrap_or(""), db).await,
+            };
+            let tz = parse_timezone(timezone.as_str()).unwrap_or(Tz::UTC);
+
+            let mut update_doc = doc! {"timezone": timezone};
+            let dates: Result<Vec<(&str, DateTime<Utc>)>, String> = ["start_date", "end_date"].iter()
+                .filter_map(|field| document.get_str(field).ok()
+                    .map(|date| parse_rfc3339(date).map(|date| (*field, date))))
+                .collect();
+            match dates {
+                Ok(dates) => for (field, date) in dates {
+                    update_doc.insert(field, date);
+                },
+                Err(e) => {
+                    println!("Skipping trip {}: {}", trip_id, e);
+                    continue;
+                },
+            }
+
+            let mut events = Vec::new();
+            for entry in document.get_array("events").cloned().unwrap_or_default() {
+                let mut entry = match entry {
+                    Bson::Document(entry) => entry,
+                    other => {
+                        events.push(other);
+                        continue;
                     },
-                    Err(_e) => Err("Incorrect Struct".to_string()),
+                };
+                let legacy_start = entry.get_str("start_date").ok()
+                    .zip(entry.get_str("start_hour").ok())
+                    .and_then(|(start_date, start_hour)| legacy_entry_start(start_date, start_hour));
+                if let Some(start) = legacy_start {
+                    entry.insert("start_date", localize(start, tz));
+                    entry.insert("start_hour", start.format("%H:%M").to_string());
                 }
+                events.push(Bson::Document(entry));
+            }
+            update_doc.insert("events", events);
+
+            trip_collection.update_one(doc! {"_id": trip_id},
+                                       doc! {"$set": update_doc, "$inc": {"version": 1}},
+                                       UpdateOptions::default()
+            ).await.map_err(|_| "Error updating trip dates".to_string())?;
+            migrated += 1;
+        }
+
+        Ok(format!("Migrated dates of {} trips", migrated))
+    }
+
+    // Fill converted_budget for the requester's currency
+    pub async fn convert_budgets(trips: &mut [Trip], currency: String, db: &MongoDb) -> Result<(), String> {
+        let converter = CurrencyConverter::load(db).await?;
+        for trip in trips.iter_mut() {
+            trip.converted_budget = Some(converter.convert(&trip.budget, currency.as_str())?);
+        }
+
+        Ok(())
+    }
+
+    // Trips go to the owner's trash, the audit log keeps the trip as it was
+    pub async fn delete_trip(trip_id: String, user_id: String, ip: Option<String>, db: &MongoDb) -> Result<String, String> {
+        let user_oid = ObjectId::with_string(user_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        let trip = Trip::get_trip(trip_id, db).await?;
+        let owner_id = if trip.user_id == user_oid {
+            Some(user_oid.clone())
+        } else {
+            User::check_admin(user_id, db).await?;
+            None
+        };
+
+        let trip_deleted = soft_delete(ContentType::Trip, trip._id.clone(), owner_id, user_oid.clone(), db).await?;
+
+        AuditEntry::record(AuditRecord {
+            action: AuditAction::DeleteTrip,
+            actor_id: Some(user_oid),
+            target_type: "trip",
+            target_id: Some(trip._id),
+            before: Some(trip_deleted),
+            after: None,
+            ip,
+        }, db).await?;
+
+        Ok("Trip moved to trash".to_string())
+    }
+
+    // Flag entries whose event was deleted or purged
+    pub async fn mark_unavailable_entries(trips: &mut [Trip], db: &MongoDb) -> Result<(), String> {
+        let event_ids: Vec<ObjectId> = trips.iter()
+            .flat_map(|trip| trip.events.iter().map(|event_entry| event_entry.event_id.clone()))
+            .collect();
+        let available = live_event_ids(event_ids, db).await?;
+
+        for event_entry in trips.iter_mut().flat_map(|trip| trip.events.iter_mut()) {
+            if !available.contains(&event_entry.event_id) {
+                event_entry.unavailable = Some(true);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Embed the details of each entry's event with a single $lookup over the trips
+    pub async fn expand_events(trips: &mut [Trip], viewer_id: Option<&String>, db: &MongoDb) -> Result<(), String> {
+        let trip_collection = db.collection("trips");
+        let trip_ids: Vec<ObjectId> = trips.iter().map(|trip| trip._id.clone()).collect();
+
+        let pipeline = vec![
+            doc! {"$match": {"_id": {"$in": trip_ids}}},
+            doc! {"$lookup": {
+                "from": "events",
+                "let": {"event_ids": "$events.event_id"},
+                "pipeline": [
+                    {"$match": {"$expr": {"$in": ["$_id", "$$event_ids"]}}},
+                    {"$project": {
+                        "name": 1, "image": 1, "city": 1, "location": 1, "price": 1, "duration": 1,
+                        "private": 1, "moderation_status": 1, "deleted_at": 1, "user_id": 1,
+                    }},
+                ],
+                "as": "event_details",
+            }},
+            doc! {"$project": {"event_details": 1}},
+        ];
+
+        let mut cursor = trip_collection.aggregate(pipeline, AggregateOptions::default())
+            .await.map_err(|_| "Error expanding trip events".to_string())?;
+        let mut details: Vec<Document> = Vec::new();
+        while let Some(Ok(document)) = cursor.next().await {
+            if let Ok(event_details) = document.get_array("event_details") {
+                details.extend(event_details.iter().filter_map(|detail| detail.as_document().cloned()));
+            }
+        }
+
+        for event_entry in trips.iter_mut().flat_map(|trip| trip.events.iter_mut()) {
+            let detail = details.iter()
+                .find(|detail| detail.get_object_id("_id").ok() == Some(&event_entry.event_id))
+                .filter(|detail| document_visible_to(detail, viewer_id));
+
+            match detail.map(|detail| bson::from_bson::<EntryEvent>(Bson::Document(detail.clone()))) {
+                Some(Ok(event)) => event_entry.event = Some(event),
+                _ => event_entry.unavailable = Some(true),
+            }
+        }
+
+        Ok(())
+    }
+
+    // Returns the number of trips holding an entry for the event
+    pub async fn orphan_entries(event_id: ObjectId, snapshot: EventSnapshot, db: &MongoDb) -> Result<i64, String> {
+        let trip_collection = db.collection("trips");
+        let snapshot = bson::to_bson(&snapshot).map_err(|_| "Error saving event snapshot".to_string())?;
+
+        match trip_collection.update_many(
+            doc! {"events.event_id": event_id.clone()},
+            doc! {
+                "$set": {"events.$[entry].orphaned": true, "events.$[entry].snapshot": snapshot},
+                "$inc": {"version": 1},
             },
-            None => Err("Trip not found".to_string()),
+            entry_update_options(event_id)
+        ).await {
+            Ok(result) => Ok(result.modified_count),
+            Err(_) => Err("Error updating trip entries".to_string()),
         }
     }
 
-    pub async fn force_private(trip_id: String, admin_id: String, db: &MongoDb) -> Result<String, String> {
+    // Undo orphan_entries once the event is restored
+    pub async fn restore_entries(event_id: ObjectId, db: &MongoDb) -> Result<i64, String> {
+        let trip_collection = db.collection("trips");
+
+        match trip_collection.update_many(
+            doc! {"events": {"$elemMatch": {"event_id": event_id.clone(), "orphaned": true}}},
+            doc! {
+                "$set": {"events.$[entry].orphaned": false},
+                "$unset": {"events.$[entry].snapshot": ""},
+                "$inc": {"version": 1},
+            },
+            entry_update_options(event_id)
+        ).await {
+            Ok(result) => Ok(result.modified_count),
+            Err(_) => Err("Error updating trip entries".to_string()),
+        }
+    }
+
+    // Find entries pointing to events that no longer exist and were never flagged,
+    // left by events removed outside delete_event. With repair they get flagged
+    pub async fn check_entries(repair: bool, db: &MongoDb) -> Result<String, String> {
+        let trip_collection = db.collection("trips");
         let event_collection = db.collection("events");
-        let user_collection = db.collection("users");
-        let trip_oid = ObjectId::with_string(trip_id.as_str().as_ref())
-            .expect("Cannot convert given string to ObjectId");
-        let admin_oid = ObjectId::with_string(admin_id.as_str().as_ref())
-            .expect("Cannot convert given string to ObjectId");
-
-        match user_collection.find_one(
-            doc!{"_id": admin_oid},
-            FindOneOptions::default()
-        ).await.expect("Error finding user") {
-            Some(admin_found) => {
-                let admin_role = admin_found.get_str("role").expect("Error getting admin role");
-
-                match admin_role {
-                    "superadmin" | "admin" => {
-                        match event_collection.update_one(
-                            doc!{"_id": trip_oid},
-                            doc!{"$set": {"private": true}},
-                            UpdateOptions::default()
-                        ).await {
-                            Ok(_) => Ok("Successfully changed trip to private".to_string()),
-                            Err(_) => Err("Error changing trip to private".to_string())
-                        }
+        let mut checked = 0;
+        let mut dangling = 0;
+
+        let mut cursor = trip_collection.find(doc! {}, FindOptions::default())
+            .await.map_err(|_| "Error finding trips".to_string())?;
+        while let Some(result) = cursor.next().await {
+            let trip = match result.map(|document| bson::from_bson::<Trip>(Bson::Document(document))) {
+                Ok(Ok(trip)) => trip,
+                _ => continue,
+            };
+            checked += 1;
+
+            let event_ids: Vec<ObjectId> = trip.events.iter()
+                .filter(|event_entry| !event_entry.orphaned)
+                .map(|event_entry| event_entry.event_id.clone())
+                .collect();
+            let available = live_event_ids(event_ids.clone(), db).await?;
+
+            for event_id in event_ids.into_iter().filter(|event_id| !available.contains(event_id)) {
+                println!("Trip {} has a dangling entry for event {}", trip._id, event_id);
+                dangling += 1;
+                if !repair {
+                    continue;
+                }
+
+                // Soft deleted events can still give a snapshot, purged ones cannot
+                let snapshot = match event_collection.find_one(doc! {"_id": event_id.clone()}, FindOneOptions::default())
+                    .await.expect("Error finding event") {
+                    Some(document) => match bson::from_bson::<Event>(Bson::Document(document.clone())) {
+                        Ok(event) => bson::to_bson(&EventSnapshot {
+                            name: event.name,
+                            price: event.price,
+                            deleted_at: document.get_str("deleted_at").unwrap_or("").to_string(),
+                        }).unwrap_or(Bson::Null),
+                        Err(_) => Bson::Null,
                     },
-                    _ => Err("Access Denied: user don't have sufficient privileges".to_string())
+                    None => Bson::Null,
+                };
+                let mut entry_update = doc! {"events.$[entry].orphaned": true};
+                if snapshot != Bson::Null {
+                    entry_update.insert("events.$[entry].snapshot", snapshot);
                 }
+
+                trip_collection.update_one(doc! {"_id": trip._id.clone()},
+                                           doc! {"$set": entry_update, "$inc": {"version": 1}},
+                                           entry_update_options(event_id)
+                ).await.map_err(|_| "Error updating trip entries".to_string())?;
+            }
+        }
+
+        match repair {
+            true => Ok(format!("Checked {} trips, flagged {} dangling entries as orphaned", checked, dangling)),
+            false => Ok(format!("Checked {} trips, found {} dangling entries", checked, dangling)),
+        }
+    }
+
+    // Private trips can only be forked by their owner. The fork points back to its source
+    // and to the trip the chain started from
+    pub async fn fork(trip_fork: TripFork, user_id: String, db: &MongoDb) -> Result<ObjectId, String> {
+        let trip_collection = db.collection("trips");
+
+        let trip = Trip::get_trip(trip_fork.to_fork_trip_id.to_hex(), db).await?.visible(Some(&user_id))?;
+        if trip.private && !trip.owned_by(user_id.as_str()) {
+            return Err("Private trips can only be forked by their owner".to_string());
+        }
+        let new_user_id = ObjectId::with_string(user_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        // Shifted by calendar days in the trip's timezone
+        let tz = trip.tz();
+        let days = (local_date(&trip_fork.start_date, tz) - trip.first_day()).num_days();
+        let shift = |date: &DateTime<Utc>| localize(local_datetime(date, tz) + Duration::days(days), tz);
+
+        let private = trip_fork.is_private();
+        let new_trip = TripCreate {
+            name: trip_fork.name,
+            start_date: shift(&trip.start_date),
+            end_date: shift(&trip.end_date),
+            timezone: Some(trip.timezone.clone()),
+            budget: trip.budget.clone(),
+            budget_limit: trip.budget_limit.clone(),
+            destination: trip.destination.clone(),
+            private,
+            user_id: new_user_id,
+        };
+        let mut trip_doc = new_trip.to_doc();
+        trip_doc.insert("budget_base", trip.budget_base);
+        trip_doc.insert("forked_from", trip._id.clone());
+        trip_doc.insert("fork_root", trip.fork_root.clone().unwrap_or_else(|| trip._id.clone()));
+
+        let new_trip_id = match trip_collection.insert_one(trip_doc, InsertOneOptions::default()).await {
+            Ok(result) => match result.inserted_id.as_object_id() {
+                Some(new_trip_id) => new_trip_id.clone(),
+                None => return Err("Error inserting Trip".to_string()),
+            },
+            Err(_) => return Err("Error inserting Trip".to_string()),
+        };
+
+        // Entries point to the trip they belong to
+        let new_events = trip.events.iter()
+            .map(|event_entry| event_entry.to_doc_shifted(new_trip_id.clone(), days, tz))
+            .collect::<Vec<Document>>();
+        trip_collection.update_one(doc! {"_id": new_trip_id.clone()},
+                                   doc! {"$set": {"events": new_events}},
+                                   UpdateOptions::default()
+        ).await.map_err(|_| "Error updating Trip".to_string())?;
+        trip_collection.update_one(doc! {"_id": trip._id.clone()},
+                                   doc! {"$inc": {"fork_count": 1}},
+                                   UpdateOptions::default()
+        ).await.map_err(|_| "Error updating Trip".to_string())?;
+
+        Ok(new_trip_id)
+    }
+
+    // Forks of a trip the requester can see, paginated like the trip listing
+    pub async fn get_forks(trip_id: String, mut trip_filter: TripFilter, db: &MongoDb) -> Result<Page<Trip>, String> {
+        let trip = Trip::get_trip(trip_id, db).await?.visible(trip_filter.requester_id.as_ref())?;
+
+        trip_filter.forked_from = Some(trip._id.to_hex());
+        Trip::get_filtered_trips(trip_filter, db).await
+    }
+
+    // Fill attribution with the source of forked trips. Sources the viewer cannot see only
+    // keep their id
+    pub async fn attribute_forks(trips: &mut [Trip], viewer_id: Option<&String>, db: &MongoDb) -> Result<(), String> {
+        let source_ids: Vec<ObjectId> = trips.iter().filter_map(|trip| trip.forked_from.clone()).collect();
+        if source_ids.is_empty() {
+            return Ok(());
+        }
+
+        let find_options = FindOptions::builder()
+            .projection(doc! {"name": 1, "user_id": 1, "private": 1, "moderation_status": 1, "deleted_at": 1})
+            .build();
+        let mut cursor = db.collection("trips").find(doc! {"_id": {"$in": source_ids}}, find_options)
+            .await.map_err(|_| "Error finding trips".to_string())?;
+        let mut sources: HashMap<ObjectId, Document> = HashMap::new();
+        while let Some(Ok(document)) = cursor.next().await {
+            if let Ok(source_id) = document.get_object_id("_id") {
+                sources.insert(source_id.clone(), document);
+            }
+        }
+
+        let owner_ids: Vec<ObjectId> = sources.values()
+            .filter_map(|source| source.get_object_id("user_id").ok().cloned())
+            .collect();
+        let find_options = FindOptions::builder()
+            .projection(doc! {"username": 1})
+            .build();
+        let mut cursor = db.collection("users").find(doc! {"_id": {"$in": owner_ids}}, find_options)
+            .await.map_err(|_| "Error finding users".to_string())?;
+        let mut usernames: HashMap<ObjectId, String> = HashMap::new();
+        while let Some(Ok(document)) = cursor.next().await {
+            if let (Ok(owner_id), Ok(username)) = (document.get_object_id("_id"), document.get_str("username")) {
+                usernames.insert(owner_id.clone(), username.to_string());
+            }
+        }
+
+        for trip in trips.iter_mut() {
+            let source_id = match trip.forked_from.clone() {
+                Some(source_id) => source_id,
+                None => continue,
+            };
+            let source = sources.get(&source_id).filter(|source| document_visible_to(source, viewer_id));
+            let owner_id = source.and_then(|source| source.get_object_id("user_id").ok().cloned());
+            trip.attribution = Some(ForkAttribution {
+                trip_id: source_id,
+                name: source.and_then(|source| source.get_str("name").ok().map(|name| name.to_string())),
+                username: owner_id.as_ref().and_then(|owner_id| usernames.get(owner_id).cloned()),
+                user_id: owner_id,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub async fn force_private(trip_id: String, reason: Option<String>, admin_id: String, ip: Option<String>, db: &MongoDb) -> Result<String, String> {
+        match set_moderation_status(ContentType::Trip, trip_id, ModerationStatus::Hidden, reason, admin_id, ip, db).await {
+            Ok(_) => Ok("Successfully hid trip".to_string()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn unhide(trip_id: String, admin_id: String, ip: Option<String>, db: &MongoDb) -> Result<String, String> {
+        match set_moderation_status(ContentType::Trip, trip_id, ModerationStatus::Visible, None, admin_id, ip, db).await {
+            Ok(_) => Ok("Successfully restored trip".to_string()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Trips saved without a valid timezone use UTC
+    pub fn tz(&self) -> Tz {
+        parse_timezone(self.timezone.as_str()).unwrap_or(Tz::UTC)
+    }
+
+    pub fn first_day(&self) -> NaiveDate {
+        local_date(&self.start_date, self.tz())
+    }
+
+    pub fn last_day(&self) -> NaiveDate {
+        local_date(&self.end_date, self.tz())
+    }
+
+    pub fn owned_by(&self, user_id: &str) -> bool {
+        self.user_id.to_hex() == user_id
+    }
+
+    pub fn shared_view(self) -> SharedTrip {
+        SharedTrip {
+            name: self.name,
+            start_date: self.start_date,
+            end_date: self.end_date,
+            timezone: self.timezone,
+            budget: self.budget,
+            destination: self.destination,
+            events: self.events.into_iter().map(EventEntry::shared_view).collect(),
+        }
+    }
+
+    // Role of a collaborator who accepted the invitation
+    pub fn role_of(&self, user_id: &str) -> Option<CollaboratorRole> {
+        self.collaborators.iter()
+            .find(|collaborator| collaborator.user_id.to_hex() == user_id && collaborator.status == InvitationStatus::Accepted)
+            .map(|collaborator| collaborator.role)
+    }
+
+    pub fn editable_by(&self, user_id: &str) -> bool {
+        self.owned_by(user_id) || self.role_of(user_id) == Some(CollaboratorRole::Editor)
+    }
+
+    pub fn hidden(&self) -> bool {
+        self.moderation_status == ModerationStatus::Hidden
+    }
+
+    fn member(&self, user_id: Option<&String>) -> bool {
+        user_id.is_some_and(|id| self.owned_by(id) || self.role_of(id).is_some())
+    }
+
+    // Private and hidden trips are only shown to the owner and accepted members
+    pub fn visible_to(&self, user_id: Option<&String>) -> bool {
+        (!self.private && !self.hidden()) || self.member(user_id)
+    }
+
+    // Trips the user cannot see give the same error as missing ones
+    pub fn visible(mut self, user_id: Option<&String>) -> Result<Trip, String> {
+        match self.visible_to(user_id) {
+            true => {
+                self.hide_collaborators_from(user_id);
+                Ok(self)
             },
-            None => Err("User not found".to_string())
+            false => Err("Trip not found".to_string()),
+        }
+    }
+
+    // Who was invited is only shown to the owner and accepted members
+    pub fn hide_collaborators_from(&mut self, user_id: Option<&String>) {
+        if !self.member(user_id) {
+            self.collaborators.clear();
+        }
+    }
+}
+
+impl ExpandQuery {
+    pub fn events(&self) -> Result<bool, String> {
+        let mut events = false;
+        for value in self.expand.iter().flat_map(|expand| expand.split(',')) {
+            match value.trim() {
+                "events" => events = true,
+                "" => (),
+                other => return Err(format!("Unknown expand value: {}", other)),
+            }
         }
+
+        Ok(events)
     }
 }
 
 impl EventEntry {
+    // Start in the trip's timezone, start_hour may be a full timestamp or "HH:MM"
+    pub fn start_datetime(&self, tz: Tz) -> Option<NaiveDateTime> {
+        let date = local_date(&self.start_date, tz);
+        match parse_rfc3339(self.start_hour.as_str()) {
+            Ok(hour) => Some(date.and_time(local_datetime(&hour, tz).time())),
+            Err(_) => parse_time(self.start_hour.as_str()).ok()
+                .map(|time| date.and_time(time)),
+        }
+    }
+
+    pub fn shared_view(self) -> SharedEntry {
+        SharedEntry {
+            event_id: self.event_id,
+            start_date: self.start_date,
+            start_hour: self.start_hour,
+            budget: self.budget,
+            duration: self.duration,
+            orphaned: self.orphaned,
+            snapshot: self.snapshot,
+            unavailable: self.unavailable,
+            event: self.event,
+        }
+    }
+
     pub fn to_doc(&self) -> Document {
         doc! {
             "_id": self._id.clone(),
             "event_id": self.event_id.clone(),
-            "start_date": self.start_date.clone(),
+            "start_date": self.start_date,
             "start_hour": self.start_hour.clone(),
-            "budget": self.budget.clone(),
+            "budget": self.budget.to_bson(),
             "duration": self.duration.clone(),
         }
     }
@@ -350,25 +1093,25 @@ impl EventEntry {
         doc! {
             "_id": id.clone(),
             "event_id": self.event_id.clone(),
-            "start_date": self.start_date.clone(),
+            "start_date": self.start_date,
             "start_hour": self.start_hour.clone(),
-            "budget": self.budget.clone(),
+            "budget": self.budget.to_bson(),
             "duration": self.duration.clone(),
         }
     }
 
-    pub fn to_doc_with_time_diff(&self, time_offset: i64) -> Document {
-        let original_start_date = DateTime::parse_from_rfc3339(self.start_date.as_str())
-            .unwrap().timestamp();
-        let new_timestamp = NaiveDateTime::from_timestamp(original_start_date + time_offset, 0)
-            .format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+    // Moved by whole days keeping the local hour, so DST changes do not shift it
+    pub fn to_doc_shifted(&self, id: ObjectId, days: i64, tz: Tz) -> Document {
+        let start = self.start_datetime(tz)
+            .unwrap_or_else(|| local_datetime(&self.start_date, tz)) + Duration::days(days);
         doc! {
-            "_id": self._id.clone(),
+            "_id": id,
             "event_id": self.event_id.clone(),
-            "start_date": new_timestamp.clone(),
-            "start_hour": new_timestamp,
-            "budget": self.budget.clone(),
+            "start_date": localize(start, tz),
+            "start_hour": start.format("%H:%M").to_string(),
+            "budget": self.budget.to_bson(),
             "duration": self.duration.clone(),
+            "fixed": self.fixed,
         }
     }
 }
@@ -376,9 +1119,16 @@ impl EventEntry {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TripCreate {
     pub name: String,
-    pub start_date: String,
-    pub end_date: String,
-    pub budget: f32,
+    #[serde(deserialize_with = "datetime_or_rfc3339")]
+    pub start_date: DateTime<Utc>,
+    #[serde(deserialize_with = "datetime_or_rfc3339")]
+    pub end_date: DateTime<Utc>,
+    // Taken from the destination when missing
+    #[serde(default)]
+    pub timezone: Option<String>,
+    pub budget: Money,
+    #[serde(default)]
+    pub budget_limit: Option<Money>,
     pub destination: String,
     pub private: bool,
     #[serde(deserialize_with = "string_to_objectid")]
@@ -389,12 +1139,17 @@ impl TripCreate {
     pub fn to_doc(&self) -> Document {
         doc! {
             "name": self.name.clone(),
-            "start_date": self.start_date.clone(),
-            "end_date": self.end_date.clone(),
-            "budget": self.budget,
+            "start_date": self.start_date,
+            "end_date": self.end_date,
+            "timezone": self.timezone.clone().unwrap_or_else(default_timezone),
+            "budget": self.budget.to_bson(),
+            "budget_limit": self.budget_limit.as_ref().map(|budget_limit| budget_limit.to_bson()).unwrap_or(Bson::Null),
             "events": [],
             "destination": self.destination.clone(),
             "private": self.private.clone(),
+            "moderation_status": "visible",
+            "collaborators": [],
+            "version": 1,
             "user_id": self.user_id.clone(),
         }
     }
@@ -405,9 +1160,13 @@ pub struct TripEdit {
     #[serde(deserialize_with = "string_to_objectid")]
     _id: ObjectId,
     name: Option<String>,
-    start_date: Option<String>,
-    end_date: Option<String>,
-    budget: Option<f32>,
+    #[serde(default, deserialize_with = "optional_datetime_or_rfc3339")]
+    start_date: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "optional_datetime_or_rfc3339")]
+    end_date: Option<DateTime<Utc>>,
+    timezone: Option<String>,
+    budget: Option<Money>,
+    budget_limit: Option<Money>,
     private: Option<bool>,
     destination: Option<String>,
 }
@@ -415,34 +1174,121 @@ pub struct TripEdit {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TripFork {
     pub name: String,
-    pub start_date: String,
+    #[serde(deserialize_with = "datetime_or_rfc3339")]
+    pub start_date: DateTime<Utc>,
+    #[serde(default)]
+    pub private: Option<bool>,
     #[serde(deserialize_with = "string_to_objectid")]
     pub to_fork_trip_id: ObjectId,
 }
 
-fn get_find_filter(trip_filter: TripFilter) -> Document {
+impl TripFork {
+    // The source's flag is never copied, forks are created like any new trip
+    pub fn is_private(&self) -> bool {
+        self.private.unwrap_or_default()
+    }
+}
+
+pub(crate) fn get_find_filter(trip_filter: TripFilter) -> Document {
     let mut filter = doc! {};
+    let own_trips = trip_filter.requester_id.is_some() && trip_filter.requester_id == trip_filter.user_id;
+    filter.insert("deleted_at", Bson::Null);
     match trip_filter.user_id {
         Some(s) => {
             match ObjectId::with_string(s.as_str().as_ref()) {
+                Ok(oi) if own_trips => filter.insert("$or", vec![
+                    doc! {"user_id": oi.clone()},
+                    doc! {"collaborators": {"$elemMatch": {"user_id": oi, "status": "accepted"}}},
+                ]),
                 Ok(oi) => filter.insert("user_id", oi),
                 Err(_) => filter.insert("private", false),
             }
         },
         None => filter.insert("private", false),
     };
+    if let Some(source_id) = trip_filter.forked_from.as_ref() {
+        // An unknown source has no forks
+        let source_oid = ObjectId::with_string(source_id.as_str()).unwrap_or_default();
+        filter.insert("forked_from", source_oid);
+    }
+    let mut budget_range = doc! {};
     match trip_filter.budget_gt {
-        Some(f) => filter.insert("budget", doc! {"$gte": f}),
+        Some(i) => budget_range.insert("$gte", i),
         None => Some(Bson::default()),
     };
     match trip_filter.budget_lt {
-        Some(f) => filter.insert("budget", doc! {"$lte": f}),
+        Some(i) => budget_range.insert("$lte", i),
         None => Some(Bson::default()),
     };
+    if !budget_range.is_empty() {
+        filter.insert("budget_base", budget_range);
+    }
+    // Hidden trips only show up when owners list their own trips
+    if !own_trips {
+        filter.insert("moderation_status", doc! {"$ne": "hidden"});
+    }
 
     filter
 }
 
+// Budget bounds are given in the requester's currency but stored budgets are compared in the base one
+async fn budget_range_to_base(trip_filter: &mut TripFilter, db: &MongoDb) -> Result<(), String> {
+    let currency = match trip_filter.currency.clone() {
+        Some(c) if c != base_currency() => c,
+        _ => return Ok(()),
+    };
+    if trip_filter.budget_gt.is_none() && trip_filter.budget_lt.is_none() {
+        return Ok(());
+    }
+
+    let converter = CurrencyConverter::load(db).await?;
+    if let Some(gt) = trip_filter.budget_gt {
+        trip_filter.budget_gt = Some(converter.to_base(&Money::new(gt, currency.as_str()))?.amount);
+    }
+    if let Some(lt) = trip_filter.budget_lt {
+        trip_filter.budget_lt = Some(converter.to_base(&Money::new(lt, currency.as_str()))?.amount);
+    }
+
+    Ok(())
+}
+
+// Same rules as the event listing, used for expanded events and fork sources: deleted
+// documents are gone, private and hidden ones are only shown to their owner
+pub(crate) fn document_visible_to(document: &Document, viewer_id: Option<&String>) -> bool {
+    let owner = viewer_id.is_some_and(|id| document.get_object_id("user_id").is_ok_and(|user_id| *id == user_id.to_hex()));
+    let deleted = !matches!(document.get("deleted_at"), None | Some(Bson::Null));
+    let private = document.get_bool("private").unwrap_or(false);
+    let hidden = document.get_str("moderation_status") == Ok("hidden");
+
+    !deleted && (owner || (!private && !hidden))
+}
+
+// Ids among the given ones of events that exist and are not deleted
+async fn live_event_ids(event_ids: Vec<ObjectId>, db: &MongoDb) -> Result<Vec<ObjectId>, String> {
+    let event_collection = db.collection("events");
+
+    let find_options = FindOptions::builder()
+        .projection(doc! {"_id": 1})
+        .build();
+    let mut cursor = event_collection.find(doc! {"_id": {"$in": event_ids}, "deleted_at": Bson::Null}, find_options)
+        .await.map_err(|_| "Error finding trip events".to_string())?;
+    let mut live_ids = Vec::new();
+    while let Some(Ok(document)) = cursor.next().await {
+        if let Ok(event_id) = document.get_object_id("_id") {
+            live_ids.push(event_id.clone());
+        }
+    }
+
+    Ok(live_ids)
+}
+
+// Targets every entry of the given event inside a trip
+fn entry_update_options(event_id: ObjectId) -> UpdateOptions {
+    UpdateOptions::builder()
+        .array_filters(vec![doc! {"entry.event_id": event_id}])
+        .build()
+}
+
 // Deserialize the String and convert it to ObjectId
 fn string_to_objectid<'de, D>(deserializer: D) -> Result<ObjectId, D::Error>
     where
@@ -450,4 +1296,66 @@ fn string_to_objectid<'de, D>(deserializer: D) -> Result<ObjectId, D::Error>
 {
     // Deserialize using a custom visitor
     deserializer.deserialize_any(ObjectIdVisitor)
-}
\ No newline at end of file
+}
+
+// Start of an entry as older versions read it, both strings in their own offset
+fn legacy_entry_start(start_date: &str, start_hour: &str) -> Option<NaiveDateTime> {
+    let date = DateTime::parse_from_rfc3339(start_date).ok()?.naive_local().date();
+    match DateTime::parse_from_rfc3339(start_hour) {
+        Ok(hour) => Some(date.and_time(hour.naive_local().time())),
+        Err(_) => parse_time(start_hour).ok().map(|time| date.and_time(time)),
+    }
+}
+
+fn check_version(trip: &Trip, expected_version: Option<i64>) -> Result<(), String> {
+    match expected_version {
+        Some(version) if version != trip.version => Err(VERSION_MISMATCH.to_string()),
+        _ => Ok(()),
+    }
+}
+
+pub fn validate_dates(start_date: &DateTime<Utc>, end_date: &DateTime<Utc>) -> Result<(), String> {
+    if start_date > end_date {
+        return Err("start_date must not be after end_date".to_string());
+    }
+
+    Ok(())
+}
+
+fn default_timezone() -> String {
+    DEFAULT_TIMEZONE.to_string()
+}
+
+// Dates are BSON datetimes, clients and older documents use RFC 3339 strings
+pub(crate) fn datetime_or_rfc3339<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: de::Deserializer<'de>,
+{
+    bson_to_datetime(Bson::deserialize(deserializer)?).map_err(de::Error::custom)
+}
+
+// Missing and null dates are both left unset
+pub(crate) fn optional_datetime_or_rfc3339<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: de::Deserializer<'de>,
+{
+    match Option::<Bson>::deserialize(deserializer)? {
+        None | Some(Bson::Null) => Ok(None),
+        Some(date) => bson_to_datetime(date).map(Some).map_err(de::Error::custom),
+    }
+}
+
+fn bson_to_datetime(date: Bson) -> Result<DateTime<Utc>, String> {
+    match date {
+        Bson::DateTime(date) => Ok(date),
+        Bson::String(date) => parse_rfc3339(date.as_str()),
+        _ => Err("Expecting a date".to_string()),
+    }
+}
+
+pub(crate) fn datetime_to_rfc3339<S>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+{
+    serializer.serialize_str(date.to_rfc3339().as_str())
+}
@@ -0,0 +1,224 @@
+use crate::models::trip::{Trip, EventEntry};
+
+use serde::{Deserialize, Serialize};
+use bson::oid::ObjectId;
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use chrono::Utc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Changes kept per trip for clients catching up after a reconnect
+pub const HISTORY_SIZE: usize = 200;
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TripChange {
+    EntryAdded { entry: EventEntry },
+    EntryRemoved { entry: EventEntry },
+    TripUpdated { trip: Trip },
+}
+
+// A change as sent to the members, seq only grows and is shared by every trip so a client
+// can tell whether it missed something
+#[derive(Serialize, Clone, Debug)]
+pub struct TripMessage {
+    pub seq: u64,
+    pub trip_id: ObjectId,
+    pub user_id: String,
+    pub at: String,
+    #[serde(flatten)]
+    pub change: TripChange,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notice {
+    // Members connected to the trip, sent whenever someone joins or leaves
+    Presence { members: Vec<String>, seq: u64 },
+    // Changes after the given seq are gone, the client has to fetch the trip again
+    Resync { seq: u64 },
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct LiveQuery {
+    // Last seq the client saw, missed changes are sent first
+    pub since: Option<u64>,
+}
+
+enum CatchUp {
+    Messages(Vec<String>),
+    Resync,
+}
+
+pub struct Subscription {
+    pub connection_id: u64,
+    pub receiver: UnboundedReceiver<String>,
+}
+
+struct Connection {
+    user_id: String,
+    sender: UnboundedSender<String>,
+}
+
+struct TripChannel {
+    // Every change after this seq is in history
+    complete_after: u64,
+    history: VecDeque<TripMessage>,
+    connections: HashMap<u64, Connection>,
+}
+
+// Fans trip changes out to the members connected to each trip. Kept in memory, a channel
+// goes away with its last connection
+pub struct TripHub {
+    seq: AtomicU64,
+    next_connection: AtomicU64,
+    channels: Mutex<HashMap<ObjectId, TripChannel>>,
+}
+
+impl Default for TripHub {
+    fn default() -> TripHub {
+        // Starting from the clock keeps seqs growing across restarts
+        TripHub::starting_at(Utc::now().timestamp_millis() as u64)
+    }
+}
+
+impl TripHub {
+    pub fn starting_at(seq: u64) -> TripHub {
+        TripHub {
+            seq: AtomicU64::new(seq),
+            next_connection: AtomicU64::new(0),
+            channels: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn seq(&self) -> u64 {
+        self.seq.load(Ordering::SeqCst)
+    }
+
+    // The seq moves even when no member is connected, so clients coming back later know
+    // they missed the change
+    pub fn publish(&self, trip_id: &ObjectId, user_id: &str, change: TripChange) -> u64 {
+        let mut channels = self.channels.lock().expect("Trip hub lock poisoned");
+        let seq = self.seq.fetch_add(1, Ordering::SeqCst) + 1;
+        let channel = match channels.get_mut(trip_id) {
+            Some(channel) => channel,
+            None => return seq,
+        };
+
+        let message = TripMessage {
+            seq,
+            trip_id: trip_id.clone(),
+            user_id: user_id.to_string(),
+            at: Utc::now().to_rfc3339(),
+            change,
+        };
+        if let Ok(text) = serde_json::to_string(&message) {
+            channel.send(text);
+        }
+        channel.history.push_back(message);
+        if channel.history.len() > HISTORY_SIZE {
+            if let Some(dropped) = channel.history.pop_front() {
+                channel.complete_after = dropped.seq;
+            }
+        }
+
+        seq
+    }
+
+    // Queues the changes after since, or a resync notice when some are gone, then tells
+    // everyone who is connected
+    pub fn subscribe(&self, trip_id: &ObjectId, user_id: &str, since: Option<u64>) -> Subscription {
+        let connection_id = self.next_connection.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = mpsc::unbounded();
+
+        let mut channels = self.channels.lock().expect("Trip hub lock poisoned");
+        let seq = self.seq();
+        let channel = channels.entry(trip_id.clone()).or_insert_with(|| TripChannel {
+            complete_after: seq,
+            history: VecDeque::new(),
+            connections: HashMap::new(),
+        });
+
+        if let Some(since) = since {
+            let missed = match channel.catch_up(since) {
+                CatchUp::Messages(missed) => missed,
+                CatchUp::Resync => serde_json::to_string(&Notice::Resync { seq }).ok().into_iter().collect(),
+            };
+            for text in missed {
+                let _ = sender.unbounded_send(text);
+            }
+        }
+
+        channel.connections.insert(connection_id, Connection { user_id: user_id.to_string(), sender });
+        channel.send_presence(seq);
+
+        Subscription { connection_id, receiver }
+    }
+
+    pub fn unsubscribe(&self, trip_id: &ObjectId, connection_id: u64) {
+        let mut channels = self.channels.lock().expect("Trip hub lock poisoned");
+        let channel = match channels.get_mut(trip_id) {
+            Some(channel) => channel,
+            None => return,
+        };
+
+        channel.connections.remove(&connection_id);
+        if channel.connections.is_empty() {
+            channels.remove(trip_id);
+        } else {
+            channel.send_presence(self.seq());
+        }
+    }
+
+    // Closes the connections of a member who no longer has access to the trip, their
+    // sockets close once the queued messages are sent
+    pub fn disconnect(&self, trip_id: &ObjectId, user_id: &str) {
+        let mut channels = self.channels.lock().expect("Trip hub lock poisoned");
+        let channel = match channels.get_mut(trip_id) {
+            Some(channel) => channel,
+            None => return,
+        };
+
+        channel.connections.retain(|_, connection| connection.user_id != user_id);
+        if channel.connections.is_empty() {
+            channels.remove(trip_id);
+        } else {
+            channel.send_presence(self.seq());
+        }
+    }
+}
+
+impl TripChannel {
+    fn catch_up(&self, since: u64) -> CatchUp {
+        if since < self.complete_after {
+            return CatchUp::Resync;
+        }
+
+        CatchUp::Messages(self.history.iter()
+            .filter(|message| message.seq > since)
+            .filter_map(|message| serde_json::to_string(message).ok())
+            .collect())
+    }
+
+    fn members(&self) -> Vec<String> {
+        let mut members: Vec<String> = self.connections.values()
+            .map(|connection| connection.user_id.clone())
+            .collect();
+        members.sort();
+        members.dedup();
+
+        members
+    }
+
+    fn send_presence(&mut self, seq: u64) {
+        if let Ok(text) = serde_json::to_string(&Notice::Presence { members: self.members(), seq }) {
+            self.send(text);
+        }
+    }
+
+    // Connections whose socket is gone are dropped
+    fn send(&mut self, text: String) {
+        self.connections.retain(|_, connection| connection.sender.unbounded_send(text.clone()).is_ok());
+    }
+}
@@ -1,9 +1,11 @@
 use crate::{MongoClient, MongoDb};
+use crate::models::currency::validate_currency;
+use crate::models::audit::{AuditAction, AuditEntry, AuditRecord};
 
 use bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 use regex::Regex;
-use mongodb::bson::doc;
+use mongodb::bson::{doc, Bson};
 use mongodb::options::{FindOneOptions, InsertOneOptions, UpdateOptions, FindOptions};
 use mongodb::bson::Document;
 use argon2::{self};
@@ -18,6 +20,8 @@ pub struct User {
     pub password: String,
     pub role: Option<String>,
     pub email: String,
+    #[serde(default)]
+    pub preferred_currency: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -63,6 +67,9 @@ impl User {
         if !re.is_match(mail.as_str()) {
             return Err("Invalid email".to_string())
         }
+        if let Some(currency) = user_to_valdiate.preferred_currency.as_ref() {
+            validate_currency(currency.as_str())?;
+        }
 
         let user_filter = doc!{"$or": [ {"email": mail}, {"username": username}]};
         match user_collection.find_one(user_filter, FindOneOptions::default()).await.expect("Error in find user") {
@@ -86,29 +93,82 @@ impl User {
             .clone()
     }
 
-    pub async fn find_user(user_to_find: UserLogin, db: &MongoDb) -> Result<User, String> {
+    pub async fn find_user(user_to_find: UserLogin, ip: Option<String>, db: &MongoDb) -> Result<User, String> {
         let user_collection = db.collection("users");
         let email = user_to_find.email.clone();
         let password = user_to_find.password.clone();
 
-        let user_filter = doc!{"email": email, "provider": {"$ne": "google"}};
-        match user_collection.find_one(user_filter, FindOneOptions::default()).await.expect("Error in find user") {
+        let user_filter = doc!{"email": email.clone(), "provider": {"$ne": "google"}};
+        let result = match user_collection.find_one(user_filter, FindOneOptions::default()).await.expect("Error in find user") {
             Some(user_found) => {
                 match bson::from_bson::<User>(bson::Bson::Document(user_found)) {
                     Ok(user) => {
                         match argon2::verify_encoded(&user.password, password.as_bytes()).unwrap() {
                             true => Ok(user),
-                            false => Err("Email or password mismatch".to_string())
+                            false => {
+                                record_login(user._id.clone(), email, "password", false, ip, db).await?;
+                                return Err("Email or password mismatch".to_string())
+                            }
                         }
                     },
                     Err(_e) => Err("Incorrect Struct".to_string()),
                 }
             },
             None => Err("User not found".to_string())
+        };
+
+        match result {
+            Ok(user) => {
+                record_login(user._id.clone(), email, "password", true, ip, db).await?;
+                Ok(user)
+            },
+            Err(e) => {
+                record_login(None, email, "password", false, ip, db).await?;
+                Err(e)
+            }
         }
     }
 
-    pub async fn promote_user(user_id: String, admin_id: String, db: &MongoDb) -> Result<String, String> {
+    // Ok when the user holds an admin or superadmin role
+    pub async fn check_admin(admin_id: String, db: &MongoDb) -> Result<(), String> {
+        let user_collection = db.collection("users");
+        let admin_oid = ObjectId::with_string(admin_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        match user_collection.find_one(
+            doc!{"_id": admin_oid},
+            FindOneOptions::default()
+        ).await.expect("Error finding user") {
+            Some(admin_found) => {
+                let admin_role = admin_found.get_str("role").expect("Error getting admin role");
+
+                match admin_role {
+                    "superadmin" | "admin" => Ok(()),
+                    _ => Err("Access Denied: user don't have sufficient privileges".to_string())
+                }
+            },
+            None => Err("User not found".to_string())
+        }
+    }
+
+    pub async fn check_superadmin(admin_id: String, db: &MongoDb) -> Result<(), String> {
+        let user_collection = db.collection("users");
+        let admin_oid = ObjectId::with_string(admin_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        match user_collection.find_one(
+            doc!{"_id": admin_oid},
+            FindOneOptions::default()
+        ).await.expect("Error finding user") {
+            Some(admin_found) => match admin_found.get_str("role") {
+                Ok("superadmin") => Ok(()),
+                _ => Err("Access Denied: user don't have sufficient privileges".to_string())
+            },
+            None => Err("User not found".to_string())
+        }
+    }
+
+    pub async fn promote_user(user_id: String, admin_id: String, ip: Option<String>, db: &MongoDb) -> Result<String, String> {
         let user_collection = db.collection("users");
         let user_oid = ObjectId::with_string(user_id.as_str().as_ref())
             .expect("Cannot convert given string to ObjectId");
@@ -116,7 +176,7 @@ impl User {
             .expect("Cannot convert given string to ObjectId");
 
         match user_collection.find_one(
-            doc!{"_id": admin_oid},
+            doc!{"_id": admin_oid.clone()},
             FindOneOptions::default()
         ).await.expect("Error finding user") {
             Some(admin_found) => {
@@ -125,11 +185,24 @@ impl User {
                 match admin_role {
                     "superadmin" => {
                         match user_collection.update_one(
-                            doc!{"_id": user_oid, "role": "user"},
+                            doc!{"_id": user_oid.clone(), "role": "user"},
                             doc!{"$set": {"role": "admin"}},
                             UpdateOptions::default()
                         ).await {
-                            Ok(_) => Ok("Successfully promoted user role".to_string()),
+                            Ok(result) => {
+                                if result.modified_count > 0 {
+                                    AuditEntry::record(AuditRecord {
+                                        action: AuditAction::PromoteUser,
+                                        actor_id: Some(admin_oid),
+                                        target_type: "user",
+                                        target_id: Some(user_oid),
+                                        before: Some(doc! {"role": "user"}),
+                                        after: Some(doc! {"role": "admin"}),
+                                        ip,
+                                    }, db).await?;
+                                }
+                                Ok("Successfully promoted user role".to_string())
+                            },
                             Err(_) => Err("Error promoting user role".to_string())
                         }
                     },
@@ -140,7 +213,7 @@ impl User {
         }
     }
 
-    pub async fn demote_user(user_id: String, admin_id: String, db: &MongoDb) -> Result<String, String> {
+    pub async fn demote_user(user_id: String, admin_id: String, ip: Option<String>, db: &MongoDb) -> Result<String, String> {
         let user_collection = db.collection("users");
         let user_oid = ObjectId::with_string(user_id.as_str().as_ref())
             .expect("Cannot convert given string to ObjectId");
@@ -148,7 +221,7 @@ impl User {
             .expect("Cannot convert given string to ObjectId");
 
         match user_collection.find_one(
-            doc!{"_id": admin_oid},
+            doc!{"_id": admin_oid.clone()},
             FindOneOptions::default()
         ).await.expect("Error finding user") {
             Some(admin_found) => {
@@ -157,11 +230,24 @@ impl User {
                 match admin_role {
                     "superadmin" => {
                         match user_collection.update_one(
-                            doc!{"_id": user_oid, "role": "admin"},
+                            doc!{"_id": user_oid.clone(), "role": "admin"},
                             doc!{"$set": {"role": "user"}},
                             UpdateOptions::default()
                         ).await {
-                            Ok(_) => Ok("Successfully demoted user role".to_string()),
+                            Ok(result) => {
+                                if result.modified_count > 0 {
+                                    AuditEntry::record(AuditRecord {
+                                        action: AuditAction::DemoteUser,
+                                        actor_id: Some(admin_oid),
+                                        target_type: "user",
+                                        target_id: Some(user_oid),
+                                        before: Some(doc! {"role": "admin"}),
+                                        after: Some(doc! {"role": "user"}),
+                                        ip,
+                                    }, db).await?;
+                                }
+                                Ok("Successfully demoted user role".to_string())
+                            },
                             Err(_) => Err("Error demoting user role".to_string())
                         }
                     },
@@ -215,6 +301,24 @@ impl User {
         }
     }
 
+    pub async fn set_preferred_currency(user_id: String, currency: String, db: &MongoDb) -> Result<String, String> {
+        let user_collection = db.collection("users");
+        let user_oid = ObjectId::with_string(user_id.as_str())
+            .map_err(|_| "Cannot convert given string to ObjectId".to_string())?;
+
+        validate_currency(currency.as_str())?;
+
+        match user_collection.update_one(
+            doc!{"_id": user_oid},
+            doc!{"$set": {"preferred_currency": currency}},
+            UpdateOptions::default()
+        ).await {
+            Ok(result) if result.matched_count > 0 => Ok("Successfully updated preferred currency".to_string()),
+            Ok(_) => Err("User not found".to_string()),
+            Err(_) => Err("Error updating preferred currency".to_string())
+        }
+    }
+
     pub async fn validate_google(user_to_valdiate: ProvidedGoogleUser, db: &MongoDb) -> Result<ProvidedGoogleUser, String> {
         let user_collection = db.collection("users");
         let mail = user_to_valdiate.email.clone();
@@ -241,12 +345,12 @@ impl User {
             .clone()
     }
 
-    pub async fn find_google_user(user_to_find: ProvidedGoogleUser, db: &MongoDb) -> Result<User, String> {
+    pub async fn find_google_user(user_to_find: ProvidedGoogleUser, ip: Option<String>, db: &MongoDb) -> Result<User, String> {
         let user_collection = db.collection("users");
         let email = user_to_find.email.clone();
 
-        let user_filter = doc!{"email": email, "provider": "google"};
-        match user_collection.find_one(user_filter, FindOneOptions::default()).await.expect("Error in find user") {
+        let user_filter = doc!{"email": email.clone(), "provider": "google"};
+        let result = match user_collection.find_one(user_filter, FindOneOptions::default()).await.expect("Error in find user") {
             Some(user_found) => {
                 match bson::from_bson::<User>(bson::Bson::Document(user_found)) {
                     Ok(user) => {
@@ -256,7 +360,11 @@ impl User {
                 }
             },
             None => Err("User not found".to_string())
-        }
+        };
+
+        let user_oid = result.as_ref().ok().and_then(|user| user._id.clone());
+        record_login(user_oid, email, "google", result.is_ok(), ip, db).await?;
+        result
     }
 
     pub async fn to_doc(&self) -> Document {
@@ -265,7 +373,11 @@ impl User {
             "username": self.username.clone(),
             "password": self.password.clone(),
             "role": "user",
-            "email": self.email.clone()
+            "email": self.email.clone(),
+            "preferred_currency": match self.preferred_currency.clone() {
+                Some(currency) => Bson::String(currency),
+                None => Bson::Null,
+            }
         }
     }
 }
@@ -283,3 +395,21 @@ impl ProvidedGoogleUser {
     }
 }
 
+// Both successful and failed attempts are kept, failed ones may not match any user
+async fn record_login(user_oid: Option<ObjectId>,
+                      email: String,
+                      provider: &str,
+                      success: bool,
+                      ip: Option<String>,
+                      db: &MongoDb
+) -> Result<(), String> {
+    AuditEntry::record(AuditRecord {
+        action: if success { AuditAction::Login } else { AuditAction::LoginFailed },
+        actor_id: if success { user_oid.clone() } else { None },
+        target_type: "user",
+        target_id: user_oid,
+        before: None,
+        after: Some(doc! {"email": email, "provider": provider}),
+        ip,
+    }, db).await
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod test {
+    use crate::MongoDb;
+    use crate::models::audit::{get_find_filter, AuditAction, AuditEntry, AuditFilter};
+
+    use mongodb::{Client, options::ClientOptions};
+    use mongodb::options::ResolverConfig;
+    use mongodb::bson::doc;
+    use bson::oid::ObjectId;
+
+    async fn get_mongo_db() -> MongoDb {
+        dotenv::dotenv().ok();
+
+        let mut mongo_options = ClientOptions::parse_with_resolver_config(
+            std::env::var("MONGO_URL").expect("Error in Mongo URL").as_str(),
+            ResolverConfig::cloudflare()
+        ).await.expect("Error found while creating client options");
+        mongo_options.app_name = Some("YeoHengServer".to_string());
+        let mongo_client = Client::with_options(mongo_options).expect("Error found while creating mongo client");
+        mongo_client.database(std::env::var("TEST_DATABASE_NAME")
+            .expect("Error retrieving database name")
+            .as_str())
+    }
+
+    #[test]
+    fn test_audit_filter() {
+        let actor_id = ObjectId::new();
+        let audit_filter = AuditFilter {
+            action: Some(AuditAction::DeleteTrip),
+            actor_id: Some(actor_id.to_hex()),
+            from: Some("2021-01-01T09:00:00+09:00".to_string()),
+            ..AuditFilter::default()
+        };
+
+        let filter = get_find_filter(audit_filter).expect("Error: test audit filter");
+
+        assert_eq!(doc! {
+            "action": "delete_trip",
+            "actor_id": actor_id,
+            "created_at": {"$gte": "2021-01-01T00:00:00+00:00"},
+        }, filter);
+    }
+
+    #[test]
+    fn test_audit_filter_invalid_date() {
+        let audit_filter = AuditFilter {
+            to: Some("yesterday".to_string()),
+            ..AuditFilter::default()
+        };
+
+        let response = get_find_filter(audit_filter).expect_err("Error: invalid date accepted");
+
+        assert_eq!("Invalid date, expecting RFC 3339: yesterday", response);
+    }
+
+    #[actix_rt::test]
+    async fn test_audit_requires_superadmin() {
+        let mongo_db = get_mongo_db().await;
+
+        let response = AuditEntry::get_entries(AuditFilter::default(), ObjectId::new().to_hex(), &mongo_db).await
+            .expect_err("Error: audit log listed for unknown user");
+
+        assert_eq!("User not found", response);
+    }
+}
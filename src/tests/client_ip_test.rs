@@ -0,0 +1,27 @@
+#[cfg(test)]
+mod test {
+    use crate::utils::client_ip::resolve_client_ip;
+
+    use std::net::IpAddr;
+
+    fn ip(addr: &str) -> IpAddr {
+        addr.parse().unwrap()
+    }
+
+    #[test]
+    fn test_forwarded_for_ignored_without_trusted_proxy() {
+        assert_eq!(Some(ip("203.0.113.7")), resolve_client_ip(Some(ip("203.0.113.7")), Some("10.0.0.1"), &[]));
+        assert_eq!(None, resolve_client_ip(None, Some("10.0.0.1"), &[]));
+    }
+
+    #[test]
+    fn test_forwarded_for_behind_trusted_proxy() {
+        let proxy = ip("10.0.0.2");
+
+        assert_eq!(Some(ip("203.0.113.7")), resolve_client_ip(Some(proxy), Some("203.0.113.7"), &[proxy]));
+        // The client sent a forged first address, the proxy appended the real one
+        assert_eq!(Some(ip("203.0.113.7")), resolve_client_ip(Some(proxy), Some("1.2.3.4, 203.0.113.7"), &[proxy]));
+        assert_eq!(Some(proxy), resolve_client_ip(Some(proxy), None, &[proxy]));
+        assert_eq!(Some(proxy), resolve_client_ip(Some(proxy), Some("unknown"), &[proxy]));
+    }
+}
@@ -0,0 +1,167 @@
+#[cfg(test)]
+mod test {
+    use crate::models::collaborator::{Collaborator, CollaboratorInvite, CollaboratorRole, InvitationStatus};
+    use crate::models::trip::Trip;
+
+    use bson::oid::ObjectId;
+
+    fn collaborator(user_id: &ObjectId, role: &str, status: &str) -> serde_json::Value {
+        serde_json::json!({
+            "user_id": {"$oid": user_id.to_hex()},
+            "role": role,
+            "status": status,
+            "invited_at": "2021-03-01T00:00:00+00:00",
+        })
+    }
+
+    fn get_trip(owner_id: &ObjectId, collaborators: Vec<serde_json::Value>, moderation_status: &str) -> Trip {
+        serde_json::from_value(serde_json::json!({
+            "_id": {"$oid": ObjectId::new().to_hex()},
+            "name": "Seoul",
+            "start_date": "2021-03-01T00:00:00+09:00",
+            "end_date": "2021-03-02T00:00:00+09:00",
+            "timezone": "Asia/Seoul",
+            "budget": {"amount": 0, "currency": "USD"},
+            "destination": "Seoul, Korea, Republic of",
+            "events": [],
+            "private": true,
+            "moderation_status": moderation_status,
+            "collaborators": collaborators,
+            "user_id": {"$oid": owner_id.to_hex()},
+        })).expect("Error parsing trip")
+    }
+
+    #[test]
+    fn test_roles() {
+        let owner_id = ObjectId::new();
+        let editor_id = ObjectId::new();
+        let viewer_id = ObjectId::new();
+        let invited_id = ObjectId::new();
+        let trip = get_trip(&owner_id, vec![
+            collaborator(&editor_id, "editor", "accepted"),
+            collaborator(&viewer_id, "viewer", "accepted"),
+            collaborator(&invited_id, "editor", "pending"),
+        ], "visible");
+
+        assert_eq!(Some(CollaboratorRole::Editor), trip.role_of(editor_id.to_hex().as_str()));
+        assert_eq!(Some(CollaboratorRole::Viewer), trip.role_of(viewer_id.to_hex().as_str()));
+        // Pending invitations give no access yet
+        assert_eq!(None, trip.role_of(invited_id.to_hex().as_str()));
+        assert_eq!(None, trip.role_of(owner_id.to_hex().as_str()));
+
+        assert!(trip.editable_by(owner_id.to_hex().as_str()));
+        assert!(trip.editable_by(editor_id.to_hex().as_str()));
+        assert!(!trip.editable_by(viewer_id.to_hex().as_str()));
+        assert!(!trip.editable_by(invited_id.to_hex().as_str()));
+        assert!(!trip.editable_by(ObjectId::new().to_hex().as_str()));
+    }
+
+    #[test]
+    fn test_hidden_trip_visible_to_members() {
+        let owner_id = ObjectId::new();
+        let viewer_id = ObjectId::new();
+        let invited_id = ObjectId::new();
+        let trip = get_trip(&owner_id, vec![
+            collaborator(&viewer_id, "viewer", "accepted"),
+            collaborator(&invited_id, "viewer", "pending"),
+        ], "hidden");
+
+        assert!(trip.visible_to(Some(&owner_id.to_hex())));
+        assert!(trip.visible_to(Some(&viewer_id.to_hex())));
+        assert!(!trip.visible_to(Some(&invited_id.to_hex())));
+        assert!(!trip.visible_to(None));
+    }
+
+    #[test]
+    fn test_private_trip_visible_to_members() {
+        let owner_id = ObjectId::new();
+        let viewer_id = ObjectId::new();
+        let invited_id = ObjectId::new();
+        let trip = get_trip(&owner_id, vec![
+            collaborator(&viewer_id, "viewer", "accepted"),
+            collaborator(&invited_id, "viewer", "pending"),
+        ], "visible");
+
+        assert!(trip.visible_to(Some(&owner_id.to_hex())));
+        assert!(trip.visible_to(Some(&viewer_id.to_hex())));
+        assert!(!trip.visible_to(Some(&invited_id.to_hex())));
+        assert!(!trip.visible_to(None));
+        assert_eq!("Trip not found", trip.visible(Some(&ObjectId::new().to_hex())).unwrap_err());
+    }
+
+    #[test]
+    fn test_public_trip_visible_to_everyone() {
+        let owner_id = ObjectId::new();
+        let mut trip = serde_json::to_value(get_trip(&owner_id, vec![], "visible")).unwrap();
+        trip["private"] = serde_json::Value::Bool(false);
+
+        let trip: Trip = serde_json::from_value(trip).expect("Error parsing trip");
+
+        assert!(trip.visible_to(None));
+        assert!(trip.visible(Some(&ObjectId::new().to_hex())).is_ok());
+    }
+
+    #[test]
+    fn test_collaborators_hidden_from_non_members() {
+        let owner_id = ObjectId::new();
+        let viewer_id = ObjectId::new();
+        let mut trip = serde_json::to_value(get_trip(&owner_id, vec![
+            collaborator(&viewer_id, "viewer", "accepted"),
+            collaborator(&ObjectId::new(), "editor", "pending"),
+        ], "visible")).unwrap();
+        trip["private"] = serde_json::Value::Bool(false);
+
+        let trip: Trip = serde_json::from_value(trip).expect("Error parsing trip");
+
+        assert_eq!(2, trip.clone().visible(Some(&owner_id.to_hex())).unwrap().collaborators.len());
+        assert_eq!(2, trip.clone().visible(Some(&viewer_id.to_hex())).unwrap().collaborators.len());
+        assert!(trip.clone().visible(Some(&ObjectId::new().to_hex())).unwrap().collaborators.is_empty());
+        assert!(trip.visible(None).unwrap().collaborators.is_empty());
+    }
+
+    #[test]
+    fn test_trip_without_collaborators() {
+        let owner_id = ObjectId::new();
+        let mut trip = serde_json::to_value(get_trip(&owner_id, vec![], "visible")).unwrap();
+        trip.as_object_mut().unwrap().remove("collaborators");
+
+        let trip: Trip = serde_json::from_value(trip).expect("Error parsing trip");
+
+        assert!(trip.collaborators.is_empty());
+        assert!(trip.editable_by(owner_id.to_hex().as_str()));
+    }
+
+    #[test]
+    fn test_parse_invite() {
+        let user_id = ObjectId::new().to_hex();
+        let invite: CollaboratorInvite = serde_json::from_value(serde_json::json!({
+            "user_id": user_id,
+            "role": "editor",
+        })).expect("Error parsing invite");
+
+        assert_eq!(user_id, invite.user_id);
+        assert_eq!(CollaboratorRole::Editor, invite.role);
+        assert!(serde_json::from_value::<CollaboratorInvite>(serde_json::json!({
+            "user_id": user_id,
+            "role": "owner",
+        })).is_err());
+    }
+
+    #[test]
+    fn test_collaborator_from_bson() {
+        let user_id = ObjectId::new();
+        let collaborator_doc = bson::doc! {
+            "user_id": user_id.clone(),
+            "role": CollaboratorRole::Viewer.as_str(),
+            "status": "pending",
+            "invited_at": "2021-03-01T00:00:00+00:00",
+        };
+
+        let collaborator = bson::from_bson::<Collaborator>(bson::Bson::Document(collaborator_doc))
+            .expect("Error parsing collaborator");
+
+        assert_eq!(user_id, collaborator.user_id);
+        assert_eq!(CollaboratorRole::Viewer, collaborator.role);
+        assert_eq!(InvitationStatus::Pending, collaborator.status);
+    }
+}
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod test {
+    use crate::models::conflict::{ConflictKind, ConflictSeverity, entry_conflicts};
+    use crate::models::currency::CurrencyConverter;
+    use crate::models::itinerary::ItineraryStop;
+    use crate::models::trip::{Trip, EventEntry};
+
+    use bson::oid::ObjectId;
+    use chrono_tz::Tz;
+    use std::collections::HashMap;
+
+    fn get_converter() -> CurrencyConverter {
+        let mut rates = HashMap::new();
+        rates.insert("KRW".to_string(), 1000.0);
+        CurrencyConverter::new("USD".to_string(), rates)
+    }
+
+    fn entry(start_hour: &str, duration: i32, amount: i64, currency: &str) -> serde_json::Value {
+        entry_on("2021-03-01T00:00:00+09:00", start_hour, duration, amount, currency)
+    }
+
+    fn entry_on(start_date: &str, start_hour: &str, duration: i32, amount: i64, currency: &str) -> serde_json::Value {
+        serde_json::json!({
+            "_id": {"$oid": ObjectId::new().to_hex()},
+            "event_id": {"$oid": ObjectId::new().to_hex()},
+            "start_date": start_date,
+            "start_hour": start_hour,
+            "budget": {"amount": amount, "currency": currency},
+            "duration": duration,
+        })
+    }
+
+    fn get_stop(value: serde_json::Value) -> ItineraryStop {
+        let event_entry: EventEntry = serde_json::from_value(value).expect("Error parsing entry");
+        ItineraryStop::from_entry(&event_entry, Tz::Asia__Seoul)
+    }
+
+    fn get_trip(events: Vec<serde_json::Value>, budget_limit: Option<i64>) -> Trip {
+        let mut trip = serde_json::json!({
+            "_id": {"$oid": ObjectId::new().to_hex()},
+            "name": "Seoul",
+            "start_date": "2021-03-01T00:00:00+09:00",
+            "end_date": "2021-03-02T00:00:00+09:00",
+            "timezone": "Asia/Seoul",
+            "budget": {"amount": 0, "currency": "USD"},
+            "destination": "Seoul, Korea, Republic of",
+            "events": events,
+            "private": false,
+            "user_id": {"$oid": ObjectId::new().to_hex()},
+        });
+        if let Some(amount) = budget_limit {
+            trip["budget_limit"] = serde_json::json!({"amount": amount, "currency": "USD"});
+        }
+        serde_json::from_value(trip).expect("Error parsing trip")
+    }
+
+    #[test]
+    fn test_no_conflicts() {
+        let trip = get_trip(vec![entry("09:00", 60, 1000, "USD")], Some(5000));
+        let stop = get_stop(entry("10:00", 60, 1000, "USD"));
+
+        let conflicts = entry_conflicts(&trip, &stop, &get_converter()).expect("Error: test conflicts");
+
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_overlap_conflict() {
+        let trip = get_trip(vec![entry("09:00", 90, 0, "USD")], None);
+        let stop = get_stop(entry("10:00", 60, 0, "USD"));
+
+        let conflicts = entry_conflicts(&trip, &stop, &get_converter()).expect("Error: test conflicts");
+
+        assert_eq!(1, conflicts.len());
+        assert_eq!(ConflictKind::Overlap, conflicts[0].kind);
+        assert!(conflicts[0].is_error());
+        assert_eq!(Some(trip.events[0].event_id.clone()), conflicts[0].event_id);
+        assert_eq!(Some("2021-03-01T10:30".to_string()), conflicts[0].end);
+    }
+
+    #[test]
+    fn test_out_of_range_conflicts() {
+        let trip = get_trip(vec![], None);
+        let converter = get_converter();
+
+        let before = get_stop(entry_on("2021-02-28T00:00:00+09:00", "10:00", 60, 0, "USD"));
+        let conflicts = entry_conflicts(&trip, &before, &converter).expect("Error: test conflicts");
+        assert_eq!(ConflictKind::BeforeTripStart, conflicts[0].kind);
+
+        // Ending past midnight on the last day is outside the trip
+        let after = get_stop(entry_on("2021-03-02T00:00:00+09:00", "23:00", 120, 0, "USD"));
+        let conflicts = entry_conflicts(&trip, &after, &converter).expect("Error: test conflicts");
+        assert_eq!(ConflictKind::AfterTripEnd, conflicts[0].kind);
+
+        let unknown = get_stop(entry("later", 60, 0, "USD"));
+        let conflicts = entry_conflicts(&trip, &unknown, &converter).expect("Error: test conflicts");
+        assert_eq!(ConflictKind::UnknownStart, conflicts[0].kind);
+    }
+
+    #[test]
+    fn test_over_budget_warning() {
+        let trip = get_trip(vec![entry("09:00", 60, 3000, "USD")], Some(4000));
+        let stop = get_stop(entry("11:00", 60, 15000, "KRW"));
+
+        let conflicts = entry_conflicts(&trip, &stop, &get_converter()).expect("Error: test conflicts");
+
+        assert_eq!(1, conflicts.len());
+        assert_eq!(ConflictKind::OverBudget, conflicts[0].kind);
+        assert_eq!(ConflictSeverity::Warning, conflicts[0].severity);
+        assert_eq!(Some(500), conflicts[0].over_by.as_ref().map(|over_by| over_by.amount));
+    }
+}
@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod test {
+    use crate::MongoDb;
+    use crate::models::currency::{
+        CurrencyConverter,
+        ExchangeRate,
+        Money,
+        RateUpdate,
+        minor_unit_exponent,
+        validate_currency
+    };
+
+    use mongodb::{Client, options::ClientOptions};
+    use mongodb::options::ResolverConfig;
+    use bson::oid::ObjectId;
+    use std::collections::HashMap;
+
+    async fn get_mongo_db() -> MongoDb {
+        dotenv::dotenv().ok();
+
+        let mut mongo_options = ClientOptions::parse_with_resolver_config(
+            std::env::var("MONGO_URL").expect("Error in Mongo URL").as_str(),
+            ResolverConfig::cloudflare()
+        ).await.expect("Error found while creating client options");
+        mongo_options.app_name = Some("YeoHengServer".to_string());
+        let mongo_client = Client::with_options(mongo_options).expect("Error found while creating mongo client");
+        mongo_client.database(std::env::var("TEST_DATABASE_NAME")
+            .expect("Error retrieving database name")
+            .as_str())
+    }
+
+    fn converter() -> CurrencyConverter {
+        let mut rates = HashMap::new();
+        rates.insert("EUR".to_string(), 0.5);
+        rates.insert("KRW".to_string(), 1200.0);
+        CurrencyConverter::new("USD".to_string(), rates)
+    }
+
+    #[test]
+    fn test_minor_unit_exponents() {
+        assert_eq!(Some(2), minor_unit_exponent("USD"));
+        assert_eq!(Some(0), minor_unit_exponent("JPY"));
+        assert_eq!(Some(0), minor_unit_exponent("KRW"));
+        assert_eq!(Some(3), minor_unit_exponent("KWD"));
+        assert!(validate_currency("XYZ").is_err());
+        assert!(Money::new(-1, "USD").validate().is_err());
+    }
+
+    #[test]
+    fn test_convert_between_currencies() {
+        let converter = converter();
+
+        assert_eq!(Money::new(500, "EUR"), converter.convert(&Money::new(1000, "USD"), "EUR").unwrap());
+        assert_eq!(Money::new(12000, "KRW"), converter.convert(&Money::new(1000, "USD"), "KRW").unwrap());
+        assert_eq!(Money::new(1000, "USD"), converter.to_base(&Money::new(12000, "KRW")).unwrap());
+        assert!(converter.convert(&Money::new(1000, "USD"), "JPY").is_err());
+    }
+
+    #[test]
+    fn test_sum_rounds_once() {
+        let converter = converter();
+        let cents = vec![Money::new(1, "USD"), Money::new(1, "USD"), Money::new(1, "USD")];
+
+        // Each cent is half a euro cent, rounding every entry would give 3 cents
+        assert_eq!(Money::new(2, "EUR"), converter.sum(&cents, "EUR").unwrap());
+        assert_eq!(Money::new(0, "EUR"), converter.sum(&[], "EUR").unwrap());
+    }
+
+    #[test]
+    fn test_decimal_round_trip() {
+        let money = Money::new(1050, "USD");
+
+        assert_eq!("10.50", money.to_decimal().to_string());
+        assert_eq!(money, Money::from_decimal(money.to_decimal(), "USD"));
+        assert_eq!(Money::new(30, "USD"), Money::from_major(0.1 + 0.2, "USD"));
+    }
+
+    #[test]
+    fn test_legacy_float_deserialization() {
+        let money: Money = serde_json::from_value(serde_json::json!(12.5))
+            .expect("Error deserializing money");
+        let structured: Money = serde_json::from_value(serde_json::json!({"amount": 500, "currency": "KRW"}))
+            .expect("Error deserializing money");
+
+        assert_eq!(1250, money.amount);
+        assert_eq!(Money::new(500, "KRW"), structured);
+    }
+
+    #[actix_rt::test]
+    async fn test_set_rate_requires_admin() {
+        let mongo_db = get_mongo_db().await;
+
+        let rate_update = RateUpdate {
+            currency: String::from("EUR"),
+            rate: 0.9,
+        };
+
+        let response = ExchangeRate::set_rate(rate_update, ObjectId::new().to_hex(), &mongo_db).await
+            .expect_err("Error: rate updated by unknown user");
+
+        assert_eq!("User not found", response);
+    }
+}
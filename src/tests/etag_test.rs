@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod test {
+    use crate::utils::etag::{etag, parse_if_match, version_filter, error_response, VERSION_MISMATCH};
+    use crate::models::event::Event;
+
+    use actix_web::http::StatusCode;
+    use bson::oid::ObjectId;
+    use mongodb::bson::{doc, Bson};
+
+    #[test]
+    fn test_etag_round_trip() {
+        assert_eq!("\"3\"", etag(3));
+        assert_eq!(Ok(Some(3)), parse_if_match(etag(3).as_str()));
+        assert_eq!(Ok(Some(3)), parse_if_match("W/\"3\""));
+        assert_eq!(Ok(Some(0)), parse_if_match(" 0 "));
+    }
+
+    #[test]
+    fn test_if_match_any() {
+        assert_eq!(Ok(None), parse_if_match("*"));
+    }
+
+    #[test]
+    fn test_invalid_if_match() {
+        assert_eq!(Err("Invalid If-Match header: \"abc\"".to_string()), parse_if_match("\"abc\""));
+        assert!(parse_if_match("\"1\", \"2\"").is_err());
+    }
+
+    #[test]
+    fn test_version_filter() {
+        assert_eq!(Bson::Int64(4), version_filter(4));
+        // Content from before versions has no version field
+        assert_eq!(Bson::Document(doc! {"$in": [0, Bson::Null]}), version_filter(0));
+    }
+
+    #[test]
+    fn test_error_response() {
+        assert_eq!(StatusCode::PRECONDITION_FAILED, error_response(VERSION_MISMATCH.to_string()).status());
+        assert_eq!(StatusCode::BAD_REQUEST, error_response("Event not found".to_string()).status());
+    }
+
+    #[test]
+    fn test_event_version_defaults_to_zero() {
+        let event: Event = serde_json::from_value(serde_json::json!({
+            "_id": {"$oid": ObjectId::new().to_hex()},
+            "name": "Gyeongbokgung",
+            "description": "Palace",
+            "tags": [],
+            "personal_type": "",
+            "rating": null,
+            "country": "KR",
+            "city": "Seoul",
+            "price": {"amount": 0, "currency": "KRW"},
+            "duration": 60,
+            "location": [126.9770, 37.5796],
+            "image": "",
+            "private": false,
+            "user_id": {"$oid": ObjectId::new().to_hex()},
+        })).expect("Error parsing event");
+
+        assert_eq!(0, event.version);
+    }
+}
@@ -2,11 +2,16 @@
 mod test {
     use super::*;
     use crate::MongoDb;
-    use crate::models::event::{Event, EventFilter};
+    use crate::models::event::{Event, EventFilter, EventSort, EventUpdate, get_find_filter};
+    use crate::models::opening_hours::MAX_EVENT_DURATION_MINUTES;
+    use crate::models::currency::Money;
+    use crate::models::report::ModerationStatus;
+    use crate::tests::helpers::seed_reference_data;
 
     use mongodb::{Client, options::ClientOptions};
     use mongodb::options::ResolverConfig;
     use bson::oid::ObjectId;
+    use mongodb::bson::{doc, Bson};
 
     fn type_of<T>(_: &T) -> &str { std::any::type_name::<T>() }
 
@@ -27,6 +32,7 @@ mod test {
     #[actix_rt::test]
     async fn test_create_event() {
         let mongo_db = get_mongo_db().await;
+        seed_reference_data(&mongo_db).await;
 
         let event = Event {
             _id: Some(ObjectId::new()),
@@ -34,18 +40,28 @@ mod test {
             description: String::from("Description"),
             tags: vec! [String::from("tag1"), String::from("tag2")],
             personal_type: String::from("Type"),
-            rating: Some(5.0),
-            country: String::from("Country"),
-            city: String::from("City"),
-            price: 100.0,
-            duration: String::from("Duration"),
+            rating: None,
+            review_count: 0,
+            save_count: 0,
+            saved: None,
+            country: String::from("Korea"),
+            city: String::from("Seoul"),
+            price: Money::new(10000, "USD"),
+            price_base: 0,
+            converted_price: None,
+            duration: 90,
+            opening_hours: None,
+            closures: Vec::new(),
             location: Some(vec! [0.0, 0.0]),
             image: String::from("Image"),
             private: false,
+            moderation_status: ModerationStatus::Visible,
+            moderation_reason: None,
+            version: 0,
             user_id: ObjectId::new(),
         };
 
-        let response = Event::create(event, &mongo_db).await;
+        let response = Event::create(event, &mongo_db).await.expect("Error creating event");
 
         assert_eq!(type_of(&ObjectId::new()), type_of(&response));
     }
@@ -55,8 +71,8 @@ mod test {
         let mongo_db = get_mongo_db().await;
 
         let filter = EventFilter {
-            offset: 0,
-            limit: 5,
+            cursor: None,
+            limit: Some(5),
             tags: None,
             personal_type: None,
             rating: None,
@@ -64,11 +80,14 @@ mod test {
             city: None,
             user_id: None,
             include_private: None,
+            ..Default::default()
         };
 
-        let response = Event::get_filtered_events(filter, &mongo_db).await;
+        let response = Event::get_filtered_events(filter, &mongo_db)
+            .await.expect("Error getting events");
 
-        assert_eq!("Test", response[0].name);
+        assert_eq!("Test", response.items[0].name);
+        assert!(response.items.len() <= 5);
     }
 
     #[actix_rt::test]
@@ -76,8 +95,8 @@ mod test {
         let mongo_db = get_mongo_db().await;
 
         let filter = EventFilter {
-            offset: 0,
-            limit: 1,
+            cursor: None,
+            limit: Some(1),
             tags: None,
             personal_type: None,
             rating: None,
@@ -85,11 +104,240 @@ mod test {
             city: None,
             user_id: None,
             include_private: Some(false),
+            ..Default::default()
         };
 
+        let page = Event::get_filtered_events(EventFilter {
+            limit: Some(1),
+            include_private: Some(false),
+            ..Default::default()
+        }, &mongo_db).await.expect("Error getting events");
+
         let response = Event::count_filtered_events(filter, &mongo_db)
             .await.expect("Error counting document");
 
-        assert_eq!(1, response);
+        // The limit only applies to the page, never to the count
+        assert_eq!(page.total, response);
+    }
+
+    #[actix_rt::test]
+    async fn test_get_event_sorted_by_price() {
+        let mongo_db = get_mongo_db().await;
+
+        let filter = EventFilter {
+            limit: Some(5),
+            sort: Some("price".to_string()),
+            ..Default::default()
+        };
+
+        let response = Event::get_filtered_events(filter, &mongo_db)
+            .await.expect("Error getting events");
+
+        assert!(response.items.windows(2).all(|pair| pair[0].price_base <= pair[1].price_base));
+    }
+
+    #[actix_rt::test]
+    async fn test_get_event_unknown_sort() {
+        let mongo_db = get_mongo_db().await;
+
+        let filter = EventFilter {
+            limit: Some(5),
+            sort: Some("popularity".to_string()),
+            ..Default::default()
+        };
+
+        let response = Event::get_filtered_events(filter, &mongo_db)
+            .await.expect_err("Error: unknown sort key accepted");
+
+        assert_eq!("Unknown sort key: popularity", response);
+    }
+
+    #[test]
+    fn test_parse_sort_keys() {
+        assert_eq!(Ok(EventSort::Rating), EventSort::parse("rating"));
+        assert_eq!(Ok(EventSort::Price), EventSort::parse("price"));
+        assert_eq!(Ok(EventSort::Newest), EventSort::parse("newest"));
+        assert_eq!(Ok(EventSort::Distance), EventSort::parse("distance"));
+        assert_eq!(Ok(EventSort::Saves), EventSort::parse("saves"));
+        assert!(EventSort::parse("Rating").is_err());
+        assert_eq!(None, EventSort::Distance.to_sort_doc());
+    }
+
+    #[test]
+    fn test_filter_price_and_rating_ranges() {
+        let filter = EventFilter {
+            price_min: Some(1000),
+            price_max: Some(5000),
+            rating_min: Some(4.0),
+            ..Default::default()
+        };
+
+        let filter_doc = get_find_filter(filter).expect("Error building filter");
+
+        assert_eq!(&doc! {"$gte": 1000_i64, "$lte": 5000_i64},
+                   filter_doc.get_document("price_base").unwrap());
+        assert_eq!(&doc! {"$gte": 4.0_f32}, filter_doc.get_document("rating").unwrap());
+    }
+
+    #[test]
+    fn test_filter_invalid_ranges() {
+        let inverted_price = EventFilter {
+            price_min: Some(5000),
+            price_max: Some(1000),
+            ..Default::default()
+        };
+        let combined_rating = EventFilter {
+            rating: Some(5.0),
+            rating_min: Some(4.0),
+            ..Default::default()
+        };
+
+        assert!(get_find_filter(inverted_price).is_err());
+        assert!(get_find_filter(combined_rating).is_err());
+    }
+
+    #[test]
+    fn test_filter_multiple_countries_and_cities() {
+        let filter = EventFilter {
+            country: Some("Mexico,Korea".to_string()),
+            city: Some("Seoul".to_string()),
+            price_max: Some(2000),
+            ..Default::default()
+        };
+
+        let filter_doc = get_find_filter(filter).expect("Error building filter");
+
+        assert_eq!(&doc! {"$in": ["Mexico", "Korea"]}, filter_doc.get_document("country").unwrap());
+        assert_eq!(&doc! {"$in": ["Seoul"]}, filter_doc.get_document("city").unwrap());
+        assert_eq!(&doc! {"$lte": 2000_i64}, filter_doc.get_document("price_base").unwrap());
+        assert!(!filter_doc.get_bool("private").unwrap());
+    }
+
+    #[test]
+    fn test_filter_hidden_events() {
+        let owner_id = ObjectId::new().to_hex();
+        let public_filter = EventFilter::default();
+        let own_filter = EventFilter {
+            user_id: Some(owner_id.clone()),
+            requester_id: Some(owner_id),
+            ..Default::default()
+        };
+
+        let public_doc = get_find_filter(public_filter).expect("Error building filter");
+        let own_doc = get_find_filter(own_filter).expect("Error building filter");
+
+        assert_eq!(&doc! {"$ne": "hidden"}, public_doc.get_document("moderation_status").unwrap());
+        assert!(own_doc.get("moderation_status").is_none());
+    }
+
+    #[test]
+    fn test_filter_deleted_events() {
+        let owner_id = ObjectId::new().to_hex();
+        let own_filter = EventFilter {
+            user_id: Some(owner_id.clone()),
+            requester_id: Some(owner_id),
+            ..Default::default()
+        };
+
+        let own_doc = get_find_filter(own_filter).expect("Error building filter");
+
+        // Owners do not get their deleted events back either, they are in the trash
+        assert_eq!(Some(&Bson::Null), own_doc.get("deleted_at"));
+    }
+
+    #[actix_rt::test]
+    async fn test_create_event_invalid_duration() {
+        let mongo_db = get_mongo_db().await;
+
+        let event = Event {
+            _id: None,
+            name: String::from("Test"),
+            description: String::from("Description"),
+            tags: vec! [String::from("tag1")],
+            personal_type: String::from("Type"),
+            rating: None,
+            review_count: 0,
+            save_count: 0,
+            saved: None,
+            country: String::from("Korea"),
+            city: String::from("Seoul"),
+            price: Money::new(10000, "USD"),
+            price_base: 0,
+            converted_price: None,
+            duration: 0,
+            opening_hours: None,
+            closures: Vec::new(),
+            location: None,
+            image: String::from("Image"),
+            private: false,
+            moderation_status: ModerationStatus::Visible,
+            moderation_reason: None,
+            version: 0,
+            user_id: ObjectId::new(),
+        };
+
+        let response = Event::create(event, &mongo_db).await
+            .expect_err("Error: invalid duration accepted");
+
+        assert_eq!(format!("Duration must be between 1 and {} minutes", MAX_EVENT_DURATION_MINUTES), response);
+    }
+
+    #[test]
+    fn test_legacy_duration_deserialization() {
+        let event: Event = serde_json::from_value(serde_json::json!({
+            "name": "Legacy",
+            "description": "Description",
+            "tags": [],
+            "personal_type": "Type",
+            "rating": null,
+            "country": "Country",
+            "city": "City",
+            "price": 10.0,
+            "duration": "1h30m",
+            "location": null,
+            "image": "Image",
+            "private": false,
+            "user_id": ObjectId::new(),
+            "_id": null
+        })).expect("Error deserializing event");
+
+        assert_eq!(90, event.duration);
+    }
+
+    #[test]
+    fn test_duration_out_of_range() {
+        // 2^32 + 60 would wrap around to 60
+        let response = serde_json::from_value::<Event>(serde_json::json!({
+            "name": "Overflow",
+            "description": "Description",
+            "tags": [],
+            "personal_type": "Type",
+            "rating": null,
+            "country": "Country",
+            "city": "City",
+            "price": 10.0,
+            "duration": 4294967356u64,
+            "location": null,
+            "image": "Image",
+            "private": false,
+            "user_id": ObjectId::new(),
+            "_id": null
+        })).expect_err("Error: overflowing duration accepted");
+
+        assert!(response.to_string().contains("duration out of range"));
+    }
+
+    #[test]
+    fn test_update_null_duration() {
+        let id = ObjectId::new().to_hex();
+        let update = |duration: serde_json::Value| serde_json::from_value::<EventUpdate>(serde_json::json!({
+            "_id": id,
+            "duration": duration,
+            "user_id": ObjectId::new().to_hex(),
+        }));
+
+        assert!(update(serde_json::Value::Null).is_ok());
+        assert!(update(serde_json::json!(90)).is_ok());
+        assert!(update(serde_json::json!(-4294967206i64)).is_err());
     }
 }
\ No newline at end of file
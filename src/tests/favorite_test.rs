@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod test {
+    use crate::MongoDb;
+    use crate::models::event::Event;
+    use crate::tests::helpers::create_test_event;
+    use crate::models::favorite::{Favorite, FavoriteFilter};
+
+    use mongodb::{Client, options::ClientOptions};
+    use mongodb::options::ResolverConfig;
+    use bson::oid::ObjectId;
+
+    async fn get_mongo_db() -> MongoDb {
+        dotenv::dotenv().ok();
+
+        let mut mongo_options = ClientOptions::parse_with_resolver_config(
+            std::env::var("MONGO_URL").expect("Error in Mongo URL").as_str(),
+            ResolverConfig::cloudflare()
+        ).await.expect("Error found while creating client options");
+        mongo_options.app_name = Some("YeoHengServer".to_string());
+        let mongo_client = Client::with_options(mongo_options).expect("Error found while creating mongo client");
+        mongo_client.database(std::env::var("TEST_DATABASE_NAME")
+            .expect("Error retrieving database name")
+            .as_str())
+    }
+
+    #[actix_rt::test]
+    async fn test_save_and_list_event() {
+        let mongo_db = get_mongo_db().await;
+        let event_id = create_test_event("Saved", None, &mongo_db).await;
+        let user_id = ObjectId::new();
+
+        Favorite::save(event_id.to_hex(), user_id.to_hex(), &mongo_db)
+            .await.expect("Error saving event");
+        // Saving again must not count twice
+        Favorite::save(event_id.to_hex(), user_id.to_hex(), &mongo_db)
+            .await.expect("Error saving event");
+
+        let saved = Favorite::get_saved_events(user_id.to_hex(), FavoriteFilter::default(), &mongo_db)
+            .await.expect("Error listing saved events");
+        let event = Event::get_event(event_id.to_hex(), &mongo_db)
+            .await.expect("Error getting event");
+
+        assert_eq!(1, saved.total);
+        assert_eq!(Some(true), saved.items[0].saved);
+        assert_eq!(1, event.save_count);
+    }
+
+    #[actix_rt::test]
+    async fn test_unsave_event() {
+        let mongo_db = get_mongo_db().await;
+        let event_id = create_test_event("Saved", None, &mongo_db).await;
+        let user_id = ObjectId::new();
+
+        Favorite::save(event_id.to_hex(), user_id.to_hex(), &mongo_db)
+            .await.expect("Error saving event");
+        Favorite::unsave(event_id.to_hex(), user_id.to_hex(), &mongo_db)
+            .await.expect("Error removing saved event");
+
+        let response = Favorite::unsave(event_id.to_hex(), user_id.to_hex(), &mongo_db)
+            .await.expect_err("Error: event removed twice");
+        let event = Event::get_event(event_id.to_hex(), &mongo_db)
+            .await.expect("Error getting event");
+
+        assert_eq!("Event was not saved".to_string(), response);
+        assert_eq!(0, event.save_count);
+    }
+
+    #[actix_rt::test]
+    async fn test_mark_saved() {
+        let mongo_db = get_mongo_db().await;
+        let saved_id = create_test_event("Saved", None, &mongo_db).await;
+        let other_id = create_test_event("Saved", None, &mongo_db).await;
+        let user_id = ObjectId::new();
+
+        Favorite::save(saved_id.to_hex(), user_id.to_hex(), &mongo_db)
+            .await.expect("Error saving event");
+
+        let mut events = vec![
+            Event::get_event(saved_id.to_hex(), &mongo_db).await.expect("Error getting event"),
+            Event::get_event(other_id.to_hex(), &mongo_db).await.expect("Error getting event"),
+        ];
+        Favorite::mark_saved(&mut events, user_id.to_hex(), &mongo_db)
+            .await.expect("Error marking saved events");
+
+        assert_eq!(Some(true), events[0].saved);
+        assert_eq!(Some(false), events[1].saved);
+    }
+}
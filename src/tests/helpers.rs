@@ -0,0 +1,55 @@
+#![cfg(test)]
+use crate::MongoDb;
+use crate::models::event::Event;
+use crate::models::currency::Money;
+use crate::models::place::load_places;
+use crate::models::report::ModerationStatus;
+
+use mongodb::options::UpdateOptions;
+use mongodb::bson::doc;
+use bson::oid::ObjectId;
+
+// Events only accept tags and places known as reference data
+pub async fn seed_reference_data(mongo_db: &MongoDb) {
+    let terms = [("tag1", "tag"), ("tag2", "tag"), ("type", "personal_type")];
+    for (name, kind) in terms.iter() {
+        mongo_db.collection("tags").update_one(
+            doc! {"name": *name, "kind": *kind},
+            doc! {"$set": {"updated_at": "2020-10-01T00:00:00Z"}},
+            UpdateOptions::builder().upsert(true).build()
+        ).await.expect("Error seeding taxonomy");
+    }
+    load_places(mongo_db).await.expect("Error loading places");
+}
+
+pub async fn create_test_event(name: &str, rating: Option<f32>, mongo_db: &MongoDb) -> ObjectId {
+    seed_reference_data(mongo_db).await;
+    let event = Event {
+        _id: None,
+        name: String::from(name),
+        description: String::from("Description"),
+        tags: vec! [String::from("tag1")],
+        personal_type: String::from("Type"),
+        rating,
+        review_count: 0,
+        save_count: 0,
+        saved: None,
+        country: String::from("Korea"),
+        city: String::from("Seoul"),
+        price: Money::new(1000, "USD"),
+        price_base: 0,
+        converted_price: None,
+        duration: 60,
+        opening_hours: None,
+        closures: Vec::new(),
+        location: None,
+        image: String::from("Image"),
+        private: false,
+        moderation_status: ModerationStatus::Visible,
+        moderation_reason: None,
+        version: 0,
+        user_id: ObjectId::new(),
+    };
+
+    Event::create(event, mongo_db).await.expect("Error creating event")
+}
@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod test {
+    use crate::models::itinerary::{Itinerary, ItineraryQuery, FreeGap};
+    use crate::models::routing::{StraightLineRouter, TravelProfile};
+    use crate::models::currency::CurrencyConverter;
+    use crate::models::trip::Trip;
+
+    use bson::oid::ObjectId;
+    use std::collections::HashMap;
+
+    fn get_converter() -> CurrencyConverter {
+        let mut rates = HashMap::new();
+        rates.insert("KRW".to_string(), 1000.0);
+        CurrencyConverter::new("USD".to_string(), rates)
+    }
+
+    fn entry(start_date: &str, start_hour: &str, duration: i32, amount: i64, currency: &str) -> serde_json::Value {
+        serde_json::json!({
+            "_id": {"$oid": ObjectId::new().to_hex()},
+            "event_id": {"$oid": ObjectId::new().to_hex()},
+            "start_date": start_date,
+            "start_hour": start_hour,
+            "budget": {"amount": amount, "currency": currency},
+            "duration": duration,
+        })
+    }
+
+    fn get_trip(events: Vec<serde_json::Value>) -> Trip {
+        serde_json::from_value(serde_json::json!({
+            "_id": {"$oid": ObjectId::new().to_hex()},
+            "name": "Seoul",
+            "start_date": "2021-03-01T00:00:00+09:00",
+            "end_date": "2021-03-02T00:00:00+09:00",
+            "timezone": "Asia/Seoul",
+            "budget": {"amount": 0, "currency": "USD"},
+            "destination": "Seoul, Korea, Republic of",
+            "events": events,
+            "private": false,
+            "user_id": {"$oid": ObjectId::new().to_hex()},
+        })).expect("Error parsing trip")
+    }
+
+    #[test]
+    fn test_itinerary_days() {
+        let trip = get_trip(vec![
+            entry("2021-03-01T00:00:00+09:00", "14:00", 120, 2000, "USD"),
+            entry("2021-03-01T00:00:00+09:00", "09:30", 90, 15000, "KRW"),
+            entry("2021-03-02T00:00:00+09:00", "10:00", 60, 500, "USD"),
+            entry("2021-03-05T00:00:00+09:00", "10:00", 60, 700, "USD"),
+        ]);
+        let window = ItineraryQuery::default().window().expect("Error reading window");
+
+        let itinerary = Itinerary::build(&trip, window, TravelProfile::Walking, &get_converter(), &StraightLineRouter).expect("Error: test build itinerary");
+
+        assert_eq!(2, itinerary.days.len());
+        let first_day = &itinerary.days[0];
+        assert_eq!("2021-03-01", first_day.date);
+        assert_eq!(Some("2021-03-01T09:30".to_string()), first_day.stops[0].start);
+        assert_eq!(Some("2021-03-01T11:00".to_string()), first_day.stops[0].end);
+        assert_eq!(Some("2021-03-01T16:00".to_string()), first_day.stops[1].end);
+        assert_eq!(vec![
+            FreeGap { from: "2021-03-01T08:00".to_string(), to: "2021-03-01T09:30".to_string(), minutes: 90 },
+            FreeGap { from: "2021-03-01T11:00".to_string(), to: "2021-03-01T14:00".to_string(), minutes: 180 },
+            FreeGap { from: "2021-03-01T16:00".to_string(), to: "2021-03-01T22:00".to_string(), minutes: 360 },
+        ], first_day.gaps);
+        assert_eq!(3500, first_day.spend.amount);
+        assert_eq!(500, itinerary.days[1].spend.amount);
+        assert_eq!(1, itinerary.unscheduled.len());
+        assert_eq!(4700, itinerary.total_spend.amount);
+    }
+
+    #[test]
+    fn test_itinerary_overlapping_stops() {
+        let trip = get_trip(vec![
+            entry("2021-03-01T00:00:00+09:00", "08:00", 180, 0, "USD"),
+            entry("2021-03-01T00:00:00+09:00", "10:00", 60, 0, "USD"),
+        ]);
+        let query = ItineraryQuery {
+            day_start: Some("08:00".to_string()),
+            day_end: Some("12:00".to_string()),
+            profile: None,
+        };
+
+        let itinerary = Itinerary::build(&trip, query.window().unwrap(), TravelProfile::Walking, &get_converter(), &StraightLineRouter)
+            .expect("Error: test build itinerary");
+
+        assert_eq!(vec![
+            FreeGap { from: "2021-03-01T11:00".to_string(), to: "2021-03-01T12:00".to_string(), minutes: 60 },
+        ], itinerary.days[0].gaps);
+    }
+
+    #[test]
+    fn test_itinerary_invalid_window() {
+        let query = ItineraryQuery {
+            day_start: Some("20:00".to_string()),
+            day_end: Some("09:00".to_string()),
+            profile: None,
+        };
+
+        assert_eq!(Err("day_start must be before day_end".to_string()), query.window());
+    }
+
+    #[test]
+    fn test_itinerary_transfers() {
+        let located = |start_hour: &str, location: [f64; 2]| {
+            let mut value = entry("2021-03-01T00:00:00+09:00", start_hour, 60, 0, "USD");
+            value["event"] = serde_json::json!({
+                "name": "Event",
+                "image": "",
+                "city": "Seoul",
+                "location": location,
+                "price": {"amount": 0, "currency": "USD"},
+            });
+            value
+        };
+        // Gyeongbokgung, then about 1km away and 40km away
+        let trip = get_trip(vec![
+            located("09:00", [126.9770, 37.5796]),
+            located("10:30", [126.9770, 37.5700]),
+            entry("2021-03-01T00:00:00+09:00", "11:00", 30, 0, "USD"),
+            located("11:45", [127.3900, 37.4800]),
+        ]);
+        let window = ItineraryQuery::default().window().unwrap();
+
+        let itinerary = Itinerary::build(&trip, window, TravelProfile::Walking, &get_converter(), &StraightLineRouter)
+            .expect("Error: test build itinerary");
+
+        let transfers = &itinerary.days[0].transfers;
+        assert_eq!(2, transfers.len());
+        assert_eq!(trip.events[0].event_id, transfers[0].from_event_id);
+        assert_eq!(30, transfers[0].available_minutes);
+        assert!(transfers[0].feasible);
+        // The stop without a location is skipped
+        assert_eq!(trip.events[3].event_id, transfers[1].to_event_id);
+        assert!(!transfers[1].feasible);
+    }
+}
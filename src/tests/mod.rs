@@ -1,3 +1,25 @@
+pub(crate) mod helpers;
 pub(crate) mod event_test;
 pub(crate) mod trip_test;
-pub(crate) mod user_test;
\ No newline at end of file
+pub(crate) mod user_test;
+pub(crate) mod pagination_test;
+pub(crate) mod review_test;
+pub(crate) mod favorite_test;
+pub(crate) mod opening_hours_test;
+pub(crate) mod currency_test;
+pub(crate) mod tag_test;
+pub(crate) mod place_test;
+pub(crate) mod report_test;
+pub(crate) mod audit_test;
+pub(crate) mod trash_test;
+pub(crate) mod itinerary_test;
+pub(crate) mod conflict_test;
+pub(crate) mod routing_test;
+pub(crate) mod optimizer_test;
+pub(crate) mod timezone_test;
+pub(crate) mod collaborator_test;
+pub(crate) mod share_link_test;
+pub(crate) mod trip_hub_test;
+pub(crate) mod etag_test;
+pub(crate) mod websocket_test;
+pub(crate) mod client_ip_test;
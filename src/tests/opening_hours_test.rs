@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod test {
+    use crate::models::opening_hours::{
+        OpeningHours,
+        TimeRange,
+        Closure,
+        parse_duration,
+        validate_duration
+    };
+
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(format!("{} {}", date, time).as_str(), "%Y-%m-%d %H:%M")
+            .unwrap()
+    }
+
+    fn range(open: &str, close: &str) -> TimeRange {
+        TimeRange { open: open.to_string(), close: close.to_string() }
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(Some(90), parse_duration("90"));
+        assert_eq!(Some(90), parse_duration("1h30m"));
+        assert_eq!(Some(120), parse_duration("2 hours"));
+        assert_eq!(Some(45), parse_duration("45 min"));
+        assert_eq!(None, parse_duration("a while"));
+        assert!(validate_duration(0).is_err());
+        assert!(validate_duration(90).is_ok());
+    }
+
+    #[test]
+    fn test_visit_within_opening_hours() {
+        // 2020-11-23 is a Monday
+        let opening_hours = OpeningHours {
+            monday: vec![range("09:00", "13:00"), range("15:00", "19:00")],
+            ..Default::default()
+        };
+
+        assert!(opening_hours.check_visit(at("2020-11-23", "10:00"), 120).is_empty());
+        assert!(opening_hours.check_visit(at("2020-11-23", "15:30"), 60).is_empty());
+        assert_eq!(1, opening_hours.check_visit(at("2020-11-23", "12:00"), 120).len());
+        assert_eq!(1, opening_hours.check_visit(at("2020-11-23", "08:00"), 30).len());
+    }
+
+    #[test]
+    fn test_visit_on_closed_day_and_overnight() {
+        let opening_hours = OpeningHours {
+            friday: vec![range("20:00", "02:00")],
+            ..Default::default()
+        };
+
+        // 2020-11-27 is a Friday
+        assert!(opening_hours.check_visit(at("2020-11-27", "23:00"), 120).is_empty());
+        assert_eq!(vec!["Event is closed on Thursday".to_string()],
+                   opening_hours.check_visit(at("2020-11-26", "21:00"), 60));
+    }
+
+    #[test]
+    fn test_visit_after_midnight() {
+        // 2020-11-27 is a Friday, Saturday has its own hours
+        let opening_hours = OpeningHours {
+            friday: vec![range("22:00", "02:00")],
+            saturday: vec![range("10:00", "18:00")],
+            ..Default::default()
+        };
+
+        assert!(opening_hours.check_visit(at("2020-11-28", "01:00"), 60).is_empty());
+        assert_eq!(1, opening_hours.check_visit(at("2020-11-28", "01:30"), 60).len());
+        // Sunday is closed even though Saturday's range does not cross midnight
+        assert_eq!(vec!["Event is closed on Sunday".to_string()],
+                   opening_hours.check_visit(at("2020-11-29", "01:00"), 60));
+    }
+
+    #[test]
+    fn test_closures() {
+        let holidays = Closure {
+            from: "12-20".to_string(),
+            to: "01-06".to_string(),
+            reason: Some("Holidays".to_string()),
+        };
+        let works = Closure {
+            from: "2021-03-01".to_string(),
+            to: "2021-03-15".to_string(),
+            reason: None,
+        };
+
+        assert!(holidays.validate().is_ok());
+        assert!(holidays.covers(NaiveDate::from_ymd(2020, 12, 31)));
+        assert!(holidays.covers(NaiveDate::from_ymd(2021, 1, 2)));
+        assert!(!holidays.covers(NaiveDate::from_ymd(2021, 2, 1)));
+        assert!(works.covers(NaiveDate::from_ymd(2021, 3, 10)));
+        assert!(!works.covers(NaiveDate::from_ymd(2022, 3, 10)));
+    }
+
+    #[test]
+    fn test_invalid_schedule() {
+        let mixed = Closure { from: "12-20".to_string(), to: "2021-01-06".to_string(), reason: None };
+        let inverted = Closure { from: "2021-02-01".to_string(), to: "2021-01-01".to_string(), reason: None };
+        let bad_hours = OpeningHours {
+            sunday: vec![range("9am", "17:00")],
+            ..Default::default()
+        };
+
+        assert!(mixed.validate().is_err());
+        assert!(inverted.validate().is_err());
+        assert!(bad_hours.validate().is_err());
+    }
+}
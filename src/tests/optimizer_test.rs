@@ -0,0 +1,134 @@
+#[cfg(test)]
+mod test {
+    use crate::models::optimizer::{Proposal, EventSchedule, check_slots};
+    use crate::models::currency::CurrencyConverter;
+    use crate::models::itinerary::ItineraryQuery;
+    use crate::models::opening_hours::{OpeningHours, TimeRange};
+    use crate::models::routing::{StraightLineRouter, TravelProfile};
+    use crate::models::trip::Trip;
+
+    use bson::oid::ObjectId;
+    use chrono::NaiveDateTime;
+    use std::collections::HashMap;
+
+    fn entry(start_hour: &str, duration: i32, fixed: bool, location: Option<[f64; 2]>) -> serde_json::Value {
+        let mut value = serde_json::json!({
+            "_id": {"$oid": ObjectId::new().to_hex()},
+            "event_id": {"$oid": ObjectId::new().to_hex()},
+            "start_date": "2021-03-01T00:00:00+09:00",
+            "start_hour": start_hour,
+            "budget": {"amount": 0, "currency": "USD"},
+            "duration": duration,
+            "fixed": fixed,
+        });
+        if let Some(location) = location {
+            value["event"] = serde_json::json!({
+                "name": "Event",
+                "image": "",
+                "city": "Seoul",
+                "location": location,
+                "price": {"amount": 0, "currency": "USD"},
+            });
+        }
+        value
+    }
+
+    fn get_trip(events: Vec<serde_json::Value>) -> Trip {
+        serde_json::from_value(serde_json::json!({
+            "_id": {"$oid": ObjectId::new().to_hex()},
+            "name": "Seoul",
+            "start_date": "2021-03-01T00:00:00+09:00",
+            "end_date": "2021-03-01T00:00:00+09:00",
+            "timezone": "Asia/Seoul",
+            "budget": {"amount": 0, "currency": "USD"},
+            "destination": "Seoul, Korea, Republic of",
+            "events": events,
+            "private": false,
+            "user_id": {"$oid": ObjectId::new().to_hex()},
+        })).expect("Error parsing trip")
+    }
+
+    fn build(trip: &Trip, schedules: &HashMap<ObjectId, EventSchedule>) -> Proposal {
+        let window = ItineraryQuery::default().window().unwrap();
+        Proposal::build(trip, schedules, window, TravelProfile::Walking, &StraightLineRouter)
+            .expect("Error: test build proposal")
+    }
+
+    #[test]
+    fn test_closest_event_next() {
+        let trip = get_trip(vec![
+            entry("later", 60, false, Some([126.9770, 37.5796])),
+            entry("later", 60, false, Some([127.3900, 37.4800])),
+            entry("later", 60, false, Some([126.9770, 37.5700])),
+        ]);
+
+        let proposal = build(&trip, &HashMap::new());
+
+        let stops = &proposal.days[0].stops;
+        assert_eq!(vec![0, 2, 1], stops.iter().map(|stop| stop.index).collect::<Vec<usize>>());
+        assert_eq!("2021-03-01T08:00", stops[0].start);
+        // About 1km walked, the slot is rounded to the next quarter
+        assert_eq!("2021-03-01T09:15", stops[1].start);
+        assert!(stops.iter().all(|stop| stop.moved));
+        assert!(proposal.unplaced.is_empty());
+        assert_eq!(proposal.distance_meters, stops.iter().map(|stop| stop.distance_meters).sum::<i64>());
+    }
+
+    #[test]
+    fn test_fixed_entries_and_opening_hours() {
+        let trip = get_trip(vec![
+            entry("10:00", 60, true, None),
+            entry("09:00", 90, false, None),
+        ]);
+        let mut schedules = HashMap::new();
+        schedules.insert(trip.events[1].event_id.clone(), EventSchedule {
+            opening_hours: Some(OpeningHours {
+                monday: vec![TimeRange { open: "09:30".to_string(), close: "18:00".to_string() }],
+                ..OpeningHours::default()
+            }),
+            closures: vec![],
+        });
+
+        let proposal = build(&trip, &schedules);
+
+        let stops = &proposal.days[0].stops;
+        assert_eq!(2, stops.len());
+        assert_eq!(0, stops[0].index);
+        assert!(stops[0].fixed);
+        assert!(!stops[0].moved);
+        assert_eq!("2021-03-01T10:00", stops[0].start);
+        // Too short between the opening and the fixed entry, goes right after it
+        assert_eq!("2021-03-01T11:00", stops[1].start);
+    }
+
+    #[test]
+    fn test_unplaced_entries() {
+        let trip = get_trip(vec![
+            entry("later", 15 * 60, false, None),
+            entry("later", 60, false, None),
+        ]);
+
+        let proposal = build(&trip, &HashMap::new());
+
+        assert_eq!(1, proposal.days[0].stops.len());
+        assert_eq!(1, proposal.unplaced.len());
+        assert_eq!(0, proposal.unplaced[0].index);
+    }
+
+    #[test]
+    fn test_check_slots() {
+        let trip = get_trip(vec![
+            entry("10:00", 60, true, None),
+            entry("09:00", 90, false, None),
+        ]);
+        let converter = CurrencyConverter::new("USD".to_string(), HashMap::new());
+        let at = |time: &str| NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M").unwrap();
+
+        assert!(check_slots(&trip, &[(1, at("2021-03-01T11:00"))], &converter).is_ok());
+        assert!(check_slots(&trip, &[(1, at("2021-03-01T10:30"))], &converter).is_err());
+        // Outside the trip dates
+        assert!(check_slots(&trip, &[(1, at("2021-03-02T11:00"))], &converter).is_err());
+        // Moving both entries is checked against where they end up
+        assert!(check_slots(&trip, &[(0, at("2021-03-01T08:00")), (1, at("2021-03-01T09:30"))], &converter).is_ok());
+    }
+}
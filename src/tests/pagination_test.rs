@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod test {
+    use crate::utils::pagination::{
+        PageRequest,
+        encode_cursor,
+        decode_cursor,
+        page_sort,
+        DEFAULT_PAGE_SIZE,
+        MAX_PAGE_SIZE
+    };
+
+    use mongodb::bson::doc;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = encode_cursor(40);
+
+        assert!(!cursor.contains("40"));
+        assert_eq!(Ok(40), decode_cursor(cursor.as_str()));
+    }
+
+    #[test]
+    fn test_invalid_cursor() {
+        assert!(decode_cursor("not a cursor").is_err());
+        assert!(decode_cursor(base64::encode("skip:-3").as_str()).is_err());
+    }
+
+    #[test]
+    fn test_page_size_defaults_and_cap() {
+        let default_page = PageRequest::new(None, None).expect("Error building page");
+        let capped_page = PageRequest::new(None, Some(10_000)).expect("Error building page");
+
+        assert_eq!(PageRequest { skip: 0, limit: DEFAULT_PAGE_SIZE }, default_page);
+        assert_eq!(MAX_PAGE_SIZE, capped_page.limit);
+        assert!(PageRequest::new(None, Some(0)).is_err());
+    }
+
+    #[test]
+    fn test_next_cursor() {
+        let page = PageRequest::new(Some(encode_cursor(20)), Some(10)).expect("Error building page");
+
+        assert_eq!(Some(encode_cursor(30)), page.next_cursor(10, 45));
+        assert_eq!(None, page.next_cursor(10, 30));
+        assert_eq!(None, page.next_cursor(7, 45));
+    }
+
+    #[test]
+    fn test_page_sort() {
+        let filter = doc! {"private": false};
+
+        assert_eq!(Some(doc! {"_id": 1}), page_sort(None, &filter));
+        assert_eq!(Some(doc! {"rating": -1}), page_sort(Some(doc! {"rating": -1}), &filter));
+        assert_eq!(None, page_sort(None, &doc! {"location": {"$near": [126.97, 37.56]}}));
+    }
+}
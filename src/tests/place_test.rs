@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod test {
+    use crate::MongoDb;
+    use crate::models::place::{City, Country, PlaceQuery, dataset, load_places, resolve_destination};
+
+    use mongodb::{Client, options::ClientOptions};
+    use mongodb::options::ResolverConfig;
+
+    async fn get_mongo_db() -> MongoDb {
+        dotenv::dotenv().ok();
+
+        let mut mongo_options = ClientOptions::parse_with_resolver_config(
+            std::env::var("MONGO_URL").expect("Error in Mongo URL").as_str(),
+            ResolverConfig::cloudflare()
+        ).await.expect("Error found while creating client options");
+        mongo_options.app_name = Some("YeoHengServer".to_string());
+        let mongo_client = Client::with_options(mongo_options).expect("Error found while creating mongo client");
+        mongo_client.database(std::env::var("TEST_DATABASE_NAME")
+            .expect("Error retrieving database name")
+            .as_str())
+    }
+
+    #[test]
+    fn test_dataset_is_consistent() {
+        let places = dataset().expect("Error reading dataset");
+        let codes: Vec<&str> = places.countries.iter().map(|country| country.code.as_str()).collect();
+
+        assert!(codes.iter().all(|code| code.len() == 2 && code.chars().all(|c| c.is_ascii_uppercase())));
+        assert!(places.cities.iter().all(|city| codes.contains(&city.country_code.as_str())));
+        assert!(places.cities.iter().all(|city| city.location.len() == 2 && !city.timezone.is_empty()));
+    }
+
+    #[test]
+    fn test_country_keys() {
+        let country = Country {
+            _id: None,
+            code: "KR".to_string(),
+            name: "South Korea".to_string(),
+            variants: vec!["Korea".to_string()],
+        };
+
+        assert_eq!(vec!["kr", "south korea", "korea"], country.keys());
+    }
+
+    #[actix_rt::test]
+    async fn test_resolve_places() {
+        let mongo_db = get_mongo_db().await;
+        load_places(&mongo_db).await.expect("Error loading places");
+
+        let country = Country::resolve("corea del sur", &mongo_db).await.expect("Error resolving country");
+        let city = City::resolve("SEÚL", Some("KR"), &mongo_db).await.expect("Error resolving city");
+
+        assert_eq!("KR", country.code);
+        assert_eq!("Seoul", city.name);
+        assert_eq!("Asia/Seoul", city.timezone);
+        assert!(City::resolve("Paris", None, &mongo_db).await.is_err());
+        assert_eq!(Ok("Paris, France".to_string()), resolve_destination("paris, fr", &mongo_db).await);
+        assert_eq!(Ok("Japan".to_string()), resolve_destination("Japón", &mongo_db).await);
+    }
+
+    #[actix_rt::test]
+    async fn test_autocomplete_cities() {
+        let mongo_db = get_mongo_db().await;
+        load_places(&mongo_db).await.expect("Error loading places");
+
+        let query = PlaceQuery {
+            q: String::from("se"),
+            country: Some(String::from("Korea")),
+            limit: None,
+        };
+
+        let response = City::autocomplete(query, &mongo_db).await.expect("Error autocompleting cities");
+
+        assert_eq!(vec!["Seoul"], response.iter().map(|city| city.name.as_str()).collect::<Vec<&str>>());
+    }
+}
@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod test {
+    use crate::MongoDb;
+    use crate::models::report::{
+        ContentType,
+        ModerationAction,
+        Report,
+        ReportCreate,
+        ReportFilter,
+        ReportReason
+    };
+
+    use mongodb::{Client, options::ClientOptions};
+    use mongodb::options::ResolverConfig;
+    use bson::oid::ObjectId;
+
+    async fn get_mongo_db() -> MongoDb {
+        dotenv::dotenv().ok();
+
+        let mut mongo_options = ClientOptions::parse_with_resolver_config(
+            std::env::var("MONGO_URL").expect("Error in Mongo URL").as_str(),
+            ResolverConfig::cloudflare()
+        ).await.expect("Error found while creating client options");
+        mongo_options.app_name = Some("YeoHengServer".to_string());
+        let mongo_client = Client::with_options(mongo_options).expect("Error found while creating mongo client");
+        mongo_client.database(std::env::var("TEST_DATABASE_NAME")
+            .expect("Error retrieving database name")
+            .as_str())
+    }
+
+    #[test]
+    fn test_moderation_names() {
+        let action: ModerationAction = serde_json::from_str("\"force_private\"").expect("Error parsing action");
+
+        assert_eq!(ModerationAction::ForcePrivate, action);
+        assert_eq!("events", ContentType::Event.collection_name());
+        assert_eq!("trips", ContentType::Trip.collection_name());
+    }
+
+    #[actix_rt::test]
+    async fn test_report_missing_content() {
+        let mongo_db = get_mongo_db().await;
+
+        let report = ReportCreate {
+            content_type: ContentType::Trip,
+            content_id: ObjectId::new().to_hex(),
+            reason: ReportReason::Spam,
+            details: None,
+        };
+
+        let response = Report::create(report, ObjectId::new().to_hex(), &mongo_db).await
+            .expect_err("Error: missing content reported");
+
+        assert_eq!("Reported content not found", response);
+    }
+
+    #[actix_rt::test]
+    async fn test_queue_requires_admin() {
+        let mongo_db = get_mongo_db().await;
+
+        let response = Report::get_queue(ReportFilter::default(), ObjectId::new().to_hex(), &mongo_db).await
+            .expect_err("Error: queue listed for unknown user");
+
+        assert_eq!("User not found", response);
+    }
+}
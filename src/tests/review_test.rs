@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod test {
+    use crate::MongoDb;
+    use crate::models::event::Event;
+    use crate::tests::helpers::create_test_event;
+    use crate::models::review::{Review, ReviewCreate, ReviewEdit, ReviewFilter, validate_rating};
+
+    use mongodb::{Client, options::ClientOptions};
+    use mongodb::options::ResolverConfig;
+    use bson::oid::ObjectId;
+
+    async fn get_mongo_db() -> MongoDb {
+        dotenv::dotenv().ok();
+
+        let mut mongo_options = ClientOptions::parse_with_resolver_config(
+            std::env::var("MONGO_URL").expect("Error in Mongo URL").as_str(),
+            ResolverConfig::cloudflare()
+        ).await.expect("Error found while creating client options");
+        mongo_options.app_name = Some("YeoHengServer".to_string());
+        let mongo_client = Client::with_options(mongo_options).expect("Error found while creating mongo client");
+        mongo_client.database(std::env::var("TEST_DATABASE_NAME")
+            .expect("Error retrieving database name")
+            .as_str())
+    }
+
+    #[actix_rt::test]
+    async fn test_review_updates_event_rating() {
+        let mongo_db = get_mongo_db().await;
+        let event_id = create_test_event("Reviewed", Some(1.0), &mongo_db).await;
+
+        for (user_id, rating) in [(ObjectId::new(), 4), (ObjectId::new(), 5)].iter().cloned() {
+            Review::create(ReviewCreate { rating, text: String::from("Nice") },
+                           event_id.to_hex(),
+                           user_id.to_hex(),
+                           &mongo_db).await.expect("Error creating review");
+        }
+
+        let event = Event::get_event(event_id.to_hex(), &mongo_db)
+            .await.expect("Error getting event");
+
+        assert_eq!(Some(4.5), event.rating);
+        assert_eq!(2, event.review_count);
+    }
+
+    #[actix_rt::test]
+    async fn test_review_edit_and_delete() {
+        let mongo_db = get_mongo_db().await;
+        let event_id = create_test_event("Reviewed", Some(1.0), &mongo_db).await;
+        let user_id = ObjectId::new();
+
+        let review_id = Review::create(ReviewCreate { rating: 2, text: String::from("Meh") },
+                                       event_id.to_hex(),
+                                       user_id.to_hex(),
+                                       &mongo_db).await.expect("Error creating review");
+
+        let review = Review::update(review_id.to_hex(),
+                                    ReviewEdit { rating: Some(3), text: None },
+                                    user_id.to_hex(),
+                                    &mongo_db).await.expect("Error updating review");
+        assert_eq!(3, review.rating);
+
+        Review::delete(review_id.to_hex(), user_id.to_hex(), &mongo_db)
+            .await.expect("Error deleting review");
+
+        let event = Event::get_event(event_id.to_hex(), &mongo_db)
+            .await.expect("Error getting event");
+        assert_eq!(None, event.rating);
+        assert_eq!(0, event.review_count);
+    }
+
+    #[actix_rt::test]
+    async fn test_single_review_per_user() {
+        let mongo_db = get_mongo_db().await;
+        let event_id = create_test_event("Reviewed", Some(1.0), &mongo_db).await;
+        let user_id = ObjectId::new();
+
+        Review::create(ReviewCreate { rating: 5, text: String::from("Great") },
+                       event_id.to_hex(),
+                       user_id.to_hex(),
+                       &mongo_db).await.expect("Error creating review");
+
+        let response = Review::create(ReviewCreate { rating: 1, text: String::from("Again") },
+                                      event_id.to_hex(),
+                                      user_id.to_hex(),
+                                      &mongo_db).await.expect_err("Error: duplicated review accepted");
+
+        assert_eq!("User already reviewed this event".to_string(), response);
+
+        let reviews = Review::get_event_reviews(event_id.to_hex(), ReviewFilter::default(), &mongo_db)
+            .await.expect("Error listing reviews");
+        assert_eq!(1, reviews.total);
+    }
+
+    #[test]
+    fn test_validate_rating() {
+        assert!(validate_rating(1).is_ok());
+        assert!(validate_rating(5).is_ok());
+        assert!(validate_rating(0).is_err());
+        assert!(validate_rating(6).is_err());
+    }
+}
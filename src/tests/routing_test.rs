@@ -0,0 +1,38 @@
+#[cfg(test)]
+mod test {
+    use crate::models::routing::{Router, StraightLineRouter, TravelProfile, great_circle_distance};
+
+    const SEOUL: [f64; 2] = [126.9780, 37.5665];
+    const BUSAN: [f64; 2] = [129.0756, 35.1796];
+
+    #[test]
+    fn test_great_circle_distance() {
+        let distance = great_circle_distance(&SEOUL, &BUSAN).expect("Error: test distance");
+
+        assert!((distance - 325_000.0).abs() < 5_000.0);
+        assert_eq!(Ok(0.0), great_circle_distance(&SEOUL, &SEOUL));
+    }
+
+    #[test]
+    fn test_invalid_location() {
+        assert!(great_circle_distance(&[126.9780], &BUSAN).is_err());
+        assert!(great_circle_distance(&[37.5665, 190.0], &BUSAN).is_err());
+    }
+
+    #[test]
+    fn test_profiles() {
+        let router = StraightLineRouter;
+        // About 9km
+        let to = [126.9780, 37.6474];
+
+        let walking = router.route(&SEOUL, &to, TravelProfile::Walking).expect("Error: test route");
+        let driving = router.route(&SEOUL, &to, TravelProfile::Driving).expect("Error: test route");
+        let transit = router.route(&SEOUL, &to, TravelProfile::Transit).expect("Error: test route");
+
+        assert_eq!(walking.distance_meters, driving.distance_meters);
+        assert_eq!(120, walking.minutes);
+        assert_eq!(23, driving.minutes);
+        assert_eq!(37, transit.minutes);
+        assert_eq!(0, router.route(&SEOUL, &SEOUL, TravelProfile::Transit).unwrap().minutes);
+    }
+}
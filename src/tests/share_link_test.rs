@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod test {
+    use crate::models::share_link::{ShareLink, ShareLinkCreate, hash_token, new_token};
+    use crate::models::trip::Trip;
+
+    use bson::oid::ObjectId;
+    use mongodb::bson::{doc, Bson};
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_new_token() {
+        let token = new_token();
+
+        assert_eq!(43, token.len());
+        assert!(token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_ne!(token, new_token());
+    }
+
+    #[test]
+    fn test_hash_token() {
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            hash_token("abc")
+        );
+        assert_ne!(hash_token("abc"), hash_token("abd"));
+    }
+
+    #[test]
+    fn test_link_expiry() {
+        let now = Utc.ymd(2021, 3, 1).and_hms(12, 0, 0);
+        let link_doc = doc! {
+            "_id": ObjectId::new(),
+            "trip_id": ObjectId::new(),
+            "token_hash": hash_token("abc"),
+            "created_at": now,
+        };
+        let mut link = bson::from_bson::<ShareLink>(Bson::Document(link_doc)).expect("Error parsing share link");
+
+        assert!(link.is_active(now));
+        link.expires_at = Some(now + Duration::hours(1));
+        assert!(link.is_active(now));
+        assert!(!link.is_active(now + Duration::hours(1)));
+    }
+
+    #[test]
+    fn test_link_hides_token_hash() {
+        let link_doc = doc! {
+            "_id": ObjectId::new(),
+            "trip_id": ObjectId::new(),
+            "token_hash": hash_token("abc"),
+            "created_at": Utc::now(),
+            "expires_at": Utc::now(),
+        };
+        let link = bson::from_bson::<ShareLink>(Bson::Document(link_doc)).expect("Error parsing share link");
+
+        let value = serde_json::to_value(&link).unwrap();
+
+        assert!(value.get("token_hash").is_none());
+        assert!(value["expires_at"].is_string());
+    }
+
+    #[test]
+    fn test_parse_link_create() {
+        let link_create: ShareLinkCreate = serde_json::from_value(serde_json::json!({}))
+            .expect("Error parsing share link");
+        assert!(link_create.expires_at.is_none());
+
+        let link_create: ShareLinkCreate = serde_json::from_value(serde_json::json!({
+            "expires_at": "2021-03-01T12:00:00+09:00"
+        })).expect("Error parsing share link");
+        assert_eq!(Some(Utc.ymd(2021, 3, 1).and_hms(3, 0, 0)), link_create.expires_at);
+
+        let link_create: ShareLinkCreate = serde_json::from_value(serde_json::json!({
+            "expires_at": null
+        })).expect("Error parsing share link");
+        assert!(link_create.expires_at.is_none());
+    }
+
+    #[test]
+    fn test_shared_view_hides_owner() {
+        let owner_id = ObjectId::new();
+        let trip_id = ObjectId::new();
+        let trip: Trip = serde_json::from_value(serde_json::json!({
+            "_id": {"$oid": trip_id.to_hex()},
+            "name": "Seoul",
+            "start_date": "2021-03-01T00:00:00+09:00",
+            "end_date": "2021-03-02T00:00:00+09:00",
+            "timezone": "Asia/Seoul",
+            "budget": {"amount": 0, "currency": "USD"},
+            "destination": "Seoul, Korea, Republic of",
+            "events": [{
+                "_id": {"$oid": trip_id.to_hex()},
+                "event_id": {"$oid": ObjectId::new().to_hex()},
+                "start_date": "2021-03-01T01:00:00.000Z",
+                "start_hour": "10:00",
+                "budget": {"amount": 1000, "currency": "USD"},
+                "duration": 60,
+            }],
+            "private": true,
+            "collaborators": [{
+                "user_id": {"$oid": ObjectId::new().to_hex()},
+                "role": "editor",
+                "status": "accepted",
+                "invited_at": "2021-03-01T00:00:00+00:00",
+            }],
+            "user_id": {"$oid": owner_id.to_hex()},
+        })).expect("Error parsing trip");
+
+        let value = serde_json::to_value(trip.shared_view()).unwrap();
+
+        assert_eq!("Seoul", value["name"]);
+        assert!(value.get("user_id").is_none());
+        assert!(value.get("collaborators").is_none());
+        assert!(value.get("private").is_none());
+        assert_eq!("10:00", value["events"][0]["start_hour"]);
+        assert!(value["events"][0].get("_id").is_none());
+        assert!(!value.to_string().contains(owner_id.to_hex().as_str()));
+        assert!(!value.to_string().contains(trip_id.to_hex().as_str()));
+    }
+}
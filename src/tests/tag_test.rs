@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod test {
+    use crate::MongoDb;
+    use crate::models::tag::{Tag, TagCreate, TagKind, Taxonomy};
+
+    use mongodb::{Client, options::ClientOptions};
+    use mongodb::options::ResolverConfig;
+    use bson::oid::ObjectId;
+    use std::collections::HashMap;
+
+    async fn get_mongo_db() -> MongoDb {
+        dotenv::dotenv().ok();
+
+        let mut mongo_options = ClientOptions::parse_with_resolver_config(
+            std::env::var("MONGO_URL").expect("Error in Mongo URL").as_str(),
+            ResolverConfig::cloudflare()
+        ).await.expect("Error found while creating client options");
+        mongo_options.app_name = Some("YeoHengServer".to_string());
+        let mongo_client = Client::with_options(mongo_options).expect("Error found while creating mongo client");
+        mongo_client.database(std::env::var("TEST_DATABASE_NAME")
+            .expect("Error retrieving database name")
+            .as_str())
+    }
+
+    fn taxonomy() -> Taxonomy {
+        let mut translations = HashMap::new();
+        translations.insert("es".to_string(), "Playa".to_string());
+
+        Taxonomy {
+            tags: vec![
+                Tag {
+                    _id: ObjectId::new(),
+                    name: "beach".to_string(),
+                    kind: TagKind::Tag,
+                    synonyms: vec!["seaside".to_string()],
+                    translations,
+                    updated_at: "2020-10-01T00:00:00Z".to_string(),
+                },
+                Tag {
+                    _id: ObjectId::new(),
+                    name: "adventurer".to_string(),
+                    kind: TagKind::PersonalType,
+                    synonyms: Vec::new(),
+                    translations: HashMap::new(),
+                    updated_at: "2020-10-01T00:00:00Z".to_string(),
+                },
+            ]
+        }
+    }
+
+    #[test]
+    fn test_canonical_terms() {
+        let taxonomy = taxonomy();
+
+        assert_eq!(Ok("beach".to_string()), taxonomy.canonical("Beach", TagKind::Tag));
+        assert_eq!(Ok("beach".to_string()), taxonomy.canonical("playa", TagKind::Tag));
+        assert_eq!(Ok("beach".to_string()), taxonomy.canonical(" SEASIDE ", TagKind::Tag));
+        assert_eq!(Err("Unknown tag: museum".to_string()), taxonomy.canonical("museum", TagKind::Tag));
+        // Terms only match tags of the same kind
+        assert!(taxonomy.canonical("beach", TagKind::PersonalType).is_err());
+    }
+
+    #[test]
+    fn test_empty_taxonomy_accepts_terms() {
+        let taxonomy = Taxonomy { tags: Vec::new() };
+        let tags = vec!["Museum".to_string(), "museum".to_string()];
+
+        assert_eq!(Ok(vec!["museum".to_string()]), taxonomy.canonical_tags(&tags));
+        assert_eq!(Ok("foodie".to_string()), taxonomy.canonical(" Foodie", TagKind::PersonalType));
+
+        // Only strict for kinds that have terms
+        let mut taxonomy = self::taxonomy();
+        taxonomy.tags.retain(|tag| tag.kind == TagKind::Tag);
+        assert!(taxonomy.canonical("museum", TagKind::Tag).is_err());
+        assert_eq!(Ok("foodie".to_string()), taxonomy.canonical("Foodie", TagKind::PersonalType));
+    }
+
+    #[test]
+    fn test_translations_cannot_be_shared() {
+        let taxonomy = taxonomy();
+        let beach_id = taxonomy.tags[0]._id.clone();
+        let playa = ["playa".to_string()];
+
+        assert_eq!(Err("Term playa is already used by tag beach".to_string()),
+                   taxonomy.check_free(playa.iter(), TagKind::Tag, None));
+        assert_eq!(Err("Term playa is already used by tag beach".to_string()),
+                   taxonomy.check_free(playa.iter(), TagKind::Tag, Some(&ObjectId::new())));
+        // A tag can keep its own terms and other kinds are not affected
+        assert!(taxonomy.check_free(playa.iter(), TagKind::Tag, Some(&beach_id)).is_ok());
+        assert!(taxonomy.check_free(playa.iter(), TagKind::PersonalType, None).is_ok());
+    }
+
+    #[test]
+    fn test_canonical_tags_are_deduplicated() {
+        let taxonomy = taxonomy();
+        let tags = vec!["Beach".to_string(), "playa".to_string(), "seaside".to_string()];
+
+        assert_eq!(Ok(vec!["beach".to_string()]), taxonomy.canonical_tags(&tags));
+        assert_eq!("museum", taxonomy.canonical_or_term("Museum", TagKind::Tag));
+    }
+
+    #[actix_rt::test]
+    async fn test_create_tag_requires_admin() {
+        let mongo_db = get_mongo_db().await;
+
+        let tag = TagCreate {
+            name: String::from("Beach"),
+            kind: TagKind::Tag,
+            synonyms: Vec::new(),
+            translations: HashMap::new(),
+        };
+
+        let response = Tag::create(tag, ObjectId::new().to_hex(), &mongo_db).await
+            .expect_err("Error: tag created by unknown user");
+
+        assert_eq!("User not found", response);
+    }
+}
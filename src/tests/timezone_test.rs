@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod test {
+    use crate::utils::timezone::{parse_timezone, localize, local_date, parse_rfc3339};
+
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use chrono_tz::Tz;
+
+    #[test]
+    fn test_parse_timezone() {
+        assert_eq!(Ok(Tz::Asia__Seoul), parse_timezone("Asia/Seoul"));
+        assert_eq!(Err("Unknown timezone: Mars/Olympus".to_string()), parse_timezone("Mars/Olympus"));
+    }
+
+    #[test]
+    fn test_localize_across_dst() {
+        let tz = Tz::America__New_York;
+
+        let winter = localize(NaiveDate::from_ymd(2021, 3, 13).and_hms(10, 0, 0), tz);
+        let summer = localize(NaiveDate::from_ymd(2021, 3, 14).and_hms(10, 0, 0), tz);
+        assert_eq!(Utc.ymd(2021, 3, 13).and_hms(15, 0, 0), winter);
+        assert_eq!(Utc.ymd(2021, 3, 14).and_hms(14, 0, 0), summer);
+
+        // 02:30 does not exist that night, it becomes 03:30
+        let skipped = localize(NaiveDate::from_ymd(2021, 3, 14).and_hms(2, 30, 0), tz);
+        assert_eq!(Utc.ymd(2021, 3, 14).and_hms(7, 30, 0), skipped);
+
+        // 01:30 happens twice in November, the first one is used
+        let repeated = localize(NaiveDate::from_ymd(2021, 11, 7).and_hms(1, 30, 0), tz);
+        assert_eq!(Utc.ymd(2021, 11, 7).and_hms(5, 30, 0), repeated);
+    }
+
+    #[test]
+    fn test_local_date() {
+        let date = parse_rfc3339("2021-03-01T00:00:00+09:00").expect("Error parsing date");
+
+        assert_eq!(NaiveDate::from_ymd(2021, 3, 1), local_date(&date, Tz::Asia__Seoul));
+        assert_eq!(NaiveDate::from_ymd(2021, 2, 28), local_date(&date, Tz::UTC));
+        assert!(parse_rfc3339("Start").is_err());
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod test {
+    use crate::MongoDb;
+    use crate::models::trash::{get_trash, restore};
+    use crate::models::report::ContentType;
+    use crate::models::trip::Trip;
+
+    use mongodb::{Client, options::ClientOptions};
+    use mongodb::options::ResolverConfig;
+    use bson::oid::ObjectId;
+
+    async fn get_mongo_db() -> MongoDb {
+        dotenv::dotenv().ok();
+
+        let mut mongo_options = ClientOptions::parse_with_resolver_config(
+            std::env::var("MONGO_URL").expect("Error in Mongo URL").as_str(),
+            ResolverConfig::cloudflare()
+        ).await.expect("Error found while creating client options");
+        mongo_options.app_name = Some("YeoHengServer".to_string());
+        let mongo_client = Client::with_options(mongo_options).expect("Error found while creating mongo client");
+        mongo_client.database(std::env::var("TEST_DATABASE_NAME")
+            .expect("Error retrieving database name")
+            .as_str())
+    }
+
+    #[actix_rt::test]
+    async fn test_delete_trip_not_owned() {
+        let mongo_db = get_mongo_db().await;
+
+        let response = Trip::delete_trip(ObjectId::new().to_hex(), ObjectId::new().to_hex(), None, &mongo_db).await
+            .expect_err("Error: deleted a trip the user does not own");
+
+        assert_eq!("Trip not found", response);
+    }
+
+    #[actix_rt::test]
+    async fn test_restore_not_deleted() {
+        let mongo_db = get_mongo_db().await;
+
+        let response = restore(ContentType::Event, ObjectId::new().to_hex(), ObjectId::new().to_hex(), &mongo_db).await
+            .expect_err("Error: restored content that is not in the trash");
+
+        assert_eq!("Event not found", response);
+    }
+
+    #[actix_rt::test]
+    async fn test_empty_trash() {
+        let mongo_db = get_mongo_db().await;
+
+        let response = get_trash(ObjectId::new().to_hex(), &mongo_db).await
+            .expect("Error: test get trash");
+
+        assert!(response.is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+#[cfg(test)]
+mod test {
+    use crate::models::trip_hub::{HISTORY_SIZE, TripChange, TripHub};
+    use crate::models::trip::EventEntry;
+
+    use bson::oid::ObjectId;
+    use futures::channel::mpsc::UnboundedReceiver;
+
+    fn get_entry(trip_id: &ObjectId) -> EventEntry {
+        serde_json::from_value(serde_json::json!({
+            "_id": {"$oid": trip_id.to_hex()},
+            "event_id": {"$oid": ObjectId::new().to_hex()},
+            "start_date": "2021-03-01T00:00:00+09:00",
+            "start_hour": "10:00",
+            "budget": {"amount": 1000, "currency": "USD"},
+            "duration": 60,
+        })).expect("Error parsing entry")
+    }
+
+    fn received(receiver: &mut UnboundedReceiver<String>) -> Vec<serde_json::Value> {
+        let mut messages = Vec::new();
+        while let Ok(Some(text)) = receiver.try_next() {
+            messages.push(serde_json::from_str(text.as_str()).expect("Error parsing message"));
+        }
+
+        messages
+    }
+
+    #[test]
+    fn test_broadcast_to_members() {
+        let hub = TripHub::starting_at(100);
+        let trip_id = ObjectId::new();
+        let mut first = hub.subscribe(&trip_id, "first", None);
+        let mut second = hub.subscribe(&trip_id, "second", None);
+        received(&mut first.receiver);
+        received(&mut second.receiver);
+
+        let seq = hub.publish(&trip_id, "first", TripChange::EntryAdded { entry: get_entry(&trip_id) });
+
+        assert_eq!(101, seq);
+        for subscription in [&mut first, &mut second] {
+            let messages = received(&mut subscription.receiver);
+            assert_eq!(1, messages.len());
+            assert_eq!("entry_added", messages[0]["type"]);
+            assert_eq!(101, messages[0]["seq"]);
+            assert_eq!("first", messages[0]["user_id"]);
+            assert_eq!("10:00", messages[0]["entry"]["start_hour"]);
+        }
+    }
+
+    #[test]
+    fn test_other_trips_not_told() {
+        let hub = TripHub::starting_at(0);
+        let trip_id = ObjectId::new();
+        let mut subscription = hub.subscribe(&trip_id, "member", None);
+        received(&mut subscription.receiver);
+
+        let other_id = ObjectId::new();
+        hub.publish(&other_id, "member", TripChange::EntryRemoved { entry: get_entry(&other_id) });
+
+        assert!(received(&mut subscription.receiver).is_empty());
+        // Seqs are shared by every trip
+        assert_eq!(1, hub.seq());
+    }
+
+    #[test]
+    fn test_presence() {
+        let hub = TripHub::starting_at(0);
+        let trip_id = ObjectId::new();
+        let mut first = hub.subscribe(&trip_id, "first", None);
+
+        let messages = received(&mut first.receiver);
+        assert_eq!("presence", messages[0]["type"]);
+        assert_eq!(serde_json::json!(["first"]), messages[0]["members"]);
+
+        let second = hub.subscribe(&trip_id, "second", None);
+        let again = hub.subscribe(&trip_id, "second", None);
+        let messages = received(&mut first.receiver);
+        assert_eq!(serde_json::json!(["first", "second"]), messages.last().unwrap()["members"]);
+
+        // Still connected from another tab
+        hub.unsubscribe(&trip_id, second.connection_id);
+        let messages = received(&mut first.receiver);
+        assert_eq!(serde_json::json!(["first", "second"]), messages.last().unwrap()["members"]);
+        hub.unsubscribe(&trip_id, again.connection_id);
+        let messages = received(&mut first.receiver);
+        assert_eq!(serde_json::json!(["first"]), messages.last().unwrap()["members"]);
+    }
+
+    #[test]
+    fn test_disconnect_removed_member() {
+        let hub = TripHub::starting_at(0);
+        let trip_id = ObjectId::new();
+        let mut owner = hub.subscribe(&trip_id, "owner", None);
+        let mut removed = hub.subscribe(&trip_id, "removed", None);
+        received(&mut removed.receiver);
+
+        hub.disconnect(&trip_id, "removed");
+        hub.publish(&trip_id, "owner", TripChange::EntryAdded { entry: get_entry(&trip_id) });
+
+        // The stream ends without the change
+        assert_eq!(Ok(None), removed.receiver.try_next().map_err(|_| ()));
+        let messages = received(&mut owner.receiver);
+        assert_eq!(serde_json::json!(["owner"]), messages[messages.len() - 2]["members"]);
+        assert_eq!("entry_added", messages.last().unwrap()["type"]);
+    }
+
+    #[test]
+    fn test_catch_up_after_reconnect() {
+        let hub = TripHub::starting_at(0);
+        let trip_id = ObjectId::new();
+        let _staying = hub.subscribe(&trip_id, "staying", None);
+        let leaving = hub.subscribe(&trip_id, "leaving", None);
+        let since = hub.seq();
+        hub.unsubscribe(&trip_id, leaving.connection_id);
+
+        hub.publish(&trip_id, "staying", TripChange::EntryAdded { entry: get_entry(&trip_id) });
+        hub.publish(&trip_id, "staying", TripChange::EntryRemoved { entry: get_entry(&trip_id) });
+
+        let mut back = hub.subscribe(&trip_id, "leaving", Some(since));
+        let messages = received(&mut back.receiver);
+        assert_eq!(3, messages.len());
+        assert_eq!("entry_added", messages[0]["type"]);
+        assert_eq!(1, messages[0]["seq"]);
+        assert_eq!("entry_removed", messages[1]["type"]);
+        assert_eq!(2, messages[1]["seq"]);
+        assert_eq!("presence", messages[2]["type"]);
+    }
+
+    #[test]
+    fn test_resync_when_history_is_gone() {
+        let hub = TripHub::starting_at(0);
+        let trip_id = ObjectId::new();
+        let subscription = hub.subscribe(&trip_id, "member", None);
+        for _ in 0..HISTORY_SIZE + 1 {
+            hub.publish(&trip_id, "member", TripChange::EntryAdded { entry: get_entry(&trip_id) });
+        }
+
+        let mut behind = hub.subscribe(&trip_id, "behind", Some(0));
+        let messages = received(&mut behind.receiver);
+        assert_eq!("resync", messages[0]["type"]);
+        let mut recent = hub.subscribe(&trip_id, "recent", Some(HISTORY_SIZE as u64));
+        let messages = received(&mut recent.receiver);
+        assert_eq!(HISTORY_SIZE as u64 + 1, messages[0]["seq"]);
+        assert_eq!("presence", messages[1]["type"]);
+
+        // Changes made while nobody was connected are not kept
+        for connection_id in [subscription.connection_id, behind.connection_id, recent.connection_id] {
+            hub.unsubscribe(&trip_id, connection_id);
+        }
+        let since = hub.seq();
+        hub.publish(&trip_id, "member", TripChange::EntryAdded { entry: get_entry(&trip_id) });
+        let mut back = hub.subscribe(&trip_id, "member", Some(since));
+        let messages = received(&mut back.receiver);
+        assert_eq!("resync", messages[0]["type"]);
+        assert_eq!(since + 1, messages[0]["seq"]);
+    }
+}
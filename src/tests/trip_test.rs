@@ -2,11 +2,17 @@
 mod test {
     use super::*;
     use crate::MongoDb;
-    use crate::models::trip::{TripCreate, TripFilter, Trip};
+    use crate::models::trip::{TripCreate, TripEdit, TripFilter, TripFork, Trip, EventEntry, ExpandQuery, document_visible_to, get_find_filter, validate_dates};
+    use crate::models::currency::Money;
+    use crate::models::place::load_places;
+    use crate::utils::timezone::parse_rfc3339;
 
     use mongodb::{Client, options::ClientOptions};
     use mongodb::options::ResolverConfig;
     use bson::oid::ObjectId;
+    use mongodb::bson::{doc, Bson};
+    use chrono::{TimeZone, Utc};
+    use chrono_tz::Tz;
 
     fn type_of<T>(_: &T) -> &str { std::any::type_name::<T>() }
 
@@ -27,18 +33,21 @@ mod test {
     #[actix_rt::test]
     async fn test_create_trip() {
         let mongo_db = get_mongo_db().await;
+        load_places(&mongo_db).await.expect("Error loading places");
 
         let event = TripCreate {
             name: String::from("Test"),
-            start_date: String::from("Start"),
-            end_date: String::from("End"),
-            budget: 150.0,
-            destination: String::from("Destination"),
+            start_date: parse_rfc3339("2021-03-01T00:00:00+09:00").unwrap(),
+            end_date: parse_rfc3339("2021-03-03T00:00:00+09:00").unwrap(),
+            timezone: None,
+            budget: Money::new(15000, "USD"),
+            budget_limit: None,
+            destination: String::from("Seoul"),
             private: false,
             user_id: ObjectId::new(),
         };
 
-        let response = Trip::create(event, &mongo_db).await;
+        let response = Trip::create(event, &mongo_db).await.expect("Error creating trip");
 
         assert_eq!(type_of(&ObjectId::new()), type_of(&response));
     }
@@ -48,16 +57,20 @@ mod test {
         let mongo_db = get_mongo_db().await;
 
         let filter = TripFilter {
-            offset: 0,
-            limit: 5,
+            cursor: None,
+            limit: Some(5),
             budget_gt: None,
             budget_lt: None,
+            currency: None,
             user_id: None,
+            forked_from: None,
+            requester_id: None,
         };
 
-        let response = Trip::get_filtered_trips(filter, &mongo_db).await;
+        let response = Trip::get_filtered_trips(filter, &mongo_db)
+            .await.expect("Error getting trips");
 
-        assert_eq!("Test", response[0].name);
+        assert_eq!("Test", response.items[0].name);
     }
 
     #[actix_rt::test]
@@ -65,16 +78,276 @@ mod test {
         let mongo_db = get_mongo_db().await;
 
         let filter = TripFilter {
-            offset: 0,
-            limit: 1,
+            cursor: None,
+            limit: Some(1),
             budget_gt: None,
             budget_lt: None,
+            currency: None,
             user_id: None,
+            forked_from: None,
+            requester_id: None,
         };
 
+        let page = Trip::get_filtered_trips(TripFilter {
+            limit: Some(1),
+            ..Default::default()
+        }, &mongo_db).await.expect("Error getting trips");
+
         let response = Trip::count_filtered_trips(filter, &mongo_db)
             .await.expect("Error counting document");
 
-        assert_eq!(1, response);
+        // The limit only applies to the page, never to the count
+        assert_eq!(page.total, response);
+    }
+
+    #[actix_rt::test]
+    async fn test_get_trip_next_page() {
+        let mongo_db = get_mongo_db().await;
+
+        let first_page = Trip::get_filtered_trips(TripFilter {
+            limit: Some(1),
+            ..Default::default()
+        }, &mongo_db).await.expect("Error getting trips");
+
+        match first_page.next_cursor {
+            Some(cursor) => {
+                let second_page = Trip::get_filtered_trips(TripFilter {
+                    cursor: Some(cursor),
+                    limit: Some(1),
+                    ..Default::default()
+                }, &mongo_db).await.expect("Error getting trips");
+
+                assert_eq!(first_page.total, second_page.total);
+                assert_ne!(serde_json::to_value(&first_page.items[0]).unwrap(),
+                           serde_json::to_value(&second_page.items[0]).unwrap());
+            },
+            None => assert!(first_page.total <= 1),
+        }
+    }
+
+    #[test]
+    fn test_new_entry_is_not_orphaned() {
+        let event_entry: EventEntry = serde_json::from_value(serde_json::json!({
+            "_id": {"$oid": ObjectId::new().to_hex()},
+            "event_id": {"$oid": ObjectId::new().to_hex()},
+            "start_date": "2021-03-01T00:00:00.000Z",
+            "start_hour": "10:00",
+            "budget": {"amount": 1000, "currency": "USD"},
+            "duration": 60,
+        })).expect("Error parsing entry");
+
+        let entry_json = serde_json::to_value(&event_entry).expect("Error serializing entry");
+
+        assert_eq!(Some(&serde_json::Value::Bool(false)), entry_json.get("orphaned"));
+        assert!(entry_json.get("snapshot").is_none());
+        assert!(!event_entry.to_doc().contains_key("orphaned"));
+    }
+
+    #[actix_rt::test]
+    async fn test_check_entries() {
+        let mongo_db = get_mongo_db().await;
+
+        let response = Trip::check_entries(false, &mongo_db).await.expect("Error: test check entries");
+
+        assert!(response.starts_with("Checked"));
+    }
+
+    #[test]
+    fn test_expand_query() {
+        let expand = |value: &str| ExpandQuery { expand: Some(value.to_string()) }.events();
+
+        assert_eq!(Ok(false), ExpandQuery::default().events());
+        assert_eq!(Ok(true), expand("events"));
+        assert_eq!(Err("Unknown expand value: user".to_string()), expand("events,user"));
+    }
+
+    #[test]
+    fn test_expanded_event_visibility() {
+        let owner_id = ObjectId::new();
+        let public_event = doc! {"user_id": owner_id.clone(), "private": false, "moderation_status": "visible"};
+        let private_event = doc! {"user_id": owner_id.clone(), "private": true, "moderation_status": "visible"};
+        let deleted_event = doc! {"user_id": owner_id.clone(), "private": false, "deleted_at": "2021-03-01T00:00:00+00:00"};
+        let viewer_id = ObjectId::new().to_hex();
+
+        assert!(document_visible_to(&public_event, None));
+        assert!(!document_visible_to(&private_event, Some(&viewer_id)));
+        assert!(document_visible_to(&private_event, Some(&owner_id.to_hex())));
+        assert!(!document_visible_to(&deleted_event, Some(&owner_id.to_hex())));
+        assert!(document_visible_to(&doc! {"user_id": owner_id, "deleted_at": Bson::Null}, None));
+    }
+
+    #[test]
+    fn test_validate_dates() {
+        let start_date = parse_rfc3339("2021-03-01T00:00:00+09:00").unwrap();
+        let end_date = parse_rfc3339("2021-03-03T00:00:00+09:00").unwrap();
+
+        assert_eq!(Ok(()), validate_dates(&start_date, &end_date));
+        assert_eq!(Ok(()), validate_dates(&start_date, &start_date));
+        assert_eq!(Err("start_date must not be after end_date".to_string()), validate_dates(&end_date, &start_date));
+    }
+
+    #[test]
+    fn test_trip_dates_from_bson() {
+        let trip_doc = doc! {
+            "_id": ObjectId::new(),
+            "name": "Seoul",
+            "start_date": Utc.ymd(2021, 2, 28).and_hms(15, 0, 0),
+            "end_date": "2021-03-02T00:00:00+09:00",
+            "timezone": "Asia/Seoul",
+            "budget": {"amount": 0, "currency": "USD"},
+            "destination": "Seoul, Korea, Republic of",
+            "events": [],
+            "private": false,
+            "user_id": ObjectId::new(),
+        };
+
+        let trip = bson::from_bson::<Trip>(Bson::Document(trip_doc)).expect("Error parsing trip");
+
+        assert_eq!(Tz::Asia__Seoul, trip.tz());
+        assert_eq!("2021-03-01", trip.first_day().format("%Y-%m-%d").to_string());
+        assert_eq!("2021-03-02", trip.last_day().format("%Y-%m-%d").to_string());
+    }
+
+    #[test]
+    fn test_parse_edit_with_null_dates() {
+        let trip_edit = serde_json::from_value::<TripEdit>(serde_json::json!({
+            "_id": ObjectId::new().to_hex(),
+            "start_date": null,
+            "end_date": null,
+        }));
+        assert!(trip_edit.is_ok());
+
+        let trip_edit = serde_json::from_value::<TripEdit>(serde_json::json!({
+            "_id": ObjectId::new().to_hex(),
+            "start_date": 1,
+        }));
+        assert!(trip_edit.is_err());
+    }
+
+    #[test]
+    fn test_shift_entry_across_dst() {
+        let event_entry: EventEntry = serde_json::from_value(serde_json::json!({
+            "_id": {"$oid": ObjectId::new().to_hex()},
+            "event_id": {"$oid": ObjectId::new().to_hex()},
+            "start_date": "2021-03-13T00:00:00-05:00",
+            "start_hour": "10:00",
+            "budget": {"amount": 1000, "currency": "USD"},
+            "duration": 60,
+        })).expect("Error parsing entry");
+
+        // New York moves to daylight saving time on 2021-03-14
+        let fork_id = ObjectId::new();
+        let shifted = event_entry.to_doc_shifted(fork_id.clone(), 1, Tz::America__New_York);
+
+        assert_eq!(Ok(&fork_id), shifted.get_object_id("_id"));
+
+        assert_eq!(Some(&Bson::DateTime(Utc.ymd(2021, 3, 14).and_hms(14, 0, 0))), shifted.get("start_date"));
+        assert_eq!(Ok("10:00"), shifted.get_str("start_hour"));
+    }
+
+    #[test]
+    fn test_budget_filter_uses_base_amount() {
+        let filter = get_find_filter(TripFilter {
+            budget_gt: Some(1000),
+            budget_lt: Some(5000),
+            ..TripFilter::default()
+        });
+
+        assert_eq!(Ok(&doc! {"$gte": 1000i64, "$lte": 5000i64}), filter.get_document("budget_base"));
+        assert!(!filter.contains_key("budget.amount"));
+    }
+
+    #[test]
+    fn test_move_trip_to_timezone() {
+        let trip_doc = doc! {
+            "_id": ObjectId::new(),
+            "name": "Seoul",
+            "start_date": "2021-03-01T00:00:00+09:00",
+            "end_date": "2021-03-02T00:00:00+09:00",
+            "timezone": "Asia/Seoul",
+            "budget": {"amount": 0, "currency": "USD"},
+            "destination": "Seoul, Korea, Republic of",
+            "events": [{
+                "_id": ObjectId::new(),
+                "event_id": ObjectId::new(),
+                "start_date": Utc.ymd(2021, 2, 28).and_hms(23, 0, 0),
+                "start_hour": "08:00",
+                "budget": {"amount": 1000, "currency": "USD"},
+                "duration": 60,
+            }],
+            "private": false,
+            "user_id": ObjectId::new(),
+        };
+        let trip = bson::from_bson::<Trip>(Bson::Document(trip_doc)).expect("Error parsing trip");
+
+        let moved = trip.moved_to_timezone(Tz::UTC);
+
+        // 08:00 on March 1st in Seoul stays 08:00 on March 1st
+        assert_eq!(Some(&Bson::DateTime(Utc.ymd(2021, 3, 1).and_hms(8, 0, 0))), moved.get("events.0.start_date"));
+        assert_eq!(Ok("08:00"), moved.get_str("events.0.start_hour"));
+        assert_eq!(Some(&Bson::DateTime(Utc.ymd(2021, 3, 1).and_hms(0, 0, 0))), moved.get("start_date"));
+        assert_eq!(Some(&Bson::DateTime(Utc.ymd(2021, 3, 2).and_hms(0, 0, 0))), moved.get("end_date"));
+    }
+
+    #[test]
+    fn test_fork_privacy_is_chosen_by_caller() {
+        let fork = |private: serde_json::Value| {
+            let mut value = serde_json::json!({
+                "name": "Seoul again",
+                "start_date": "2021-04-01T00:00:00+09:00",
+                "to_fork_trip_id": ObjectId::new().to_hex(),
+            });
+            if !private.is_null() {
+                value["private"] = private;
+            }
+            serde_json::from_value::<TripFork>(value).expect("Error parsing fork")
+        };
+
+        assert!(!fork(serde_json::Value::Null).is_private());
+        assert!(fork(serde_json::Value::Bool(true)).is_private());
+        assert!(!fork(serde_json::Value::Bool(false)).is_private());
+    }
+
+    #[test]
+    fn test_fork_lineage_from_bson() {
+        let source_id = ObjectId::new();
+        let trip_doc = doc! {
+            "_id": ObjectId::new(),
+            "name": "Seoul",
+            "start_date": Utc.ymd(2021, 2, 28).and_hms(15, 0, 0),
+            "end_date": Utc.ymd(2021, 3, 1).and_hms(15, 0, 0),
+            "budget": {"amount": 0, "currency": "USD"},
+            "destination": "Seoul, Korea, Republic of",
+            "events": [],
+            "private": false,
+            "forked_from": source_id.clone(),
+            "fork_root": source_id.clone(),
+            "user_id": ObjectId::new(),
+        };
+
+        let trip = bson::from_bson::<Trip>(Bson::Document(trip_doc)).expect("Error parsing trip");
+
+        assert_eq!(Some(source_id.clone()), trip.forked_from);
+        assert_eq!(Some(source_id), trip.fork_root);
+        assert_eq!(0, trip.fork_count);
+        assert_eq!(0, trip.version);
+        assert!(trip.attribution.is_none());
+    }
+
+    #[test]
+    fn test_fork_source_visibility() {
+        let owner_id = ObjectId::new();
+        let public_trip = doc! {"user_id": owner_id.clone(), "private": false, "moderation_status": "visible"};
+        let private_trip = doc! {"user_id": owner_id.clone(), "private": true};
+        let hidden_trip = doc! {"user_id": owner_id.clone(), "private": false, "moderation_status": "hidden"};
+        let deleted_trip = doc! {"user_id": owner_id.clone(), "private": false, "deleted_at": Utc::now()};
+        let viewer_id = ObjectId::new().to_hex();
+
+        assert!(document_visible_to(&public_trip, None));
+        assert!(!document_visible_to(&private_trip, Some(&viewer_id)));
+        assert!(document_visible_to(&private_trip, Some(&owner_id.to_hex())));
+        assert!(!document_visible_to(&hidden_trip, None));
+        assert!(document_visible_to(&hidden_trip, Some(&owner_id.to_hex())));
+        assert!(!document_visible_to(&deleted_trip, Some(&owner_id.to_hex())));
     }
-}
\ No newline at end of file
+}
@@ -38,6 +38,7 @@ mod test {
             password: String::from("test"),
             role: None,
             email: String::from("test@test.com"),
+            preferred_currency: None,
         };
 
         let response = User::insert(user, &mongo_db).await;
@@ -56,6 +57,7 @@ mod test {
             password: String::from("test"),
             role: None,
             email: String::from("test@test.com"),
+            preferred_currency: None,
         };
 
         let response = User::get_all_like_user("te".to_string(),
@@ -71,10 +73,12 @@ mod test {
 
         let response = User::promote_user("5fb746a300fdc2fc00d86b68".to_string(),
                                           "5fb7437c00058e520064685f".to_string(),
+                                          None,
                                           &mongo_db).await.expect("Error: test promote user");
 
         let response = User::demote_user("5fb746a300fdc2fc00d86b68".to_string(),
                                           "5fb7437c00058e520064685f".to_string(),
+                                          None,
                                           &mongo_db).await.expect("Error: test promote user");
 
         assert_eq!("Successfully demoted user role".to_string(), response);
@@ -91,6 +95,7 @@ mod test {
             password: String::from("test"),
             role: None,
             email: String::from("test@test.com"),
+            preferred_currency: None,
         };
 
         let response = User::validate(user, &mongo_db)
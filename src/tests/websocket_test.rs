@@ -0,0 +1,13 @@
+#[cfg(test)]
+mod test {
+    use crate::utils::websocket::parse_protocol_token;
+
+    #[test]
+    fn test_parse_protocol_token() {
+        assert_eq!(Some("abc.def".to_string()), parse_protocol_token("access_token, abc.def"));
+        assert_eq!(Some("abc.def".to_string()), parse_protocol_token("access_token,abc.def"));
+        assert_eq!(None, parse_protocol_token("access_token"));
+        assert_eq!(None, parse_protocol_token("access_token, "));
+        assert_eq!(None, parse_protocol_token("chat, abc.def"));
+    }
+}
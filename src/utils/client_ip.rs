@@ -0,0 +1,41 @@
+use actix_web::HttpRequest;
+use std::net::IpAddr;
+
+// The connecting address. Behind proxies listed in TRUSTED_PROXIES (comma separated),
+// X-Forwarded-For is read from the right up to the first address they did not add
+pub fn client_ip(req: &HttpRequest) -> Option<String> {
+    let forwarded_for = req.headers().get("X-Forwarded-For")
+        .and_then(|header| header.to_str().ok());
+
+    resolve_client_ip(req.peer_addr().map(|addr| addr.ip()), forwarded_for, &trusted_proxies())
+        .map(|ip| ip.to_string())
+}
+
+// Anything left of the first untrusted address may have been sent by the client
+pub fn resolve_client_ip(peer: Option<IpAddr>, forwarded_for: Option<&str>, trusted: &[IpAddr]) -> Option<IpAddr> {
+    let mut client = peer?;
+    if !trusted.contains(&client) {
+        return Some(client);
+    }
+
+    for addr in forwarded_for.unwrap_or("").rsplit(',') {
+        match addr.trim().parse::<IpAddr>() {
+            Ok(addr) => {
+                client = addr;
+                if !trusted.contains(&addr) {
+                    break;
+                }
+            },
+            Err(_) => break,
+        }
+    }
+
+    Some(client)
+}
+
+fn trusted_proxies() -> Vec<IpAddr> {
+    std::env::var("TRUSTED_PROXIES").unwrap_or_default()
+        .split(',')
+        .filter_map(|addr| addr.trim().parse::<IpAddr>().ok())
+        .collect()
+}
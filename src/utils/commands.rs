@@ -0,0 +1,30 @@
+use crate::MongoDb;
+use crate::models::currency::migrate_legacy_amounts;
+use crate::models::trip::Trip;
+use crate::models::tag::migrate_tags;
+use crate::models::place::{load_places, migrate_places};
+use crate::models::trash::purge_expired;
+
+// Maintenance commands, run as `yeoheng-server <command>` instead of starting the server
+pub async fn run(command: &str, db: &MongoDb) -> std::io::Result<()> {
+    let result = match command {
+        "migrate-currency" => migrate_legacy_amounts(db).await,
+        "repair-budgets" => Trip::repair_budgets(db).await,
+        "migrate-tags" => migrate_tags(db).await,
+        "load-places" => load_places(db).await,
+        "migrate-places" => migrate_places(db).await,
+        "purge-trash" => purge_expired(db).await,
+        "check-entries" => Trip::check_entries(false, db).await,
+        "repair-entries" => Trip::check_entries(true, db).await,
+        "migrate-dates" => Trip::migrate_dates(db).await,
+        _ => Err(format!("Unknown command: {}", command)),
+    };
+
+    match result {
+        Ok(msg) => {
+            println!("{}", msg);
+            Ok(())
+        },
+        Err(e) => Err(std::io::Error::other(e)),
+    }
+}
@@ -1,4 +1,7 @@
+use crate::models::opening_hours::parse_duration;
+
 use serde::de::{self, Visitor};
+use std::convert::TryFrom;
 use std::fmt;
 use bson::oid::ObjectId;
 
@@ -27,4 +30,83 @@ impl<'de> Visitor<'de> for ObjectIdVisitor {
     {
         Ok(ObjectId::default())
     }
-}
\ No newline at end of file
+}
+
+pub struct DurationVisitor;
+
+// Durations are minutes, legacy free text durations are parsed when possible
+impl<'de> Visitor<'de> for DurationVisitor {
+    type Value = i32;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("Expecting a duration in minutes")
+    }
+
+    fn visit_i32<E>(self, v: i32) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+    {
+        Ok(v)
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+    {
+        i32::try_from(v).map_err(|_| E::custom("duration out of range"))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+    {
+        i32::try_from(v).map_err(|_| E::custom("duration out of range"))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+    {
+        Ok(v.round() as i32)
+    }
+
+    // Unreadable legacy values become 0 so they fail validation instead of hiding the event
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+    {
+        Ok(parse_duration(v).unwrap_or(0))
+    }
+}
+
+pub struct OptionalDurationVisitor;
+
+// A missing or null duration is left unset
+impl<'de> Visitor<'de> for OptionalDurationVisitor {
+    type Value = Option<i32>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("Expecting a duration in minutes or null")
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(DurationVisitor).map(Some)
+    }
+}
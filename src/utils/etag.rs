@@ -0,0 +1,47 @@
+use actix_web::{HttpRequest, HttpResponse};
+use mongodb::bson::{doc, Bson};
+
+pub const VERSION_MISMATCH: &str = "Version mismatch, the content was changed since it was fetched";
+
+pub fn etag(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+// Version the client expects from If-Match, None when the header is missing or "*"
+pub fn if_match(req: &HttpRequest) -> Result<Option<i64>, String> {
+    let header = match req.headers().get("If-Match") {
+        Some(header) => header.to_str().map_err(|_| "Invalid If-Match header".to_string())?,
+        None => return Ok(None),
+    };
+
+    parse_if_match(header)
+}
+
+pub fn parse_if_match(header: &str) -> Result<Option<i64>, String> {
+    let header = header.trim();
+    if header == "*" {
+        return Ok(None);
+    }
+
+    header.trim_start_matches("W/")
+        .trim_matches('"')
+        .parse::<i64>()
+        .map(Some)
+        .map_err(|_| format!("Invalid If-Match header: {}", header))
+}
+
+// Documents stored before versions existed have none and count as version 0
+pub fn version_filter(version: i64) -> Bson {
+    match version {
+        0 => Bson::Document(doc! {"$in": [0, Bson::Null]}),
+        _ => Bson::Int64(version),
+    }
+}
+
+// 412 when the update lost the race, 400 otherwise
+pub fn error_response(e: String) -> HttpResponse {
+    match e.as_str() {
+        VERSION_MISMATCH => HttpResponse::PreconditionFailed().body(e),
+        _ => HttpResponse::BadRequest().body(e),
+    }
+}
@@ -1,2 +1,8 @@
 pub(crate) mod external_services;
 pub(crate) mod custom_visitors;
+pub(crate) mod pagination;
+pub(crate) mod commands;
+pub(crate) mod client_ip;
+pub(crate) mod timezone;
+pub(crate) mod websocket;
+pub(crate) mod etag;
@@ -0,0 +1,119 @@
+use serde::{Serialize, de::DeserializeOwned};
+use mongodb::Collection;
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::options::{FindOptions, CountOptions};
+use futures::stream::StreamExt;
+
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+// Envelope returned by every paginated listing
+#[derive(Serialize, Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub total: i64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PageRequest {
+    pub skip: i64,
+    pub limit: i64,
+}
+
+impl PageRequest {
+    pub fn new(cursor: Option<String>, limit: Option<i64>) -> Result<PageRequest, String> {
+        let skip = match cursor {
+            Some(c) => decode_cursor(c.as_str())?,
+            None => 0,
+        };
+        let limit = match limit {
+            Some(l) if l < 1 => return Err("limit must be greater than zero".to_string()),
+            Some(l) => l.min(MAX_PAGE_SIZE),
+            None => DEFAULT_PAGE_SIZE,
+        };
+
+        Ok(PageRequest { skip, limit })
+    }
+
+    // Cursor pointing to the page after this one, if there is one
+    pub fn next_cursor(&self, returned: usize, total: i64) -> Option<String> {
+        let next_skip = self.skip + returned as i64;
+        if returned as i64 == self.limit && next_skip < total {
+            Some(encode_cursor(next_skip))
+        } else {
+            None
+        }
+    }
+}
+
+// Cursors are opaque to clients, they only have to send back what they got
+pub fn encode_cursor(skip: i64) -> String {
+    base64::encode_config(format!("skip:{}", skip), base64::URL_SAFE_NO_PAD)
+}
+
+pub fn decode_cursor(cursor: &str) -> Result<i64, String> {
+    let invalid = || "Invalid cursor".to_string();
+
+    let decoded = base64::decode_config(cursor, base64::URL_SAFE_NO_PAD).map_err(|_| invalid())?;
+    let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+
+    match decoded.strip_prefix("skip:").map(|s| s.parse::<i64>()) {
+        Some(Ok(skip)) if skip >= 0 => Ok(skip),
+        _ => Err(invalid()),
+    }
+}
+
+// Run a paginated find. The count filter is kept apart because operators like $near
+// are not allowed in count_documents
+pub async fn find_page<T: DeserializeOwned>(collection: &Collection,
+                                            filter: Document,
+                                            count_filter: Document,
+                                            sort: Option<Document>,
+                                            page_request: PageRequest
+) -> Result<Page<T>, String> {
+    let total = match collection.count_documents(count_filter, CountOptions::default()).await {
+        Ok(count) => count,
+        Err(_) => return Err("Error counting document".to_string()),
+    };
+
+    let find_options = FindOptions::builder()
+        .limit(page_request.limit)
+        .skip(page_request.skip)
+        .sort(page_sort(sort, &filter))
+        .build();
+
+    let mut cursor = match collection.find(filter, find_options).await {
+        Ok(cursor) => cursor,
+        Err(_) => return Err("Error finding collection".to_string()),
+    };
+    let mut items = Vec::new();
+    let mut fetched = 0;
+    while let Some(result) = cursor.next().await {
+        fetched += 1;
+        match result {
+            Ok(document) =>
+                match bson::from_bson::<T>(bson::Bson::Document(document)) {
+                    Ok(item) => items.push(item),
+                    Err(e) => println!("{:?}", e),
+                },
+            Err(_) => println!("Error retrieving Document"),
+        }
+    }
+
+    let next_cursor = page_request.next_cursor(fetched, total);
+
+    Ok(Page { items, next_cursor, total })
+}
+
+// Skipping needs the same order on every request, $near already sorts by distance
+pub fn page_sort(sort: Option<Document>, filter: &Document) -> Option<Document> {
+    let near = filter.values()
+        .any(|value| matches!(value, Bson::Document(condition) if condition.contains_key("$near")));
+
+    match sort {
+        Some(sort) => Some(sort),
+        None if near => None,
+        None => Some(doc! {"_id": 1}),
+    }
+}
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+
+pub const DEFAULT_TIMEZONE: &str = "UTC";
+
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.parse::<Tz>().map_err(|_| format!("Unknown timezone: {}", name))
+}
+
+// Instant of a wall clock time. Times skipped by a DST change move forward by the gap,
+// repeated ones take the first occurrence
+pub fn localize(local: NaiveDateTime, tz: Tz) -> DateTime<Utc> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(date) | LocalResult::Ambiguous(date, _) => date.with_timezone(&Utc),
+        LocalResult::None => {
+            let offset = tz.offset_from_utc_datetime(&local).fix();
+            Utc.from_utc_datetime(&(local - Duration::seconds(offset.local_minus_utc() as i64)))
+        },
+    }
+}
+
+pub fn local_datetime(date: &DateTime<Utc>, tz: Tz) -> NaiveDateTime {
+    date.with_timezone(&tz).naive_local()
+}
+
+pub fn local_date(date: &DateTime<Utc>, tz: Tz) -> NaiveDate {
+    local_datetime(date, tz).date()
+}
+
+pub fn parse_rfc3339(date: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(date)
+        .map(|parsed| parsed.with_timezone(&Utc))
+        .map_err(|_| format!("Invalid date, expecting RFC 3339: {}", date))
+}
@@ -0,0 +1,89 @@
+use actix_codec::{Decoder, Encoder};
+use actix_http::ws::{self, Codec, Frame, Message};
+use actix_web::error::ErrorInternalServerError;
+use actix_web::web::BytesMut;
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use futures::channel::mpsc::{self, UnboundedReceiver};
+use futures::future;
+use futures::stream::{self, StreamExt};
+
+// Browsers cannot set headers on a WebSocket, they send the token as a second protocol:
+// new WebSocket(url, ["access_token", token]). Unlike the query, it stays out of access logs
+pub const TOKEN_PROTOCOL: &str = "access_token";
+
+// Upgrades the request and writes every string received on outgoing as a text frame.
+// Pings are answered, other client frames are ignored. on_close runs once the client
+// goes away, the socket closes when outgoing ends
+pub fn start<F>(req: &HttpRequest,
+                mut payload: web::Payload,
+                outgoing: UnboundedReceiver<String>,
+                on_close: F
+) -> Result<HttpResponse, Error>
+    where
+        F: FnOnce() + 'static,
+{
+    let mut response = ws::handshake(req.head())?;
+    // Browsers drop the connection unless the protocol they offered is accepted
+    if protocol_token(req).is_some() {
+        response.header("Sec-WebSocket-Protocol", TOKEN_PROTOCOL);
+    }
+    let (control, control_receiver) = mpsc::unbounded::<Message>();
+
+    actix_rt::spawn(async move {
+        let mut codec = Codec::new();
+        let mut buffer = BytesMut::new();
+        'read: while let Some(Ok(chunk)) = payload.next().await {
+            buffer.extend_from_slice(&chunk);
+            loop {
+                match codec.decode(&mut buffer) {
+                    Ok(Some(Frame::Ping(message))) => {
+                        let _ = control.unbounded_send(Message::Pong(message));
+                    },
+                    Ok(Some(Frame::Close(reason))) => {
+                        let _ = control.unbounded_send(Message::Close(reason));
+                        break 'read;
+                    },
+                    Ok(Some(_)) => (),
+                    Ok(None) => break,
+                    Err(_) => break 'read,
+                }
+            }
+        }
+        on_close();
+    });
+
+    let mut codec = Codec::new();
+    let outgoing = outgoing.map(Message::Text)
+        .chain(stream::once(future::ready(Message::Close(None))));
+    let frames = stream::select(outgoing, control_receiver)
+        // Nothing is written after a close frame
+        .scan(false, |closed, message| {
+            if *closed {
+                return future::ready(None);
+            }
+            *closed = matches!(message, Message::Close(_));
+            future::ready(Some(message))
+        })
+        .map(move |message| {
+            let mut frame = BytesMut::new();
+            codec.encode(message, &mut frame)
+                .map(|_| frame.freeze())
+                .map_err(ErrorInternalServerError)
+        });
+
+    Ok(response.streaming(frames))
+}
+
+pub fn protocol_token(req: &HttpRequest) -> Option<String> {
+    req.headers().get("Sec-WebSocket-Protocol")
+        .and_then(|header| header.to_str().ok())
+        .and_then(parse_protocol_token)
+}
+
+pub fn parse_protocol_token(header: &str) -> Option<String> {
+    let mut protocols = header.split(',').map(|protocol| protocol.trim());
+    match (protocols.next(), protocols.next()) {
+        (Some(TOKEN_PROTOCOL), Some(token)) if !token.is_empty() => Some(token.to_string()),
+        _ => None,
+    }
+}